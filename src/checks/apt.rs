@@ -1,11 +1,9 @@
 extern crate getopts;
-extern crate libc;
 
 use std::error;
 use std::error::Error;
-use std::fs;
 use std::io;
-use std::os::unix::fs::MetadataExt;
+use std::path::Path;
 use std::time;
 
 use logic::*;
@@ -116,17 +114,25 @@ check! {
 
 	},
 
-	perform = |self, plugin_provider, check_result_builder| {
+	perform = |self, plugin_provider, environment, check_result_builder| {
+
+		environment.enter_phase (
+			"checking root filesystem");
 
 		let root_filesystem_exists =
 			self.check_root_filesystem (
+				environment,
 				& mut check_result_builder,
 			) ?;
 
 		if root_filesystem_exists {
 
+			environment.enter_phase (
+				"checking last update");
+
 			self.check_elapsed_hours (
 				plugin_provider,
+				environment,
 				& mut check_result_builder,
 			).unwrap_or_else (
 				|error|
@@ -138,8 +144,12 @@ check! {
 
 			);
 
+			environment.enter_phase (
+				"checking reboot recommendation");
+
 			self.check_reboot_recommendation (
 				plugin_provider,
+				environment,
 				& mut check_result_builder,
 			).unwrap_or_else (
 				|error|
@@ -151,8 +161,12 @@ check! {
 
 			);
 
+			environment.enter_phase (
+				"checking package upgrades");
+
 			self.check_package_upgrades (
 				plugin_provider,
+				environment,
 				& mut check_result_builder,
 			).unwrap_or_else (
 				|error|
@@ -164,8 +178,12 @@ check! {
 
 			);
 
+			environment.enter_phase (
+				"checking apt cache");
+
 			self.check_apt_cache (
 				plugin_provider,
+				environment,
 				& mut check_result_builder,
 			).unwrap_or_else (
 				|error|
@@ -187,11 +205,12 @@ impl CheckAptInstance {
 
 	fn check_root_filesystem (
 		& self,
+		environment: & Environment,
 		check_result_builder: & mut CheckResultBuilder,
 	) -> Result <bool, Box <error::Error>> {
 
-		match fs::metadata (
-			& self.root_filesystem_path) {
+		match environment.file_metadata (
+			Path::new (& self.root_filesystem_path)) {
 
 			Ok (_metadata) =>
 				Ok (true),
@@ -215,6 +234,7 @@ impl CheckAptInstance {
 	fn check_elapsed_hours (
 		& self,
 		_plugin_provider: & PluginProvider,
+		environment: & Environment,
 		check_result_builder: & mut CheckResultBuilder,
 	) -> Result <(), Box <error::Error>> {
 
@@ -224,6 +244,7 @@ impl CheckAptInstance {
 				self.root_filesystem_prefix);
 
 		match file_age_if_exists (
+			environment,
 			update_success_stamp_path.as_str (),
 		) ? {
 
@@ -306,6 +327,7 @@ impl CheckAptInstance {
 	fn check_reboot_recommendation (
 		& self,
 		_plugin_provider: & PluginProvider,
+		environment: & Environment,
 		check_result_builder: & mut CheckResultBuilder,
 	) -> Result <(), Box <error::Error>> {
 
@@ -315,6 +337,7 @@ impl CheckAptInstance {
 				self.root_filesystem_prefix);
 
 		match file_age_if_exists (
+			environment,
 			reboot_required_path.as_str (),
 		) ? {
 
@@ -374,23 +397,14 @@ impl CheckAptInstance {
 	fn check_package_upgrades (
 		& self,
 		_plugin_provider: & PluginProvider,
+		environment: & Environment,
 		check_result_builder: & mut CheckResultBuilder,
 	) -> Result <(), Box <error::Error>> {
 
-		aptc::configuration_set_string (
-			"Dir",
-			& self.root_filesystem_path,
-		) ?;
-
-		aptc::configuration_set_string (
-			"Dir::State::Status",
-			format! (
-				"{}/var/lib/dpkg/status",
-				self.root_filesystem_path),
-		) ?;
-
 		let summary =
-			aptc::upgrade_summary_get () ?;
+			environment.apt_upgrade_summary (
+				Path::new (& self.root_filesystem_path),
+			) ?;
 
 		let total =
 			summary.upgrade +
@@ -453,6 +467,25 @@ impl CheckAptInstance {
 
 		}
 
+		for (label, count) in vec! [
+			("upgrade", summary.upgrade),
+			("remove", summary.remove),
+			("install", summary.install),
+			("broken", summary.broken),
+			("bad", summary.bad),
+		] {
+
+			check_result_builder.performance_data (
+				PerformanceData::new (
+					label,
+					count as f64,
+					PerformanceDataUnit::None,
+				).minimum (
+					0.0,
+				));
+
+		}
+
 		// TODO list packages to upgrade
 		// TODO show security updates
 
@@ -463,6 +496,7 @@ impl CheckAptInstance {
 	fn check_apt_cache (
 		& self,
 		_plugin_provider: & PluginProvider,
+		environment: & Environment,
 		check_result_builder: & mut CheckResultBuilder,
 	) -> Result <(), Box <error::Error>> {
 
@@ -470,6 +504,7 @@ impl CheckAptInstance {
 			vec! [];
 
 		self.check_apt_cache_directory (
+			environment,
 			check_result_builder,
 			& mut extra_files,
 			"/var/cache/apt",
@@ -481,6 +516,7 @@ impl CheckAptInstance {
 			]) ?;
 
 		self.check_apt_cache_directory (
+			environment,
 			check_result_builder,
 			& mut extra_files,
 			"/var/cache/apt/archives",
@@ -491,12 +527,24 @@ impl CheckAptInstance {
 		) ?;
 
 		self.check_apt_cache_directory (
+			environment,
 			check_result_builder,
 			& mut extra_files,
 			"/var/cache/apt/archives/partial",
 			& vec! [],
 		) ?;
 
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"cache_files",
+				extra_files.len () as f64,
+				PerformanceDataUnit::None,
+			).warning_above (
+				0.0,
+			).minimum (
+				0.0,
+			));
+
 		if ! extra_files.is_empty () {
 
 			check_result_builder.warning (
@@ -528,6 +576,7 @@ impl CheckAptInstance {
 
 	fn check_apt_cache_directory (
 		& self,
+		environment: & Environment,
 		check_result_builder: & mut CheckResultBuilder,
 		extra_files: & mut Vec <String>,
 		directory_name: & str,
@@ -540,52 +589,38 @@ impl CheckAptInstance {
 				self.root_filesystem_path,
 				directory_name);
 
-		for entry_result in
-			fs::read_dir (
-				full_directory_name,
-			) ? {
-
-			match entry_result {
+		match environment.read_directory (
+			Path::new (& full_directory_name),
+		) {
 
-				Ok (entry) => {
+			Ok (entry_file_names) => {
 
-					let entry_file_name =
-						entry.file_name ()
-							.to_string_lossy ()
-							.into_owned ();
+				for entry_file_name in entry_file_names {
 
 					if ! allowed_file_names.contains (
 						& entry_file_name) {
 
-						let entry_path =
-							entry.path ()
-								.to_string_lossy ()
-								.into_owned ();
-
-						let entry_path_from_root =
-							& entry_path [
-								self.root_filesystem_path.len () .. ];
-
 						extra_files.push (
-							entry_path_from_root.to_string ());
+							format! (
+								"{}/{}",
+								directory_name,
+								entry_file_name));
 
 					}
 
-				},
-
-				Err (error) => {
+				}
 
-					check_result_builder.unknown (
-						format! (
-							"Error reading apt cache directory: {}: {}",
-							directory_name,
-							error));
+			},
 
-					break;
+			Err (error) => {
 
-				},
+				check_result_builder.unknown (
+					format! (
+						"Error reading apt cache directory: {}: {}",
+						directory_name,
+						error));
 
-			};
+			},
 
 		}
 
@@ -596,12 +631,13 @@ impl CheckAptInstance {
 }
 
 fn file_age_if_exists (
+	environment: & Environment,
 	file_path: & str,
 ) -> Result <Option <time::Duration>, Box <error::Error>> {
 
 	let metadata =
-		match fs::metadata (
-			& file_path) {
+		match environment.file_metadata (
+			Path::new (file_path)) {
 
 		Ok (metadata) =>
 			metadata,
@@ -622,15 +658,9 @@ fn file_age_if_exists (
 
 	};
 
-	let timestamp =
-		time::UNIX_EPOCH
-		+ time::Duration::new (
-			metadata.mtime () as u64,
-			0);
-
 	let elapsed_duration =
-		time::SystemTime::now ().duration_since (
-			timestamp,
+		environment.now ().duration_since (
+			metadata.modified,
 		) ?;
 
 	Ok (Some (
@@ -638,156 +668,98 @@ fn file_age_if_exists (
 
 }
 
-mod aptc {
-
-	use std::error;
-	use std::ffi;
-	use std::ptr;
+#[ cfg (test) ]
+mod tests {
 
 	use logic::*;
-
-	use checks::apt::aptc_extern;
-
-	pub use checks::apt::aptc_extern::UpgradeSummary;
-
-	pub fn configuration_set_string <
-		NameAsStr: AsRef <str>,
-		ValueAsStr: AsRef <str>,
-	> (
-		name_as_str: NameAsStr,
-		value_as_str: ValueAsStr,
-	) -> Result <(), Box <error::Error>> {
-
-		let name =
-			ffi::CString::new (
-				name_as_str.as_ref (),
-			) ?;
-
-		let value =
-			ffi::CString::new (
-				value_as_str.as_ref (),
-			) ?;
-
-		unsafe {
-
-			aptc_extern::aptc_configuration_set_string (
-				name.as_ptr (),
-				value.as_ptr ());
-
-		}
-
-		Ok (())
-
-	}
-
-	pub fn upgrade_summary_get (
-	) -> Result <UpgradeSummary, Box <error::Error>> {
-
-		let mut summary =
-			UpgradeSummary {
-				upgrade: 0,
-				remove: 0,
-				install: 0,
-				broken: 0,
-				bad: 0,
-				reserved05: 0,
-				reserved06: 0,
-				reserved07: 0,
-				reserved08: 0,
-				reserved09: 0,
-				reserved10: 0,
-				reserved11: 0,
-				reserved12: 0,
-				reserved13: 0,
-				reserved14: 0,
-				reserved15: 0,
-			};
-
-		let success =
-			unsafe {
-				aptc_extern::aptc_upgrade_summary_get (
-					& mut summary)
-			};
-
-		if success {
-
-			Ok (summary)
-
-		} else {
-
-			let error_c_string =
-				unsafe {
-					aptc_extern::aptc_error_message ()
-				};
-
-			Err (
-				Box::new (
-					SimpleError::from (
-
-				if error_c_string == ptr::null () {
-
-					"unknown error".to_string ()
-
-				} else {
-
-					let error_c_str =
-						unsafe {
-							ffi::CStr::from_ptr (
-								error_c_string)
-						};
-
-					error_c_str.to_string_lossy ().into_owned ()
-
-				}
-
-			)))
-
-		}
+	use logic::testing::*;
+
+	#[ test ]
+	fn up_to_date () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"apt/up-to-date",
+				& [
+					"--update-warning", "24h",
+					"--update-critical", "48h",
+					"--reboot-warning", "24h",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Ok,
+			& [
+				"last update 3 hours ago",
+				"no packages need upgrading",
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& [
+				"upgrade=0;;;0",
+				"remove=0;;;0",
+				"install=0;;;0",
+				"broken=0;;;0",
+				"bad=0;;;0",
+				"cache_files=0;~:0;;0",
+			]);
 
 	}
 
-}
+	#[ test ]
+	fn neglected () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"apt/neglected",
+				& [
+					"--update-warning", "24h",
+					"--update-critical", "48h",
+					"--reboot-warning", "24h",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [
+				"last update 72 hours ago (critical is 48)",
+				"reboot recommended for 54 hours (warning is 24)",
+				"3 packages need upgrading (warning)",
+				"1 packages are broken (critical)",
+				"Apt cache contains 1 files",
+			]);
+
+		assert_eq! (
+			check_result.extra_information (),
+			& vec! [
+				"".to_string (),
+				"Extra files in APT cache:".to_string (),
+				"".to_string (),
+				"/var/cache/apt/archives/foo_1.0_amd64.deb".to_string (),
+			]);
 
-mod aptc_extern {
-
-	extern crate libc;
-
-	#[ repr (C) ]
-	pub struct UpgradeSummary {
-		pub upgrade: u64,
-		pub remove: u64,
-		pub install: u64,
-		pub broken: u64,
-		pub bad: u64,
-		pub reserved05: u64,
-		pub reserved06: u64,
-		pub reserved07: u64,
-		pub reserved08: u64,
-		pub reserved09: u64,
-		pub reserved10: u64,
-		pub reserved11: u64,
-		pub reserved12: u64,
-		pub reserved13: u64,
-		pub reserved14: u64,
-		pub reserved15: u64,
 	}
 
-	#[ link (name = "apt-pkg") ]
-	#[ link (name = "stdc++") ]
-	#[ link (name = "aptc", kind = "static") ]
-	extern "C" {
-
-		pub fn aptc_configuration_set_string (
-			name: * const libc::c_char,
-			value: * const libc::c_char,
-		);
-
-		pub fn aptc_upgrade_summary_get (
-			summary: * mut UpgradeSummary,
-		) -> bool;
-
-		pub fn aptc_error_message (
-		) -> * const libc::c_char;
+	#[ test ]
+	fn missing_root_filesystem () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"apt/up-to-date",
+				& [
+					"--root-filesystem", "/srv/missing",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [
+				"unable to see root filesystem: /srv/missing: not in fixture: /srv/missing",
+			]);
 
 	}
 
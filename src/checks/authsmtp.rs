@@ -1,12 +1,28 @@
-extern crate curl;
 extern crate xml;
 
 use getopts;
 
 use std::error;
+use std::error::Error;
 use std::time;
 
+use chrono::DateTime;
+
+use hyper::Uri;
+use hyper::header::Authorization;
+use hyper::header::Basic;
+
 use logic::*;
+use lowlevel::http::*;
+
+const DEFAULT_API_URL: & str =
+	"https://secure.authsmtp.com/restful";
+
+// usage early in the period says little about the rest of it, so we don't
+// project until this much of it has passed
+
+const MIN_PROJECTION_ELAPSED: f64 =
+	0.1;
 
 check! {
 
@@ -18,14 +34,19 @@ check! {
 
 	instance = CheckAuthsmtpInstance {
 
-		data_usage_warning: Option <f64>,
-		data_usage_critical: Option <f64>,
+		data_usage_warning: Option <check_helper::CheckRange>,
+		data_usage_critical: Option <check_helper::CheckRange>,
 
-		message_usage_warning: Option <f64>,
-		message_usage_critical: Option <f64>,
+		message_usage_warning: Option <check_helper::CheckRange>,
+		message_usage_critical: Option <check_helper::CheckRange>,
 
+		projected_usage_warning: Option <check_helper::CheckRange>,
+		projected_usage_critical: Option <check_helper::CheckRange>,
+
+		api_url: Uri,
 		api_username: String,
 		api_password: String,
+		api_timeout: time::Duration,
 
 	},
 
@@ -34,25 +55,39 @@ check! {
 		options_spec.optopt (
 			"",
 			"data-usage-warning",
-			"data usage warning level",
+			"data usage warning level, fraction or range",
 			"FRACTION");
 
 		options_spec.optopt (
 			"",
 			"data-usage-critical",
-			"data usage critical level",
+			"data usage critical level, fraction or range",
 			"FRACTION");
 
 		options_spec.optopt (
 			"",
 			"message-usage-warning",
-			"message usage warning level",
+			"message usage warning level, fraction or range",
 			"FRACTION");
 
 		options_spec.optopt (
 			"",
 			"message-usage-critical",
-			"message usage critical level",
+			"message usage critical level, fraction or range",
+			"FRACTION");
+
+		options_spec.optopt (
+			"",
+			"projected-usage-warning",
+			"projected end of period usage warning level for both messages \
+			and data, fraction or range",
+			"FRACTION");
+
+		options_spec.optopt (
+			"",
+			"projected-usage-critical",
+			"projected end of period usage critical level for both messages \
+			and data, fraction or range",
 			"FRACTION");
 
 		options_spec.reqopt (
@@ -67,6 +102,18 @@ check! {
 			"AuthSMTP API password",
 			"PASS");
 
+		options_spec.optopt (
+			"",
+			"api-url",
+			"AuthSMTP API base URL, defaults to the public API",
+			"URL");
+
+		options_spec.optopt (
+			"",
+			"api-timeout",
+			"AuthSMTP API request timeout, defaults to 30s",
+			"DURATION");
+
 	},
 
 	options_parse = |options_matches| {
@@ -74,32 +121,88 @@ check! {
 		// data usage
 
 		let data_usage_warning =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"data-usage-warning",
+				check_helper::CheckRangeBareValue::AlertAbove,
 			) ?;
 
 		let data_usage_critical =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"data-usage-critical",
+				check_helper::CheckRangeBareValue::AlertAbove,
 			) ?;
 
 		// message usage
 
 		let message_usage_warning =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"message-usage-warning",
+				check_helper::CheckRangeBareValue::AlertAbove,
 			) ?;
 
 		let message_usage_critical =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"message-usage-critical",
+				check_helper::CheckRangeBareValue::AlertAbove,
+			) ?;
+
+		// projected usage
+
+		let projected_usage_warning =
+			arg_helper::parse_check_range (
+				options_matches,
+				"projected-usage-warning",
+				check_helper::CheckRangeBareValue::AlertAbove,
+			) ?;
+
+		let projected_usage_critical =
+			arg_helper::parse_check_range (
+				options_matches,
+				"projected-usage-critical",
+				check_helper::CheckRangeBareValue::AlertAbove,
+			) ?;
+
+		// api
+
+		let api_url_string =
+			arg_helper::parse_string_or_default (
+				options_matches,
+				"api-url",
+				DEFAULT_API_URL,
+			) ?;
+
+		let api_url: Uri =
+			api_url_string.parse ().map_err (
+				|_|
+				format! (
+					"invalid url for --api-url: {}",
+					api_url_string),
 			) ?;
 
-		// api credentials
+		match (api_url.scheme (), api_url.host ()) {
+
+			(Some ("http"), Some (_)) | (Some ("https"), Some (_)) =>
+				(),
+
+			_ =>
+				return Err (Box::new (
+					SimpleError::from (
+						format! (
+							"invalid url for --api-url: {}",
+							api_url_string)))),
+
+		}
+
+		let api_timeout =
+			arg_helper::parse_duration_or_default (
+				options_matches,
+				"api-timeout",
+				& time::Duration::new (30, 0),
+			) ?;
 
 		let api_username =
 			options_matches.opt_str (
@@ -121,43 +224,72 @@ check! {
 			message_usage_warning: message_usage_warning,
 			message_usage_critical: message_usage_critical,
 
+			projected_usage_warning: projected_usage_warning,
+			projected_usage_critical: projected_usage_critical,
+
+			api_url: api_url,
 			api_username: api_username,
 			api_password: api_password,
+			api_timeout: api_timeout,
 
 		}
 
 	},
 
-	perform = |self, plugin_provider, check_result_builder| {
+	perform = |self, plugin_provider, environment, check_result_builder| {
 
-		let (result_string, result_element) =
-			self.make_api_call () ?;
+		environment.enter_phase (
+			& format! (
+				"calling the AuthSMTP API at {}",
+				self.api_url));
 
-		let basic_user_result_option =
-			self.interpret_result (
-				& mut check_result_builder,
-				& result_element,
-			) ?;
+		match self.make_api_call () {
 
-		if basic_user_result_option.is_some () {
+			Ok ((result_string, result_element)) => {
 
-			let basic_user_result =
-				basic_user_result_option.unwrap ();
+				let basic_user_result_option =
+					self.interpret_result (
+						& mut check_result_builder,
+						& result_element,
+					) ?;
 
-			self.check_messages_result (
-				& mut check_result_builder,
-				& basic_user_result,
-			) ?;
+				if basic_user_result_option.is_some () {
 
-			self.check_data_result (
-				& mut check_result_builder,
-				& basic_user_result,
-			) ?;
+					let basic_user_result =
+						basic_user_result_option.unwrap ();
 
-		}
+					self.check_messages_result (
+						& mut check_result_builder,
+						& basic_user_result,
+					) ?;
 
-		check_result_builder.extra_information (
-			result_string);
+					self.check_data_result (
+						& mut check_result_builder,
+						& basic_user_result,
+					) ?;
+
+					if self.projection_enabled () {
+
+						self.check_projected_usage (
+							& mut check_result_builder,
+							environment.now (),
+							& basic_user_result,
+						) ?;
+
+					}
+
+				}
+
+				check_result_builder.extra_information (
+					result_string);
+
+			},
+
+			Err (api_error) =>
+				check_result_builder.unknown (
+					api_error.message ()),
+
+		}
 
 	},
 
@@ -182,7 +314,7 @@ macro_rules! xml_child_element {
 
 				$check_result_builder.unknown (
 					format! (
-						"can't find <{}> element in <{}>",
+						"malformed response: can't find <{}> element in <{}>",
 						$child_element_name,
 						$parent_element.name));
 
@@ -224,7 +356,8 @@ macro_rules! xml_child_element_u64 {
 
 					$check_result_builder.unknown (
 						format! (
-							"unable to interpret numerical value for <{}>",
+							"malformed response: unable to interpret numerical \
+							value for <{}>",
 							$child_element_name));
 
 					return Ok (None);
@@ -244,122 +377,161 @@ struct BasicUserResult {
 	data_limit: u64,
 	messages_sent: u64,
 	data_sent: u64,
+	period: Result <(time::SystemTime, time::SystemTime), String>,
 }
 
 impl CheckAuthsmtpInstance {
 
 	fn make_api_call (
 		& self,
-	) -> Result <(String, xml::Element), Box <error::Error>> {
-
-		// set up the http call
-
-		let mut curl_easy =
-			curl::easy::Easy::new ();
-
-		curl_easy.url (
-			& format! (
-				"https://secure.authsmtp.com/restful/basic_user/{}",
-				self.api_username),
-			) ?;
+	) -> Result <(String, xml::Element), ApiError> {
 
-		curl_easy.connect_timeout (
-			time::Duration::from_secs (3),
-		) ?;
+		let secure =
+			self.api_url.scheme () == Some ("https");
 
-		curl_easy.follow_location (
-			true,
-		) ?;
+		let host =
+			self.api_url.host ().unwrap_or ("").to_string ();
 
-		curl_easy.username (
-			self.api_username.as_str (),
-		) ?;
-
-		curl_easy.password (
-			self.api_password.as_str (),
-		) ?;
+		let path =
+			format! (
+				"{}/basic_user/{}",
+				self.api_url.path ().trim_right_matches ('/'),
+				self.api_username);
 
-		curl_easy.get (
-			true,
-		) ?;
+		let headers =
+			vec! [
+				("Accept".to_string (), "application/xml".to_string ()),
+				(
+					"Authorization".to_string (),
+					Authorization (
+						Basic {
+							username: self.api_username.to_string (),
+							password: Some (self.api_password.to_string ()),
+						},
+					).to_string (),
+				),
+			];
 
 		// make the call
 
-		let mut response_buffer: Vec <u8> =
-			vec! [];
-
-		{
-
-			let mut curl_transfer =
-				curl_easy.transfer ();
-
-			curl_transfer.write_function (
-				|data| {
+		let http_response =
+			http_simple_perform (
+				& HttpSimpleRequest {
+
+				address: & host,
+				hostname: & host,
+
+				port: self.api_url.port ().map (
+					|port| port as u64,
+				).unwrap_or (
+					if secure { 443 } else { 80 },
+				),
+
+				secure: secure,
+				connect_to: None,
+				tls_options: & HttpTlsOptions::default (),
+
+				method: HttpMethod::Get,
+				path: & path,
+				headers: & headers,
+				body: & [],
+
+				// an unreachable api should fail fast, as it did with curl
+
+				connect_timeout: Some (time::Duration::from_secs (3)),
+				timeout: self.api_timeout,
+
+			}).map_err (
+				|error|
+				match error {
+					HttpError::Timeout =>
+						ApiError::Connection (
+							"timed out".to_string ()),
+					other_error =>
+						ApiError::Connection (
+							other_error.description ().to_string ()),
+				}
+			) ?;
 
-				response_buffer.extend_from_slice (
-					data);
+		// the body carries the error details when there are any, so we parse
+		// it before looking at the status
 
-				Ok (data.len ())
+		let response_body =
+			String::from_utf8 (
+				http_response.body_bytes ().to_vec (),
+			).map_err (
+				|_| "response is not valid UTF-8".to_string (),
+			);
 
-			}) ?;
+		let response_element =
+			response_body.clone ().and_then (
+				|response_body| parse_xml (& response_body));
 
-			curl_transfer.perform () ?;
+		match http_response.status_code () {
 
-		}
+			200 =>
+				(),
 
-		let response_code =
-			curl_easy.response_code () ?;
+			401 | 403 =>
+				return Err (
+					ApiError::Authentication (
+						format! (
+							"{} {}",
+							http_response.status_code (),
+							http_response.status_message ()))),
 
-		if response_code != 200 {
+			// redirects aren't followed, since that would send the credentials
+			// on to whatever host is named. the api doesn't redirect, so one
+			// most likely means that --api-url is wrong.
 
-			return Err (
-				Box::new (
-					SimpleError::from (
+			301 | 302 | 303 | 307 | 308 =>
+				return Err (
+					ApiError::Api (
 						format! (
-							"server returned {}",
-							response_code,
-						).to_string ())));
+							"redirected to {}, check --api-url",
+							http_response.headers ().iter ().find (
+								|& & (ref name, _)|
+								name.to_lowercase () == "location"
+							).map (
+								|& (_, ref value)| value.as_str ()
+							).unwrap_or ("unknown location")))),
+
+			_ =>
+				return Err (
+					ApiError::Api (
+						response_element.ok ().and_then (
+							|response_element|
+							error_message (
+								& response_element)
+						).unwrap_or (
+							format! (
+								"server returned {} {}",
+								http_response.status_code (),
+								http_response.status_message ())))),
 
 		}
 
-		// parse the response body
-
-		let mut xml_parser =
-			xml::Parser::new ();
-
 		let response_body =
-			String::from_utf8 (
-				response_buffer,
+			response_body.map_err (
+				ApiError::Malformed,
 			) ?;
 
-		xml_parser.feed_str (
-			response_body.as_str ());
-
-		let mut xml_builder =
-			xml::ElementBuilder::new ();
-
-		for xml_result in xml_parser.filter_map (
-			|xml_event|
+		let response_element =
+			response_element.map_err (
+				ApiError::Malformed,
+			) ?;
 
-			xml_builder.handle_event (
-				xml_event)) {
+		if let Some (error_message) =
+			error_message (
+				& response_element) {
 
-			return Ok (
-				(
-					response_body,
-					xml_result ?,
-				)
-			);
+			return Err (
+				ApiError::Api (
+					error_message));
 
 		}
 
-		Err (
-			Box::new (
-				SimpleError::from (
-					format! (
-						"don't understand server response: {}",
-						response_body,
-					).to_string ())))
+		Ok ((response_body, response_element))
 
 	}
 
@@ -373,7 +545,7 @@ impl CheckAuthsmtpInstance {
 
 			check_result_builder.unknown (
 				format! (
-					"got element <{}>, expected <result>",
+					"malformed response: got element <{}>, expected <result>",
 					result_element.name));
 
 			return Ok (None);
@@ -414,6 +586,14 @@ impl CheckAuthsmtpInstance {
 						basic_user_element,
 						"data_sent"),
 
+				// the billing period is only needed when we are projecting
+				// usage, so a problem with it is left for the projection to
+				// report
+
+				period:
+					basic_user_period (
+						basic_user_element),
+
 			}
 
 		))
@@ -430,113 +610,760 @@ impl CheckAuthsmtpInstance {
 			basic_user_result.messages_sent as f64
 				/ basic_user_result.messages_limit as f64;
 
-		if
-
-			self.message_usage_critical.is_some ()
+		check_helper::check_ratio_in_range (
+			check_result_builder,
+			& self.message_usage_warning,
+			& self.message_usage_critical,
+			& format! (
+				"messages {} of {}",
+				basic_user_result.messages_sent,
+				basic_user_result.messages_limit),
+			message_usage_value,
+		) ?;
 
-			&& message_usage_value
-				> self.message_usage_critical.unwrap ()
+		let messages_limit =
+			basic_user_result.messages_limit as f64;
+
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"messages",
+				basic_user_result.messages_sent as f64,
+				PerformanceDataUnit::None,
+			).warning_range (
+				self.message_usage_warning.map (
+					|range| range.scale (messages_limit)),
+			).critical_range (
+				self.message_usage_critical.map (
+					|range| range.scale (messages_limit)),
+			).minimum (
+				0.0,
+			).maximum (
+				messages_limit,
+			));
 
-		{
-
-			check_result_builder.critical (
-				format! (
-					"messages {} of {} or {}% (critical is {}%)",
-					basic_user_result.messages_sent,
-					basic_user_result.messages_limit,
-					(message_usage_value * 100.0) as u64,
-					(self.message_usage_critical.unwrap () * 100.0) as u64));
+		Ok (())
 
-		} else if
+	}
 
-			self.message_usage_warning.is_some ()
+	fn check_data_result (
+		& self,
+		check_result_builder: & mut CheckResultBuilder,
+		basic_user_result: & BasicUserResult,
+	) -> Result <(), Box <error::Error>> {
 
-			&& message_usage_value
-				> self.message_usage_warning.unwrap ()
+		let data_usage_value =
+			basic_user_result.data_sent as f64
+				/ basic_user_result.data_limit as f64;
 
-		{
+		check_helper::check_ratio_in_range (
+			check_result_builder,
+			& self.data_usage_warning,
+			& self.data_usage_critical,
+			& format! (
+				"data {} of {} mb",
+				basic_user_result.data_sent / 1024 / 1024,
+				basic_user_result.data_limit / 1024 / 1024),
+			data_usage_value,
+		) ?;
 
-			check_result_builder.warning (
-				format! (
-					"messages {} of {} or {}% (warning is {}%)",
-					basic_user_result.messages_sent,
-					basic_user_result.messages_limit,
-					(message_usage_value * 100.0) as u64,
-					(self.message_usage_warning.unwrap () * 100.0) as u64));
+		let data_limit =
+			basic_user_result.data_limit as f64;
+
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"data",
+				basic_user_result.data_sent as f64,
+				PerformanceDataUnit::Bytes,
+			).warning_range (
+				self.data_usage_warning.map (
+					|range| range.scale (data_limit)),
+			).critical_range (
+				self.data_usage_critical.map (
+					|range| range.scale (data_limit)),
+			).minimum (
+				0.0,
+			).maximum (
+				data_limit,
+			));
 
-		} else {
+		Ok (())
 
-			check_result_builder.ok (
-				format! (
-					"messages {} of {} or {}%",
-					basic_user_result.messages_sent,
-					basic_user_result.messages_limit,
-					(message_usage_value * 100.0) as u64));
+	}
 
-		}
+	fn projection_enabled (
+		& self,
+	) -> bool {
 
-		Ok (())
+		self.projected_usage_warning.is_some ()
+			|| self.projected_usage_critical.is_some ()
 
 	}
 
-	fn check_data_result (
+	// projects usage to the end of the billing period, assuming the rate so
+	// far continues
+
+	fn check_projected_usage (
 		& self,
 		check_result_builder: & mut CheckResultBuilder,
+		now: time::SystemTime,
 		basic_user_result: & BasicUserResult,
 	) -> Result <(), Box <error::Error>> {
 
-		let data_usage_value =
-			basic_user_result.data_sent as f64
-				/ basic_user_result.data_limit as f64;
+		let (period_start, period_end) =
+			match basic_user_result.period {
 
-		if
+			Ok (period) =>
+				period,
 
-			self.data_usage_critical.is_some ()
+			Err (ref reason) => {
 
-			&& data_usage_value
-				> self.data_usage_critical.unwrap ()
+				check_result_builder.unknown (
+					format! (
+						"unable to project usage: {}",
+						reason));
 
-		{
+				return Ok (());
 
-			check_result_builder.critical (
-				format! (
-					"data {} of {} mb or {}% (critical is {}%)",
-					basic_user_result.data_sent / 1024 / 1024,
-					basic_user_result.data_limit / 1024 / 1024,
-					(data_usage_value * 100.0) as u64,
-					(self.data_usage_critical.unwrap () * 100.0) as u64));
+			},
 
-		} else if
+		};
 
-			self.data_usage_warning.is_some ()
+		let period_length =
+			match period_end.duration_since (period_start) {
 
-			&& data_usage_value
-				> self.data_usage_warning.unwrap ()
+			Ok (period_length) if period_length > time::Duration::new (0, 0) =>
+				period_length,
 
-		{
+			_ => {
 
-			check_result_builder.warning (
-				format! (
-					"data {} of {} mb or {}% (warning is {}%)",
-					basic_user_result.data_sent / 1024 / 1024,
-					basic_user_result.data_limit / 1024 / 1024,
-					(data_usage_value * 100.0) as u64,
-					(self.data_usage_warning.unwrap () * 100.0) as u64));
+				check_result_builder.unknown (
+					"malformed response: billing period ends before it starts");
+
+				return Ok (());
+
+			},
+
+		};
+
+		let period_elapsed =
+			match now.duration_since (period_start) {
 
-		} else {
+			Ok (period_elapsed) if period_elapsed < period_length =>
+				period_elapsed,
+
+			_ => {
+
+				check_result_builder.unknown (
+					"current time is outside the billing period");
+
+				return Ok (());
+
+			},
+
+		};
+
+		let elapsed_fraction =
+			check_helper::duration_as_seconds (& period_elapsed)
+				/ check_helper::duration_as_seconds (& period_length);
+
+		check_result_builder.extra_information (
+			format! (
+				"billing period {}% elapsed, ends in {}",
+				(elapsed_fraction * 100.0) as u64,
+				check_helper::display_duration_short (
+					& (period_length - period_elapsed))));
+
+		if elapsed_fraction < MIN_PROJECTION_ELAPSED {
 
 			check_result_builder.ok (
-				format! (
-					"data {} of {} mb or {}%",
-					basic_user_result.data_sent / 1024 / 1024,
-					basic_user_result.data_limit / 1024 / 1024,
-					(data_usage_value * 100.0) as u64));
+				"too early in billing period to project usage");
+
+			return Ok (());
 
 		}
 
+		// messages
+
+		let messages_projected =
+			basic_user_result.messages_sent as f64 / elapsed_fraction;
+
+		let messages_limit =
+			basic_user_result.messages_limit as f64;
+
+		check_helper::check_ratio_in_range (
+			check_result_builder,
+			& self.projected_usage_warning,
+			& self.projected_usage_critical,
+			& format! (
+				"messages projected {} of {}",
+				messages_projected.round () as u64,
+				basic_user_result.messages_limit),
+			messages_projected / messages_limit,
+		) ?;
+
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"messages_projected",
+				messages_projected.round (),
+				PerformanceDataUnit::None,
+			).warning_range (
+				self.projected_usage_warning.map (
+					|range| range.scale (messages_limit)),
+			).critical_range (
+				self.projected_usage_critical.map (
+					|range| range.scale (messages_limit)),
+			).minimum (
+				0.0,
+			).maximum (
+				messages_limit,
+			));
+
+		// data
+
+		let data_projected =
+			basic_user_result.data_sent as f64 / elapsed_fraction;
+
+		let data_limit =
+			basic_user_result.data_limit as f64;
+
+		check_helper::check_ratio_in_range (
+			check_result_builder,
+			& self.projected_usage_warning,
+			& self.projected_usage_critical,
+			& format! (
+				"data projected {} of {} mb",
+				data_projected.round () as u64 / 1024 / 1024,
+				basic_user_result.data_limit / 1024 / 1024),
+			data_projected / data_limit,
+		) ?;
+
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"data_projected",
+				data_projected.round (),
+				PerformanceDataUnit::Bytes,
+			).warning_range (
+				self.projected_usage_warning.map (
+					|range| range.scale (data_limit)),
+			).critical_range (
+				self.projected_usage_critical.map (
+					|range| range.scale (data_limit)),
+			).minimum (
+				0.0,
+			).maximum (
+				data_limit,
+			));
+
 		Ok (())
 
 	}
+}
+
+// ---------- api errors
+
+enum ApiError {
+	Connection (String),
+	Authentication (String),
+	Api (String),
+	Malformed (String),
+}
+
+impl ApiError {
+
+	fn message (
+		& self,
+	) -> String {
+
+		match * self {
+
+			ApiError::Connection (ref reason) =>
+				format! (
+					"unable to contact api: {}",
+					reason),
+
+			ApiError::Authentication (ref reason) =>
+				format! (
+					"authentication failed: {}",
+					reason),
+
+			ApiError::Api (ref reason) =>
+				format! (
+					"api error: {}",
+					reason),
+
+			ApiError::Malformed (ref reason) =>
+				format! (
+					"malformed response: {}",
+					reason),
+
+		}
+
+	}
+
+}
+
+fn basic_user_period (
+	basic_user_element: & xml::Element,
+) -> Result <(time::SystemTime, time::SystemTime), String> {
+
+	Ok ((
+		xml_child_timestamp (
+			basic_user_element,
+			"period_start",
+		) ?,
+		xml_child_timestamp (
+			basic_user_element,
+			"period_end",
+		) ?,
+	))
+
+}
+
+fn xml_child_timestamp (
+	parent_element: & xml::Element,
+	child_element_name: & str,
+) -> Result <time::SystemTime, String> {
+
+	let child_element =
+		parent_element.get_child (
+			child_element_name,
+			None,
+		).ok_or_else (
+			||
+			format! (
+				"can't find <{}> element in <{}>",
+				child_element_name,
+				parent_element.name),
+		) ?;
+
+	match DateTime::parse_from_rfc3339 (
+		child_element.content_str ().trim ()) {
+
+		Ok (value) if value.timestamp () >= 0 =>
+			Ok (
+				time::UNIX_EPOCH
+					+ time::Duration::new (
+						value.timestamp () as u64,
+						0)),
+
+		_ =>
+			Err (
+				format! (
+					"unable to interpret timestamp for <{}>",
+					child_element_name)),
+
+	}
+
+}
+
+fn parse_xml (
+	response_body: & str,
+) -> Result <xml::Element, String> {
+
+	let mut xml_parser =
+		xml::Parser::new ();
+
+	xml_parser.feed_str (
+		response_body);
+
+	let mut xml_builder =
+		xml::ElementBuilder::new ();
+
+	for xml_result in xml_parser.filter_map (
+		|xml_event|
+
+		xml_builder.handle_event (
+			xml_event)) {
+
+		return xml_result.map_err (
+			|error| error.to_string ());
+
+	}
+
+	Err (
+		format! (
+			"no xml element in {:?}",
+			response_body))
+
+}
+
+// errors come back either as the whole document or inside the result
+
+fn error_message (
+	response_element: & xml::Element,
+) -> Option <String> {
+
+	if response_element.name == "error" {
+
+		return Some (
+			response_element.content_str ().trim ().to_string ());
+
+	}
+
+	response_element.get_child (
+		"error",
+		None,
+	).map (
+		|error_element|
+		error_element.content_str ().trim ().to_string ()
+	)
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::sync::mpsc;
+
+	use logic::*;
+	use logic::testing::*;
+
+	const XML_HEADERS: & 'static [(& 'static str, & 'static str)] =
+		& [ ("Content-Type", "application/xml") ];
+
+	const REDIRECT_HEADERS: & 'static [(& 'static str, & 'static str)] =
+		& [ ("Location", "https://api.example.com/restful/basic_user/example") ];
+
+	// the authsmtp api answers a single request on a local port
+
+	fn start_stand_in_api (
+		response_status: & 'static str,
+		response_body: & 'static str,
+	) -> (String, mpsc::Receiver <StandInRequest>) {
+
+		let (port, receiver) =
+			start_stand_in_http_server (
+				response_status,
+				XML_HEADERS,
+				response_body);
+
+		(
+			format! (
+				"http://127.0.0.1:{}/restful",
+				port),
+			receiver,
+		)
+
+	}
+
+	#[ test ]
+	fn usage_within_limits () {
+
+		let (api_url, receiver) =
+			start_stand_in_api (
+				"200 OK",
+				"<result><basic_user>\
+				<messages_limit>10000</messages_limit>\
+				<data_limit>2147483648</data_limit>\
+				<messages_sent>2500</messages_sent>\
+				<data_sent>536870912</data_sent>\
+				</basic_user></result>");
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--api-url", & api_url,
+					"--api-username", "example",
+					"--api-password", "secret",
+					"--message-usage-warning", "0.8",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Ok,
+			& [
+				"messages 2500 of 10000 or 25%",
+				"data 512 of 2048 mb or 25%",
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& [
+				"messages=2500;8000;;0;10000",
+				"data=536870912B;;;0;2147483648",
+			]);
+
+		let request =
+			receiver.recv ().unwrap ();
+
+		assert_eq! (
+			request.request_line,
+			"GET /restful/basic_user/example HTTP/1.1");
+
+		assert_eq! (
+			request.header ("authorization"),
+			Some ("Basic ZXhhbXBsZTpzZWNyZXQ="));
+
+	}
+
+	#[ test ]
+	fn projected_usage () {
+
+		let (api_url, _receiver) =
+			start_stand_in_api (
+				"200 OK",
+				"<result><basic_user>\
+				<messages_limit>10000</messages_limit>\
+				<data_limit>2147483648</data_limit>\
+				<messages_sent>6000</messages_sent>\
+				<data_sent>536870912</data_sent>\
+				<period_start>2017-06-01T00:00:00Z</period_start>\
+				<period_end>2017-07-01T00:00:00Z</period_end>\
+				</basic_user></result>");
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"authsmtp/mid-period",
+				& [
+					"--api-url", & api_url,
+					"--api-username", "example",
+					"--api-password", "secret",
+					"--projected-usage-warning", "1.0",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Warning,
+			& [
+				"messages 6000 of 10000 or 60%",
+				"data 512 of 2048 mb or 25%",
+				"messages projected 12000 of 10000 or 120% (warning is above 100%)",
+				"data projected 1024 of 2048 mb or 50%",
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& [
+				"messages=6000;;;0;10000",
+				"data=536870912B;;;0;2147483648",
+				"messages_projected=12000;10000;;0;10000",
+				"data_projected=1073741824B;2147483648;;0;2147483648",
+			]);
+
+	}
+
+	#[ test ]
+	fn projection_too_early () {
+
+		let (api_url, _receiver) =
+			start_stand_in_api (
+				"200 OK",
+				"<result><basic_user>\
+				<messages_limit>10000</messages_limit>\
+				<data_limit>2147483648</data_limit>\
+				<messages_sent>600</messages_sent>\
+				<data_sent>536870912</data_sent>\
+				<period_start>2017-06-01T00:00:00Z</period_start>\
+				<period_end>2017-07-01T00:00:00Z</period_end>\
+				</basic_user></result>");
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"authsmtp/early-period",
+				& [
+					"--api-url", & api_url,
+					"--api-username", "example",
+					"--api-password", "secret",
+					"--projected-usage-warning", "1.0",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Ok,
+			& [
+				"messages 600 of 10000 or 6%",
+				"data 512 of 2048 mb or 25%",
+				"too early in billing period to project usage",
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& [
+				"messages=600;;;0;10000",
+				"data=536870912B;;;0;2147483648",
+			]);
+
+	}
+
+	#[ test ]
+	fn projection_without_period () {
+
+		let (api_url, _receiver) =
+			start_stand_in_api (
+				"200 OK",
+				"<result><basic_user>\
+				<messages_limit>10000</messages_limit>\
+				<data_limit>2147483648</data_limit>\
+				<messages_sent>6000</messages_sent>\
+				<data_sent>536870912</data_sent>\
+				<period_start>2017-06-01T00:00:00Z</period_start>\
+				</basic_user></result>");
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--api-url", & api_url,
+					"--api-username", "example",
+					"--api-password", "secret",
+					"--message-usage-warning", "0.5",
+					"--projected-usage-warning", "1.0",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Warning,
+			& [
+				"messages 6000 of 10000 or 60% (warning is above 50%)",
+				"data 512 of 2048 mb or 25%",
+				"unable to project usage: can't find <period_end> element in \
+				<basic_user>",
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& [
+				"messages=6000;5000;;0;10000",
+				"data=536870912B;;;0;2147483648",
+			]);
+
+	}
+
+	#[ test ]
+	fn authentication_failure () {
+
+		let (api_url, _receiver) =
+			start_stand_in_api (
+				"401 Unauthorized",
+				"<error>Invalid credentials</error>");
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--api-url", & api_url,
+					"--api-username", "example",
+					"--api-password", "wrong",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [ "authentication failed: 401 Unauthorized" ]);
+
+	}
+
+	#[ test ]
+	fn api_error () {
+
+		let (api_url, _receiver) =
+			start_stand_in_api (
+				"200 OK",
+				"<result><error>Account suspended</error></result>");
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--api-url", & api_url,
+					"--api-username", "example",
+					"--api-password", "secret",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [ "api error: Account suspended" ]);
+
+	}
+
+	#[ test ]
+	fn redirect_not_followed () {
+
+		let (port, _receiver) =
+			start_stand_in_http_server (
+				"301 Moved Permanently",
+				REDIRECT_HEADERS,
+				"");
+
+		let api_url =
+			format! (
+				"http://127.0.0.1:{}/restful",
+				port);
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--api-url", & api_url,
+					"--api-username", "example",
+					"--api-password", "secret",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [
+				"api error: redirected to \
+				https://api.example.com/restful/basic_user/example, \
+				check --api-url",
+			]);
+
+	}
+
+	#[ test ]
+	fn response_not_xml () {
+
+		let (api_url, _receiver) =
+			start_stand_in_api (
+				"200 OK",
+				"Service temporarily unavailable");
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--api-url", & api_url,
+					"--api-username", "example",
+					"--api-password", "secret",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [
+				"malformed response: no xml element in \
+				\"Service temporarily unavailable\"",
+			]);
+
+	}
+
+	#[ test ]
+	fn response_without_basic_user () {
+
+		let (api_url, _receiver) =
+			start_stand_in_api (
+				"200 OK",
+				"<result><status>ok</status></result>");
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--api-url", & api_url,
+					"--api-username", "example",
+					"--api-password", "secret",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [ "malformed response: can't find <basic_user> element in <result>" ]);
+
+	}
 
 }
 
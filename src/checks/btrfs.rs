@@ -19,12 +19,12 @@ check! {
 
 		path: String,
 
-		space_ratio_warning: Option <f64>,
-		space_ratio_critical: Option <f64>,
+		space_ratio_warning: Option <check_helper::CheckRange>,
+		space_ratio_critical: Option <check_helper::CheckRange>,
 		space_ratio_raid_level: Option <SpaceRatioRaidLevel>,
 
-		balance_ratio_warning: Option <f64>,
-		balance_ratio_critical: Option <f64>,
+		balance_ratio_warning: Option <check_helper::CheckRange>,
+		balance_ratio_critical: Option <check_helper::CheckRange>,
 
 	},
 
@@ -43,13 +43,13 @@ check! {
 		options_spec.optopt (
 			"",
 			"space-ratio-warning",
-			"free disk space warning threshold",
+			"free disk space warning threshold, ratio or range",
 			"RATIO");
 
 		options_spec.optopt (
 			"",
 			"space-ratio-critical",
-			"free disk space critical threshold",
+			"free disk space critical threshold, ratio or range",
 			"RATIO");
 
 		options_spec.optopt (
@@ -63,13 +63,13 @@ check! {
 		options_spec.optopt (
 			"",
 			"balance-ratio-warning",
-			"block balance warning threshold",
+			"block balance warning threshold, ratio or range",
 			"RATIO");
 
 		options_spec.optopt (
 			"",
 			"balance-ratio-critical",
-			"block balance critical threshold",
+			"block balance critical threshold, ratio or range",
 			"RATIO");
 
 	},
@@ -86,15 +86,17 @@ check! {
 		// space ratio
 
 		let space_ratio_warning =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"space-ratio-warning",
+				check_helper::CheckRangeBareValue::AlertBelow,
 			) ?;
 
 		let space_ratio_critical =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"space-ratio-critical",
+				check_helper::CheckRangeBareValue::AlertBelow,
 			) ?;
 
 		let space_ratio_raid_level =
@@ -106,15 +108,17 @@ check! {
 		// balance ratio
 
 		let balance_ratio_warning =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"balance-ratio-warning",
+				check_helper::CheckRangeBareValue::AlertAbove,
 			) ?;
 
 		let balance_ratio_critical =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"balance-ratio-critical",
+				check_helper::CheckRangeBareValue::AlertAbove,
 			) ?;
 
 		// return
@@ -134,10 +138,13 @@ check! {
 
 	},
 
-	perform = |self, plugin_provider, check_result_builder| {
+	perform = |self, plugin_provider, environment, check_result_builder| {
 
 		// open directory
 
+		environment.enter_phase (
+			"opening filesystem");
+
 		let file_descriptor =
 			lowlevel::FileDescriptor::open (
 				& self.path,
@@ -146,11 +153,17 @@ check! {
 
 		// perform checks
 
+		environment.enter_phase (
+			"reading space information");
+
 		self.check_space_ratio (
 			& mut check_result_builder,
 			file_descriptor.get_value (),
 		) ?;
 
+		environment.enter_phase (
+			"reading balance information");
+
 		self.check_balance_ratio (
 			& mut check_result_builder,
 			file_descriptor.get_value (),
@@ -272,10 +285,10 @@ impl CheckBtrfsInstance {
 			total_bytes_free as f64
 			/ total_bytes as f64;
 
-		check_helper::check_ratio_greater_than (
+		check_helper::check_ratio_in_range (
 			check_result_builder,
-			self.space_ratio_warning,
-			self.space_ratio_critical,
+			& self.space_ratio_warning,
+			& self.space_ratio_critical,
 			& format! (
 				"free space is {}",
 				check_helper::display_data_size_ratio (
@@ -284,6 +297,18 @@ impl CheckBtrfsInstance {
 			total_free_ratio,
 		) ?;
 
+		check_result_builder.performance_data (
+			ratio_performance_data (
+				"free",
+				total_free_ratio,
+			).warning_range (
+				self.space_ratio_warning.map (
+					|range| range.scale (100.0)),
+			).critical_range (
+				self.space_ratio_critical.map (
+					|range| range.scale (100.0)),
+			));
+
 		Ok (())
 
 	}
@@ -369,10 +394,10 @@ impl CheckBtrfsInstance {
 			effective_bytes_free as f64
 			/ effective_bytes as f64;
 
-		check_helper::check_ratio_greater_than (
+		check_helper::check_ratio_in_range (
 			check_result_builder,
-			self.space_ratio_warning,
-			self.space_ratio_critical,
+			& self.space_ratio_warning,
+			& self.space_ratio_critical,
 			& format! (
 				"raid1 free space is {}",
 				check_helper::display_data_size_ratio (
@@ -381,6 +406,18 @@ impl CheckBtrfsInstance {
 			effective_free_ratio,
 		) ?;
 
+		check_result_builder.performance_data (
+			ratio_performance_data (
+				"raid1_free",
+				effective_free_ratio,
+			).warning_range (
+				self.space_ratio_warning.map (
+					|range| range.scale (100.0)),
+			).critical_range (
+				self.space_ratio_critical.map (
+					|range| range.scale (100.0)),
+			));
+
 		Ok (())
 
 	}
@@ -421,10 +458,10 @@ impl CheckBtrfsInstance {
 			free_space as f64
 			/ total_space as f64;
 
-		check_helper::check_ratio_lesser_than (
+		check_helper::check_ratio_in_range (
 			check_result_builder,
-			self.balance_ratio_warning,
-			self.balance_ratio_critical,
+			& self.balance_ratio_warning,
+			& self.balance_ratio_critical,
 			& format! (
 				"block free space is {}",
 				check_helper::display_data_size_ratio (
@@ -433,10 +470,39 @@ impl CheckBtrfsInstance {
 			balance_space_ratio,
 		) ?;
 
+		check_result_builder.performance_data (
+			ratio_performance_data (
+				"block_free",
+				balance_space_ratio,
+			).warning_range (
+				self.balance_ratio_warning.map (
+					|range| range.scale (100.0)),
+			).critical_range (
+				self.balance_ratio_critical.map (
+					|range| range.scale (100.0)),
+			));
+
 		Ok (())
 
 	}
 
 }
 
+fn ratio_performance_data (
+	label: & str,
+	ratio: f64,
+) -> PerformanceData {
+
+	PerformanceData::new (
+		label,
+		ratio * 100.0,
+		PerformanceDataUnit::Percent,
+	).minimum (
+		0.0,
+	).maximum (
+		100.0,
+	)
+
+}
+
 // ex: noet ts=4 filetype=rust
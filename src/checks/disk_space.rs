@@ -1,9 +1,7 @@
 extern crate getopts;
-extern crate libc;
 
 use std::error;
-use std::ffi;
-use std::mem;
+use std::path::Path;
 
 use logic::*;
 
@@ -19,8 +17,8 @@ check! {
 
 		path: String,
 
-		space_ratio_warning: Option <f64>,
-		space_ratio_critical: Option <f64>,
+		space_ratio_warning: Option <check_helper::CheckRange>,
+		space_ratio_critical: Option <check_helper::CheckRange>,
 
 	},
 
@@ -35,13 +33,13 @@ check! {
 		options_spec.optopt (
 			"",
 			"space-ratio-warning",
-			"free disk space warning threshold",
+			"free disk space warning threshold, ratio or range",
 			"RATIO");
 
 		options_spec.optopt (
 			"",
 			"space-ratio-critical",
-			"free disk space critical threshold",
+			"free disk space critical threshold, ratio or range",
 			"RATIO");
 
 	},
@@ -58,15 +56,17 @@ check! {
 		// space ratio
 
 		let space_ratio_warning =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"space-ratio-warning",
+				check_helper::CheckRangeBareValue::AlertBelow,
 			) ?;
 
 		let space_ratio_critical =
-			arg_helper::parse_decimal_fraction (
+			arg_helper::parse_check_range (
 				options_matches,
 				"space-ratio-critical",
+				check_helper::CheckRangeBareValue::AlertBelow,
 			) ?;
 
 		// return
@@ -82,38 +82,26 @@ check! {
 
 	},
 
-	perform = |self, plugin_provider, check_result_builder| {
+	perform = |self, plugin_provider, environment, check_result_builder| {
 
-		let path_c =
-			ffi::CString::new (
-				self.path.to_owned (),
-			) ?;
-
-		let mut filesystem_stats: libc::statfs =
-			unsafe {
-				mem::zeroed ()
-			};
-
-		let statfs_result =
-			unsafe {
-				libc::statfs (
-					path_c.as_ptr (),
-					& mut filesystem_stats)
-			};
+		environment.enter_phase (
+			"reading filesystem statistics");
 
-		if statfs_result != 0 {
+		match environment.filesystem_space (
+			Path::new (& self.path),
+		) {
 
-			check_result_builder.unknown (
-				format! (
-					"statfs returned {}",
-					statfs_result));
+			Ok (filesystem_space) =>
+				self.perform_space_check (
+					& mut check_result_builder,
+					& filesystem_space,
+				) ?,
 
-		} else {
-
-			self.perform_space_check (
-				& mut check_result_builder,
-				& filesystem_stats,
-			) ?;
+			Err (error) =>
+				check_result_builder.unknown (
+					format! (
+						"statfs failed: {}",
+						error)),
 
 		}
 
@@ -126,25 +114,22 @@ impl CheckDiskSpaceInstance {
 	fn perform_space_check (
 		& self,
 		check_result_builder: & mut CheckResultBuilder,
-		filesystem_stats: & libc::statfs,
+		filesystem_space: & FilesystemSpace,
 	) -> Result <(), Box <error::Error>> {
 
-		let block_size =
-			filesystem_stats.f_bsize as u64;
-
 		let total_space =
-			filesystem_stats.f_blocks as u64 * block_size;
+			filesystem_space.total_bytes;
 
 		let available_space =
-			filesystem_stats.f_bavail as u64 * block_size;
+			filesystem_space.available_bytes;
 
 		let available_space_ratio =
 			available_space as f64 / total_space as f64;
 
-		check_helper::check_ratio_greater_than (
+		check_helper::check_ratio_in_range (
 			check_result_builder,
-			self.space_ratio_warning,
-			self.space_ratio_critical,
+			& self.space_ratio_warning,
+			& self.space_ratio_critical,
 			& format! (
 				"free space is {}",
 				check_helper::display_data_size_ratio (
@@ -153,10 +138,109 @@ impl CheckDiskSpaceInstance {
 			available_space_ratio,
 		) ?;
 
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"free",
+				available_space as f64,
+				PerformanceDataUnit::Bytes,
+			).warning_range (
+				self.space_ratio_warning.map (
+					|range| range.scale (total_space as f64)),
+			).critical_range (
+				self.space_ratio_critical.map (
+					|range| range.scale (total_space as f64)),
+			).minimum (
+				0.0,
+			).maximum (
+				total_space as f64,
+			));
+
 		Ok (())
 
 	}
 
 }
 
+#[ cfg (test) ]
+mod tests {
+
+	use logic::*;
+	use logic::testing::*;
+
+	#[ test ]
+	fn plenty_free () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"disk-space/plenty-free",
+				& [
+					"--path", "/srv",
+					"--space-ratio-warning", "0.25",
+					"--space-ratio-critical", "0.125",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Ok,
+			& [
+				"free space is 60 of 100 GiB or 60%",
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& [
+				"free=64424509440B;26843545600:;13421772800:;0;107374182400",
+			]);
+
+	}
+
+	#[ test ]
+	fn nearly_full () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"disk-space/nearly-full",
+				& [
+					"--path", "/srv",
+					"--space-ratio-warning", "0.25",
+					"--space-ratio-critical", "0.125",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [
+				"free space is 5 of 100 GiB or 5% (critical is below 12%)",
+			]);
+
+	}
+
+	#[ test ]
+	fn missing_filesystem () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"disk-space/plenty-free",
+				& [
+					"--path", "/missing",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [
+				"statfs failed: not in fixture: /missing",
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& []);
+
+	}
+
+}
+
 // ex: noet ts=4 filetype=rust
@@ -4,9 +4,15 @@ extern crate serde_json;
 
 use std::error;
 use std::error::Error;
+use std::path::Path;
 use std::time;
 
+use chrono::DateTime;
+
 use hyper::Uri;
+use hyper::header::Authorization;
+use hyper::header::Basic;
+use hyper::header::Bearer;
 
 use logic::*;
 use lowlevel::http::*;
@@ -21,24 +27,79 @@ check! {
 
 	instance = CheckGenericInstance {
 
-		target: Uri,
+		target: Option <String>,
+		file: Option <String>,
+		path: Option <String>,
+		tls_options: HttpTlsOptions,
+
+		send_headers: Vec <(String, String)>,
+		bearer_token: Option <String>,
+		username: Option <String>,
+		password: Option <String>,
 
 		request_time_warning: Option <time::Duration>,
 		request_time_critical: Option <time::Duration>,
 		request_timeout: time::Duration,
 
+		max_age_warning: Option <time::Duration>,
+		max_age_critical: Option <time::Duration>,
+
 	},
 
 	options_spec = |options_spec| {
 
 		// path
 
-		options_spec.reqopt (
+		options_spec.optopt (
 			"",
 			"target",
-			"target URL for generic check",
+			"target URL for generic check, http, https or unix:///path/to/socket",
 			"TARGET");
 
+		options_spec.optopt (
+			"",
+			"file",
+			"read the check result from this file instead of a target URL",
+			"PATH");
+
+		options_spec.optopt (
+			"",
+			"path",
+			"request path, replacing any path in the target URL, defaults to \
+			/ for unix socket targets",
+			"PATH");
+
+		// request
+
+		options_spec.optmulti (
+			"",
+			"send-header",
+			"header to send, eg 'name: value'",
+			"NAME:VALUE");
+
+		options_spec.optopt (
+			"",
+			"bearer-token",
+			"bearer token to send in the authorization header",
+			"TOKEN");
+
+		options_spec.optopt (
+			"",
+			"username",
+			"username for basic authentication",
+			"USERNAME");
+
+		options_spec.optopt (
+			"",
+			"password",
+			"password for basic authentication",
+			"PASSWORD");
+
+		// tls
+
+		arg_helper::tls_options_spec (
+			& mut options_spec);
+
 		// timeouts
 
 		options_spec.optopt (
@@ -59,17 +120,95 @@ check! {
 			"status request time critical duration",
 			"DURATION");
 
+		// staleness
+
+		options_spec.optopt (
+			"",
+			"max-age-warning",
+			"warning threshold for the age of the result's timestamp",
+			"DURATION");
+
+		options_spec.optopt (
+			"",
+			"max-age-critical",
+			"critical threshold for the age of the result's timestamp",
+			"DURATION");
+
 	},
 
 	options_parse = |options_matches| {
 
+		let target =
+			arg_helper::parse_string (
+				options_matches,
+				"target",
+			) ?;
+
+		let file =
+			arg_helper::parse_string (
+				options_matches,
+				"file",
+			) ?;
+
+		if target.is_some () == file.is_some () {
+
+			return Err (Box::new (
+				SimpleError::from (
+					"Exactly one of --target and --file must be given")));
+
+		}
+
+		if options_matches.opt_present ("password")
+			&& ! options_matches.opt_present ("username") {
+
+			return Err (Box::new (
+				SimpleError::from (
+					"--password can only be used with --username")));
+
+		}
+
 		CheckGenericInstance {
 
 			target:
-				arg_helper::parse_string_required (
+				target,
+
+			file:
+				file,
+
+			path:
+				arg_helper::parse_string (
+					options_matches,
+					"path",
+				) ?,
+
+			tls_options:
+				arg_helper::parse_tls_options (
+					options_matches,
+				) ?,
+
+			send_headers:
+				arg_helper::parse_header_multiple (
+					options_matches,
+					"send-header",
+				) ?,
+
+			bearer_token:
+				arg_helper::parse_string (
+					options_matches,
+					"bearer-token",
+				) ?,
+
+			username:
+				arg_helper::parse_string (
+					options_matches,
+					"username",
+				) ?,
+
+			password:
+				arg_helper::parse_string (
 					options_matches,
-					"target",
-				) ?.parse () ?,
+					"password",
+				) ?,
 
 			request_time_warning:
 				arg_helper::parse_duration (
@@ -90,15 +229,60 @@ check! {
 					& time::Duration::new (60, 0),
 				) ?,
 
+			max_age_warning:
+				arg_helper::parse_duration (
+					options_matches,
+					"max-age-warning",
+				) ?,
+
+			max_age_critical:
+				arg_helper::parse_duration (
+					options_matches,
+					"max-age-critical",
+				) ?,
+
 		}
 
 	},
 
-	perform = |self, plugin_provider, check_result_builder| {
+	perform = |self, plugin_provider, environment, check_result_builder| {
 
-		self.perform_request (
-			& mut check_result_builder,
-		) ?;
+		let response_string =
+			match (self.target.as_ref (), self.file.as_ref ()) {
+
+			(Some (target), _) => {
+
+				environment.enter_phase (
+					& format! (
+						"requesting {}",
+						target));
+
+				self.perform_request (
+					& mut check_result_builder,
+					target,
+				) ?
+
+			},
+
+			(None, Some (file)) =>
+				self.read_file (
+					& mut check_result_builder,
+					environment,
+					file),
+
+			(None, None) =>
+				None,
+
+		};
+
+		if let Some (response_string) = response_string {
+
+			self.process_response (
+				& mut check_result_builder,
+				environment.now (),
+				& response_string);
+
+		}
 
 	},
 
@@ -106,44 +290,149 @@ check! {
 
 impl CheckGenericInstance {
 
+	// batch jobs write the same document to a file instead of serving it
+
+	fn read_file (
+		& self,
+		check_result_builder: & mut CheckResultBuilder,
+		environment: & Environment,
+		file: & str,
+	) -> Option <String> {
+
+		let file_contents =
+			match environment.read_file (
+				Path::new (file),
+			) {
+
+			Ok (file_contents) => file_contents,
+
+			Err (error) => {
+
+				check_result_builder.critical (
+					format! (
+						"Unable to read {}: {}",
+						file,
+						error));
+
+				return None;
+
+			},
+
+		};
+
+		match String::from_utf8 (
+			file_contents,
+		) {
+
+			Ok (file_string) =>
+				Some (file_string),
+
+			Err (error) => {
+
+				check_result_builder.critical (
+					format! (
+						"Error decoding {} as UTF-8 string: {}",
+						file,
+						error));
+
+				None
+
+			},
+
+		}
+
+	}
+
 	fn perform_request (
 		& self,
 		check_result_builder: & mut CheckResultBuilder,
-	) -> Result <(), Box <Error>> {
+		target_string: & str,
+	) -> Result <Option <String>, Box <Error>> {
 
-		let http_request =
-			HttpSimpleRequest {
+		let target =
+			match parse_target (
+				target_string,
+			) {
 
-			address: self.target.host ().unwrap (),
-			hostname: self.target.host ().unwrap (),
+			Ok (target) => target,
 
-			port: self.target.port ().unwrap_or (
-				match self.target.scheme ().unwrap () {
-					"http" => 80,
-					"https" => 443,
-					_ => panic! ("Invalid scheme"),
-				}
-			) as u64,
+			Err (error) => {
+
+				check_result_builder.unknown (
+					format! (
+						"Invalid target {}: {}",
+						target_string,
+						error));
+
+				return Ok (None);
 
-			secure: match self.target.scheme ().unwrap () {
-				"http" => false,
-				"https" => true,
-				_ => panic! ("Invalid scheme"),
 			},
 
-			headers: & Vec::new (),
+		};
+
+		let path =
+			self.path.as_ref ().unwrap_or (
+				& target.path);
+
+		let headers =
+			self.request_headers ();
+
+		let http_request =
+			HttpSimpleRequest {
+
+			address: & target.host,
+			hostname: & target.host,
+			port: target.port,
+			secure: target.secure,
+
+			tls_options: & self.tls_options,
+			connect_to: None,
+
+			headers: & headers,
+			body: & [],
 
 			method: HttpMethod::Get,
-			path: self.target.path (),
+			path: path,
 
+			connect_timeout: Some (self.request_timeout),
 			timeout: self.request_timeout,
 
 		};
 
+		let http_result =
+			match target.socket_path {
+
+			Some (ref socket_path) =>
+				http_unix_perform (
+					socket_path,
+					& http_request),
+
+			None =>
+				http_simple_perform (
+					& http_request),
+
+		};
+
 		let http_response =
-			http_simple_perform (
-				& http_request,
-			) ?;
+			match http_result {
+
+			Ok (http_response) => http_response,
+
+			Err (HttpError::Certificate (reason)) => {
+
+				check_result_builder.critical (
+					format! (
+						"Certificate verification failed: {}",
+						reason));
+
+				return Ok (None);
+
+			},
+
+			Err (error) =>
+				return Err (error.into ()),
+
+		};
 
 		check_helper::check_duration_less_than (
 			check_result_builder,
@@ -167,15 +456,50 @@ impl CheckGenericInstance {
 						"Error decoding result as UTF-8 string: {}",
 						error));
 
-				return Ok (());
+				return Ok (None);
 
 			},
 
 		};
 
+		Ok (Some (hyper_response_string))
+
+	}
+
+	fn request_headers (
+		& self,
+	) -> Vec <(String, String)> {
+
+		let mut headers =
+			self.send_headers.clone ();
+
+		if let Some (authorization) =
+			authorization_header (
+				& self.bearer_token,
+				& self.username,
+				& self.password) {
+
+			headers.push ((
+				"Authorization".to_string (),
+				authorization,
+			));
+
+		}
+
+		headers
+
+	}
+
+	fn process_response (
+		& self,
+		check_result_builder: & mut CheckResultBuilder,
+		now: time::SystemTime,
+		response_string: & str,
+	) {
+
 		let check_response =
 			match serde_json::from_str::<GenericCheckResponse> (
-				& hyper_response_string,
+				response_string,
 			) {
 
 			Ok (value) => value,
@@ -187,38 +511,102 @@ impl CheckGenericInstance {
 						"Error decoding JSON structure: {}",
 						error.description ()));
 
-				return Ok (());
+				return;
 
 			},
 
 		};
 
-		match check_response.status.as_str () {
+		if check_response.protocol_version > PROTOCOL_VERSION {
 
-			"ok" =>
-				check_result_builder.ok (
-					check_response.status_message),
+			check_result_builder.unknown (
+				format! (
+					"Unsupported protocol version: {}",
+					check_response.protocol_version));
 
-			"warning" =>
-				check_result_builder.warning (
-					check_response.status_message),
+			return;
 
-			"critical" =>
-				check_result_builder.critical (
-					check_response.status_message),
+		}
 
-			"unknown" =>
-				check_result_builder.unknown (
-					check_response.status_message),
+		// performance data is checked first, so that a bad entry doesn't
+		// leave us with half a result
 
-			_ =>
-				check_result_builder.unknown (
-					format! (
-						"Invalid check result status: {}",
-						check_response.status)),
+		let mut performance_data: Vec <PerformanceData> =
+			Vec::new ();
+
+		for generic_performance_data
+			in check_response.performance_data.iter () {
+
+			match generic_performance_data.to_performance_data () {
+
+				Ok (value) =>
+					performance_data.push (
+						value),
+
+				Err (error) => {
+
+					check_result_builder.unknown (
+						format! (
+							"Invalid performance data {}: {}",
+							generic_performance_data.label,
+							error));
+
+					return;
+
+				},
+
+			}
+
+		}
+
+		let timestamp =
+			match check_response.timestamp {
+
+			Some (ref timestamp) =>
+				match parse_timestamp (timestamp) {
+
+				Ok (timestamp) =>
+					Some (timestamp),
+
+				Err (error) => {
+
+					check_result_builder.unknown (
+						format! (
+							"Invalid timestamp: {}",
+							error));
+
+					return;
+
+				},
+
+			},
+
+			None =>
+				None,
+
+		};
+
+		report_status (
+			check_result_builder,
+			"",
+			& check_response.status,
+			check_response.status_message,
+			true);
+
+		for sub_check in check_response.checks {
+
+			report_sub_check (
+				check_result_builder,
+				"",
+				sub_check);
 
 		}
 
+		self.check_age (
+			check_result_builder,
+			now,
+			timestamp);
+
 		for additional_message in check_response.additional_messages {
 
 			check_result_builder.extra_information (
@@ -226,24 +614,829 @@ impl CheckGenericInstance {
 
 		}
 
-		Ok (())
+		for performance_data in performance_data {
+
+			check_result_builder.performance_data (
+				performance_data);
+
+		}
+
+	}
+
+	// a status served from a cache, or written by a job which has stopped
+	// running, looks fine apart from its age
+
+	fn check_age (
+		& self,
+		check_result_builder: & mut CheckResultBuilder,
+		now: time::SystemTime,
+		timestamp: Option <time::SystemTime>,
+	) {
+
+		let timestamp =
+			match timestamp {
+
+			Some (timestamp) =>
+				timestamp,
+
+			None => {
+
+				if self.max_age_warning.is_some ()
+					|| self.max_age_critical.is_some () {
+
+					check_result_builder.unknown (
+						"No timestamp in result, needed to check its age");
+
+				}
+
+				return;
+
+			},
+
+		};
+
+		// a timestamp slightly in the future is just clock skew
+
+		let age =
+			now.duration_since (
+				timestamp,
+			).unwrap_or (
+				time::Duration::new (0, 0),
+			);
+
+		check_helper::check_duration_less_than (
+			check_result_builder,
+			& self.max_age_warning,
+			& self.max_age_critical,
+			& format! (
+				"Result is {} old",
+				check_helper::display_duration_short (
+					& age)),
+			& age);
+
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"age",
+				check_helper::duration_as_seconds (
+					& age),
+				PerformanceDataUnit::Seconds,
+			).warning_above (
+				self.max_age_warning.as_ref ().map (
+					check_helper::duration_as_seconds),
+			).critical_above (
+				self.max_age_critical.as_ref ().map (
+					check_helper::duration_as_seconds),
+			).minimum (
+				0.0,
+			));
+
+	}
+
+}
+
+// a bearer token wins over basic authentication if both are given
+
+fn authorization_header (
+	bearer_token: & Option <String>,
+	username: & Option <String>,
+	password: & Option <String>,
+) -> Option <String> {
+
+	if let Some (ref bearer_token) = * bearer_token {
+
+		Some (
+			Authorization (
+				Bearer {
+					token: bearer_token.to_string (),
+				},
+			).to_string ())
+
+	} else if let Some (ref username) = * username {
+
+		Some (
+			Authorization (
+				Basic {
+					username: username.to_string (),
+					password: password.clone (),
+				},
+			).to_string ())
+
+	} else {
+
+		None
+
+	}
+
+}
+
+// ---------- targets
+
+struct GenericTarget {
+	secure: bool,
+	host: String,
+	port: u64,
+	path: String,
+	socket_path: Option <String>,
+}
+
+fn parse_target (
+	target_string: & str,
+) -> Result <GenericTarget, String> {
+
+	if target_string.starts_with ("unix://") {
+
+		let socket_path =
+			& target_string ["unix://".len () ..];
+
+		if ! socket_path.starts_with ('/') {
+
+			return Err (
+				"socket path must be absolute".to_string ());
+
+		}
+
+		return Ok (GenericTarget {
+			secure: false,
+			host: "localhost".to_string (),
+			port: 80,
+			path: "/".to_string (),
+			socket_path: Some (socket_path.to_string ()),
+		});
 
 	}
 
+	let target =
+		target_string.parse::<Uri> ().map_err (
+			|error| error.description ().to_string (),
+		) ?;
+
+	let secure =
+		match target.scheme () {
+
+			Some ("http") => false,
+			Some ("https") => true,
+
+			Some (scheme) =>
+				return Err (
+					format! (
+						"unsupported scheme {}",
+						scheme)),
+
+			None =>
+				return Err (
+					"missing scheme".to_string ()),
+
+		};
+
+	let host =
+		match target.host () {
+			Some (host) => host.to_string (),
+			None => return Err ("missing host".to_string ()),
+		};
+
+	Ok (GenericTarget {
+
+		secure: secure,
+		host: host,
+
+		port: target.port ().map (
+			|port| port as u64,
+		).unwrap_or (
+			if secure { 443 } else { 80 },
+		),
+
+		path: match target.query () {
+			Some (query) => format! ("{}?{}", target.path (), query),
+			None => target.path ().to_string (),
+		},
+
+		socket_path: None,
+
+	})
+
 }
 
+// ---------- protocol
+
+// version 1 has only status, status message and additional messages, and is
+// assumed when the version is missing. version 2 adds performance data, sub
+// checks and the timestamp.
+
+const PROTOCOL_VERSION: u64 = 2;
+
 #[ derive (Serialize, Deserialize) ]
 struct GenericCheckResponse {
 
+	#[ serde (rename = "protocol-version", default = "default_protocol_version") ]
+	protocol_version: u64,
+
 	#[ serde (rename = "status") ]
 	status: String,
 
 	#[ serde (rename = "status-message") ]
 	status_message: String,
 
-	#[ serde (rename = "additional-messages") ]
+	#[ serde (rename = "additional-messages", default) ]
 	additional_messages: Vec <String>,
 
+	#[ serde (rename = "performance-data", default) ]
+	performance_data: Vec <GenericPerformanceData>,
+
+	#[ serde (rename = "checks", default) ]
+	checks: Vec <GenericSubCheck>,
+
+	#[ serde (rename = "timestamp") ]
+	timestamp: Option <serde_json::Value>,
+
+}
+
+#[ derive (Serialize, Deserialize) ]
+struct GenericPerformanceData {
+
+	#[ serde (rename = "label") ]
+	label: String,
+
+	#[ serde (rename = "value") ]
+	value: f64,
+
+	#[ serde (rename = "unit", default) ]
+	unit: String,
+
+	#[ serde (rename = "warning") ]
+	warning: Option <serde_json::Value>,
+
+	#[ serde (rename = "critical") ]
+	critical: Option <serde_json::Value>,
+
+	#[ serde (rename = "minimum") ]
+	minimum: Option <f64>,
+
+	#[ serde (rename = "maximum") ]
+	maximum: Option <f64>,
+
+}
+
+#[ derive (Serialize, Deserialize) ]
+struct GenericSubCheck {
+
+	#[ serde (rename = "name") ]
+	name: String,
+
+	#[ serde (rename = "status") ]
+	status: String,
+
+	#[ serde (rename = "status-message", default) ]
+	status_message: String,
+
+	#[ serde (rename = "checks", default) ]
+	checks: Vec <GenericSubCheck>,
+
+}
+
+fn default_protocol_version (
+) -> u64 {
+	1
+}
+
+impl GenericPerformanceData {
+
+	fn to_performance_data (
+		& self,
+	) -> Result <PerformanceData, String> {
+
+		let unit =
+			match self.unit.as_str () {
+				"" => PerformanceDataUnit::None,
+				"s" => PerformanceDataUnit::Seconds,
+				"ms" => PerformanceDataUnit::Milliseconds,
+				"us" => PerformanceDataUnit::Microseconds,
+				"%" => PerformanceDataUnit::Percent,
+				"B" => PerformanceDataUnit::Bytes,
+				"KB" => PerformanceDataUnit::Kilobytes,
+				"MB" => PerformanceDataUnit::Megabytes,
+				"TB" => PerformanceDataUnit::Terabytes,
+				"c" => PerformanceDataUnit::Counter,
+				_ => return Err (
+					format! (
+						"unknown unit: {}",
+						self.unit)),
+			};
+
+		Ok (
+			PerformanceData::new (
+				self.label.to_string (),
+				self.value,
+				unit,
+			).warning_range (
+				parse_threshold (
+					& self.warning,
+				) ?,
+			).critical_range (
+				parse_threshold (
+					& self.critical,
+				) ?,
+			).minimum (
+				self.minimum,
+			).maximum (
+				self.maximum,
+			)
+		)
+
+	}
+
+}
+
+// thresholds are either a plain number, the upper limit, or a string in the
+// usual plugin range format, which is what a plain number means anyway
+
+fn parse_threshold (
+	threshold: & Option <serde_json::Value>,
+) -> Result <Option <check_helper::CheckRange>, String> {
+
+	match * threshold {
+
+		None | Some (serde_json::Value::Null) =>
+			Ok (None),
+
+		Some (serde_json::Value::Number (ref number)) =>
+			parse_threshold_string (
+				& number.to_string ()),
+
+		Some (serde_json::Value::String (ref range_string)) =>
+			parse_threshold_string (
+				range_string),
+
+		Some (ref other) =>
+			Err (
+				format! (
+					"invalid threshold: {}",
+					other)),
+
+	}
+
+}
+
+// timestamps are either rfc 3339 strings or seconds since the epoch
+
+fn parse_timestamp (
+	timestamp: & serde_json::Value,
+) -> Result <time::SystemTime, String> {
+
+	let seconds =
+		match * timestamp {
+
+		serde_json::Value::String (ref timestamp_string) =>
+			DateTime::parse_from_rfc3339 (
+				timestamp_string,
+			).map (
+				|timestamp| timestamp.timestamp () as f64
+			).map_err (
+				|error|
+				format! (
+					"{}: {}",
+					timestamp_string,
+					error),
+			) ?,
+
+		serde_json::Value::Number (ref number) =>
+			number.as_f64 ().unwrap_or (-1.0),
+
+		ref other =>
+			return Err (
+				other.to_string ()),
+
+	};
+
+	if seconds < 0.0 {
+
+		return Err (
+			timestamp.to_string ());
+
+	}
+
+	time::UNIX_EPOCH.checked_add (
+		check_helper::duration_from_seconds (
+			seconds),
+	).ok_or_else (
+		|| "timestamp out of range".to_string ())
+
+}
+
+fn parse_threshold_string (
+	range_string: & str,
+) -> Result <Option <check_helper::CheckRange>, String> {
+
+	check_helper::CheckRange::parse_number (
+		range_string,
+		check_helper::CheckRangeBareValue::AlertAbove,
+	).map (
+		Some,
+	).map_err (
+		|error| error.description ().to_string ()
+	)
+
+}
+
+// sub check results are aggregated into the overall status, problems are
+// shown in the status line and the rest in the additional information
+
+fn report_sub_check (
+	check_result_builder: & mut CheckResultBuilder,
+	parent_name: & str,
+	sub_check: GenericSubCheck,
+) {
+
+	let name =
+		if parent_name.is_empty () {
+			sub_check.name
+		} else {
+			format! (
+				"{} / {}",
+				parent_name,
+				sub_check.name)
+		};
+
+	let message =
+		if sub_check.status_message.is_empty () {
+			format! (
+				"{}: {}",
+				name,
+				sub_check.status)
+		} else {
+			format! (
+				"{}: {}",
+				name,
+				sub_check.status_message)
+		};
+
+	report_status (
+		check_result_builder,
+		& name,
+		& sub_check.status,
+		message,
+		false);
+
+	for child_check in sub_check.checks {
+
+		report_sub_check (
+			check_result_builder,
+			& name,
+			child_check);
+
+	}
+
+}
+
+fn report_status (
+	check_result_builder: & mut CheckResultBuilder,
+	name: & str,
+	status: & str,
+	message: String,
+	show_ok: bool,
+) {
+
+	match status {
+
+		"ok" if show_ok =>
+			check_result_builder.ok (
+				message),
+
+		"ok" =>
+			check_result_builder.extra_information (
+				message),
+
+		"warning" =>
+			check_result_builder.warning (
+				message),
+
+		"critical" =>
+			check_result_builder.critical (
+				message),
+
+		"unknown" =>
+			check_result_builder.unknown (
+				message),
+
+		_ if name.is_empty () =>
+			check_result_builder.unknown (
+				format! (
+					"Invalid check result status: {}",
+					status)),
+
+		_ =>
+			check_result_builder.unknown (
+				format! (
+					"Invalid check result status for {}: {}",
+					name,
+					status)),
+
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use logic::*;
+	use logic::testing::*;
+
+	use super::authorization_header;
+	use super::parse_target;
+
+	fn check_status_file (
+		file: & str,
+	) -> CheckResult {
+
+		run_with_fixture (
+			& * super::new (),
+			"generic/status-files",
+			& [ "--file", file ])
+
+	}
+
+	#[ test ]
+	fn version_one_response () {
+
+		let check_result =
+			check_status_file (
+				"/var/lib/queue/status.json");
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Warning,
+			& [ "queue is long" ]);
+
+		assert_eq! (
+			check_result.extra_information (),
+			& [ "42 jobs waiting".to_string () ]);
+
+	}
+
+	#[ test ]
+	fn sub_checks_and_performance_data () {
+
+		let check_result =
+			check_status_file (
+				"/var/lib/app/status.json");
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [
+				"application running",
+				"queue / mail: worker stopped",
+			]);
+
+		assert_eq! (
+			check_result.extra_information (),
+			& [
+				"database: ok".to_string (),
+				"queue: ok".to_string (),
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& [
+				"queue_length=42;100;200;0",
+				"cache_hits=93.5%",
+			]);
+
+	}
+
+	#[ test ]
+	fn unsupported_protocol_version () {
+
+		let check_result =
+			check_status_file (
+				"/var/lib/future/status.json");
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [ "Unsupported protocol version: 3" ]);
+
+	}
+
+	#[ test ]
+	fn fresh_status_file () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"generic/status-files",
+				& [
+					"--file", "/var/lib/backup/status.json",
+					"--max-age-warning", "6h",
+					"--max-age-critical", "1d",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Ok,
+			& [
+				"backup completed",
+				"Result is 30.0m old",
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& [ "age=1800s;21600;86400;0" ]);
+
+	}
+
+	#[ test ]
+	fn stale_status_file () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"generic/status-files",
+				& [
+					"--file", "/var/lib/export/status.json",
+					"--max-age-warning", "6h",
+					"--max-age-critical", "1d",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [
+				"export completed",
+				"Result is 1.08d old (critical is above 1.00d)",
+			]);
+
+	}
+
+	#[ test ]
+	fn status_file_without_timestamp () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"generic/status-files",
+				& [
+					"--file", "/var/lib/report/status.json",
+					"--max-age-warning", "6h",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [
+				"report completed",
+				"No timestamp in result, needed to check its age",
+			]);
+
+	}
+
+	#[ test ]
+	fn timestamp_out_of_range () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"generic/status-files",
+				& [
+					"--file", "/var/lib/distant/status.json",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [ "Invalid timestamp: timestamp out of range" ]);
+
+	}
+
+	#[ test ]
+	fn missing_status_file () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"generic/status-files",
+				& [
+					"--file", "/var/lib/import/status.json",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [
+				"Unable to read /var/lib/import/status.json: \
+				not in fixture: /var/lib/import/status.json",
+			]);
+
+	}
+
+	#[ test ]
+	fn targets () {
+
+		let target =
+			parse_target (
+				"https://status.example.com:8443/health?full=1",
+			).unwrap ();
+
+		assert_eq! (
+			(
+				target.secure,
+				target.host.as_str (),
+				target.port,
+				target.path.as_str (),
+				target.socket_path,
+			),
+			(true, "status.example.com", 8443, "/health?full=1", None));
+
+		let target =
+			parse_target (
+				"http://status.example.com",
+			).unwrap ();
+
+		assert_eq! (
+			(target.secure, target.port),
+			(false, 80));
+
+		let target =
+			parse_target (
+				"unix:///run/app/status.sock",
+			).unwrap ();
+
+		assert_eq! (
+			target.socket_path,
+			Some ("/run/app/status.sock".to_string ()));
+
+		for & (target_string, expected_error) in [
+			("ftp://status.example.com/", "unsupported scheme ftp"),
+			("unix://run/app/status.sock", "socket path must be absolute"),
+			("/health", "missing scheme"),
+		].iter () {
+
+			assert_eq! (
+				parse_target (target_string).err (),
+				Some (expected_error.to_string ()),
+				"{}",
+				target_string);
+
+		}
+
+		// hyper may already refuse this, otherwise we do
+
+		assert! (
+			parse_target ("http:///health").is_err ());
+
+	}
+
+	#[ test ]
+	fn authorization_headers () {
+
+		let token =
+			Some ("secret".to_string ());
+
+		let username =
+			Some ("monitor".to_string ());
+
+		let password =
+			Some ("hunter2".to_string ());
+
+		assert_eq! (
+			authorization_header (& token, & username, & password),
+			Some ("Bearer secret".to_string ()));
+
+		assert_eq! (
+			authorization_header (& None, & username, & password),
+			Some ("Basic bW9uaXRvcjpodW50ZXIy".to_string ()));
+
+		assert_eq! (
+			authorization_header (& None, & username, & None),
+			Some ("Basic bW9uaXRvcjo=".to_string ()));
+
+		assert_eq! (
+			authorization_header (& None, & None, & None),
+			None);
+
+	}
+
+	#[ test ]
+	fn password_without_username () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"generic/status-files",
+				& [
+					"--file", "/var/lib/backup/status.json",
+					"--password", "hunter2",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Unknown,
+			& [
+				"unable to process command line arguments due to program error",
+				"--password can only be used with --username",
+			]);
+
+	}
+
 }
 
 // ex: noet ts=4 filetype=rust
@@ -4,18 +4,26 @@ extern crate getopts;
 
 use std::collections::HashMap;
 use std::error;
+use std::fs::File;
+use std::io::Read;
+use std::net::IpAddr;
 use std::thread;
 use std::thread::JoinHandle;
 use std::time::Duration;
 use std::time::Instant;
 
-use chrono::Duration as ChronoDuration;
 use chrono::offset::Utc;
 
+use hyper::Uri;
+
 use itertools::Itertools;
 
+use regex::Regex;
+
 use resolv;
 
+use serde_json;
+
 use logic::*;
 use lowlevel::http::*;
 
@@ -30,22 +38,47 @@ check! {
 	instance = CheckHttpInstance {
 
 		address: String,
+		hostname: String,
+		connect_to: Option <(String, u64)>,
 		port: u64,
 		secure: bool,
+		tls_options: HttpTlsOptions,
+
+		address_family: AddressFamily,
+		expect_ipv4_addresses: Option <u64>,
+		expect_ipv6_addresses: Option <u64>,
 
 		method: HttpMethod,
 		path: String,
 
 		send_headers: Vec <(String, String)>,
+		body: Vec <u8>,
+		content_type: Option <String>,
 
 		request_count: u64,
 
+		follow_redirects: u64,
+		expect_final_url: Option <String>,
+		expect_redirect_to: Option <String>,
+
 		expect_status_code: Vec <u64>,
 		expect_headers: Vec <(String, String)>,
 		expect_body_text: Option <String>,
-
-		response_time_warning: Option <Duration>,
-		response_time_critical: Option <Duration>,
+		expect_body_regex: Vec <Regex>,
+		reject_body_text: Option <String>,
+		reject_body_regex: Option <Regex>,
+		expect_json: Vec <JsonAssertion>,
+		body_max_size: Option <u64>,
+		body_min_size: Option <u64>,
+
+		cert_expiry_warning: Duration,
+		cert_expiry_critical: Duration,
+		expect_cert_names: Vec <String>,
+		expect_cert_issuer: Option <String>,
+
+		response_time_warning: Option <check_helper::CheckRange>,
+		response_time_critical: Option <check_helper::CheckRange>,
+		response_time_statistic: ResponseTimeStatistic,
 
 		timeout: Duration,
 
@@ -61,6 +94,20 @@ check! {
 			"address to connect to, hostname or IP",
 			"ADDRESS");
 
+		options_spec.optopt (
+			"",
+			"hostname",
+			"hostname to send in the host header and for SNI, defaults to \
+			the address",
+			"HOSTNAME");
+
+		options_spec.optopt (
+			"",
+			"connect-to",
+			"connect to this host and port instead of the address, while \
+			still presenting the hostname",
+			"HOST:PORT");
+
 		options_spec.optopt (
 			"",
 			"port",
@@ -72,12 +119,35 @@ check! {
 			"secure",
 			"use a secure connection, ie SSL or TLS");
 
+		arg_helper::tls_options_spec (
+			& mut options_spec);
+
+		options_spec.optopt (
+			"",
+			"address-family",
+			"address family to resolve and connect to: ipv4, ipv6 or both \
+			(default)",
+			"FAMILY");
+
+		options_spec.optopt (
+			"",
+			"expect-ipv4-addresses",
+			"minimum number of IPv4 addresses to resolve",
+			"COUNT");
+
+		options_spec.optopt (
+			"",
+			"expect-ipv6-addresses",
+			"minimum number of IPv6 addresses to resolve",
+			"COUNT");
+
 		// request options
 
 		options_spec.optopt (
 			"",
 			"method",
-			"method to use: get (default) or post",
+			"method to use: get (default), head, post, put, delete, options \
+			or patch",
 			"METHOD");
 
 		options_spec.optopt (
@@ -92,12 +162,51 @@ check! {
 			"header to send, eg 'name: value'",
 			"NAME:VALUE");
 
+		options_spec.optopt (
+			"",
+			"body",
+			"request body to send",
+			"TEXT");
+
+		options_spec.optopt (
+			"",
+			"body-file",
+			"file containing request body to send",
+			"PATH");
+
+		options_spec.optopt (
+			"",
+			"content-type",
+			"content type of request body",
+			"TYPE");
+
 		options_spec.optopt (
 			"",
 			"request-count",
-			"send multilpe requests on same connection",
+			"number of requests to send on the same connection, defaults \
+			to 1",
+			"COUNT");
+
+		// redirect options
+
+		options_spec.optopt (
+			"",
+			"follow-redirects",
+			"maximum number of redirects to follow, defaults to 0",
 			"COUNT");
 
+		options_spec.optopt (
+			"",
+			"expect-final-url",
+			"url expected after following redirects",
+			"URL");
+
+		options_spec.optopt (
+			"",
+			"expect-redirect-to",
+			"url the first response is expected to redirect to",
+			"URL");
+
 		// response options
 
 		options_spec.optopt (
@@ -118,20 +227,92 @@ check! {
 			"text to expect in body",
 			"TEXT");
 
+		options_spec.optmulti (
+			"",
+			"expect-body-regex",
+			"regular expression to expect in body, may be repeated",
+			"REGEX");
+
+		options_spec.optopt (
+			"",
+			"reject-body-text",
+			"text which must not appear in body",
+			"TEXT");
+
+		options_spec.optopt (
+			"",
+			"reject-body-regex",
+			"regular expression which must not match body",
+			"REGEX");
+
+		options_spec.optmulti (
+			"",
+			"expect-json",
+			"value to expect in json body, eg '$.status=\"ok\"', may be \
+			repeated",
+			"PATH=VALUE");
+
+		options_spec.optopt (
+			"",
+			"body-max-size",
+			"maximum size of body in bytes",
+			"BYTES");
+
+		options_spec.optopt (
+			"",
+			"body-min-size",
+			"minimum size of body in bytes",
+			"BYTES");
+
+		// certificate options
+
+		options_spec.optopt (
+			"",
+			"cert-expiry-warning",
+			"warn when certificate expires within this duration, defaults to \
+			1 week",
+			"DURATION");
+
+		options_spec.optopt (
+			"",
+			"cert-expiry-critical",
+			"critical when certificate expires within this duration, \
+			defaults to 5 days",
+			"DURATION");
+
+		options_spec.optmulti (
+			"",
+			"expect-cert-name",
+			"name the certificate must be valid for, may be repeated",
+			"NAME");
+
+		options_spec.optopt (
+			"",
+			"expect-cert-issuer",
+			"common name or distinguished name of the expected issuer",
+			"ISSUER");
+
 		// timings
 
 		options_spec.optopt (
 			"",
 			"response-time-warning",
-			"total response time warning threshold",
+			"total response time warning threshold, duration or range",
 			"DURATION");
 
 		options_spec.optopt (
 			"",
 			"response-time-critical",
-			"total response time critical threshold",
+			"total response time critical threshold, duration or range",
 			"DURATION");
 
+		options_spec.optopt (
+			"",
+			"response-time-statistic",
+			"response time the thresholds apply to with --request-count: min, \
+			median, p95 or max (default)",
+			"STATISTIC");
+
 		options_spec.optopt (
 			"",
 			"timeout",
@@ -142,7 +323,7 @@ check! {
 
 	options_parse = |options_matches| {
 
-		// determine secure parameter beforehand
+		// determine secure and address parameters beforehand
 
 		let secure =
 			arg_helper::check_if_present (
@@ -150,16 +331,39 @@ check! {
 				"secure",
 			) ?;
 
+		let address =
+			arg_helper::parse_string_required (
+				options_matches,
+				"address",
+			) ?;
+
+		let (cert_expiry_warning, cert_expiry_critical) =
+			arg_helper::parse_cert_expiry (
+				options_matches,
+			) ?;
+
 		// return
 
 		CheckHttpInstance {
 
 			// connection
 
-			address:
-				arg_helper::parse_string_required (
+			hostname:
+				arg_helper::parse_string_or_default (
 					options_matches,
-					"address",
+					"hostname",
+					& address,
+				) ?,
+
+			address:
+				address,
+
+			connect_to:
+				parse_connect_to (
+					arg_helper::parse_string (
+						options_matches,
+						"connect-to",
+					) ?,
 				) ?,
 
 			port:
@@ -172,10 +376,40 @@ check! {
 			secure:
 				secure,
 
+			tls_options:
+				arg_helper::parse_tls_options (
+					options_matches,
+				) ?,
+
+			address_family:
+				arg_helper::parse_enum (
+					options_matches,
+					"address-family",
+				) ?.unwrap_or (
+					AddressFamily::Both,
+				),
+
+			expect_ipv4_addresses:
+				arg_helper::parse_positive_integer (
+					options_matches,
+					"expect-ipv4-addresses",
+				) ?,
+
+			expect_ipv6_addresses:
+				arg_helper::parse_positive_integer (
+					options_matches,
+					"expect-ipv6-addresses",
+				) ?,
+
 			// request
 
 			method:
-				HttpMethod::Get,
+				arg_helper::parse_enum (
+					options_matches,
+					"method",
+				) ?.unwrap_or (
+					HttpMethod::Get,
+				),
 
 			path:
 				arg_helper::parse_string_or_default (
@@ -185,11 +419,20 @@ check! {
 				) ?,
 
 			send_headers:
-				parse_headers (
-					arg_helper::parse_string_multiple (
-						options_matches,
-						"send-header",
-					) ?,
+				arg_helper::parse_header_multiple (
+					options_matches,
+					"send-header",
+				) ?,
+
+			body:
+				parse_body (
+					options_matches,
+				) ?,
+
+			content_type:
+				arg_helper::parse_string (
+					options_matches,
+					"content-type",
 				) ?,
 
 			request_count:
@@ -199,6 +442,27 @@ check! {
 					1,
 				) ?,
 
+			// redirects
+
+			follow_redirects:
+				arg_helper::parse_positive_integer_or_default (
+					options_matches,
+					"follow-redirects",
+					0,
+				) ?,
+
+			expect_final_url:
+				arg_helper::parse_string (
+					options_matches,
+					"expect-final-url",
+				) ?,
+
+			expect_redirect_to:
+				arg_helper::parse_string (
+					options_matches,
+					"expect-redirect-to",
+				) ?,
+
 			// response
 
 			expect_status_code:
@@ -222,20 +486,88 @@ check! {
 					"expect-body-text",
 				) ?,
 
+			expect_body_regex:
+				arg_helper::parse_regex_multiple (
+					options_matches,
+					"expect-body-regex",
+				) ?,
+
+			reject_body_text:
+				arg_helper::parse_string (
+					options_matches,
+					"reject-body-text",
+				) ?,
+
+			reject_body_regex:
+				arg_helper::parse_regex (
+					options_matches,
+					"reject-body-regex",
+				) ?,
+
+			expect_json:
+				parse_json_assertions (
+					arg_helper::parse_string_multiple (
+						options_matches,
+						"expect-json",
+					) ?,
+				) ?,
+
+			body_max_size:
+				arg_helper::parse_positive_integer (
+					options_matches,
+					"body-max-size",
+				) ?,
+
+			body_min_size:
+				arg_helper::parse_positive_integer (
+					options_matches,
+					"body-min-size",
+				) ?,
+
+			// certificate
+
+			cert_expiry_warning:
+				cert_expiry_warning,
+
+			cert_expiry_critical:
+				cert_expiry_critical,
+
+			expect_cert_names:
+				arg_helper::parse_string_multiple (
+					options_matches,
+					"expect-cert-name",
+				) ?,
+
+			expect_cert_issuer:
+				arg_helper::parse_string (
+					options_matches,
+					"expect-cert-issuer",
+				) ?,
+
 			// timings
 
 			response_time_warning:
-				arg_helper::parse_duration (
+				arg_helper::parse_duration_range (
 					options_matches,
 					"response-time-warning",
+					check_helper::CheckRangeBareValue::AlertAbove,
 				) ?,
 
 			response_time_critical:
-				arg_helper::parse_duration (
+				arg_helper::parse_duration_range (
 					options_matches,
 					"response-time-critical",
+					check_helper::CheckRangeBareValue::AlertAbove,
 				) ?,
 
+			response_time_statistic:
+				arg_helper::parse_enum (
+					options_matches,
+					"response-time-statistic",
+				) ?.unwrap_or (
+					ResponseTimeStatistic::Max,
+				),
+
 			timeout:
 				arg_helper::parse_duration_or_default (
 					options_matches,
@@ -247,12 +579,13 @@ check! {
 
 	},
 
-	perform = |self, plugin_provider, check_result_builder| {
+	perform = |self, plugin_provider, environment, check_result_builder| {
 
 		// perform http request
 
 		self.check_http (
 			& mut check_result_builder,
+			environment,
 		) ?;
 
 	},
@@ -264,33 +597,95 @@ impl CheckHttpInstance {
 	fn check_http (
 		& self,
 		check_result_builder: & mut CheckResultBuilder,
+		environment: & Environment,
 	) -> Result <(), Box <error::Error>> {
 
-		let (lookup_duration, addresses) =
-			self.perform_hostname_lookup ();
+		// with --connect-to we resolve and contact that host instead
+
+		let lookup_name =
+			match self.connect_to {
+				Some ((ref connect_host, _)) => connect_host,
+				None => & self.address,
+			};
+
+		environment.enter_phase (
+			& format! (
+				"resolving {}",
+				lookup_name));
+
+		let (lookup_duration, ipv4_addresses, ipv6_addresses) =
+			self.perform_hostname_lookup (
+				lookup_name);
 
 		let num_addresses =
-			addresses.len ();
+			ipv4_addresses.len () + ipv6_addresses.len ();
 
 		if num_addresses == 0 {
 
 			check_result_builder.critical (
 				format! (
 					"Failed to resolve hostname: {}",
-					& self.address));
+					lookup_name));
 
 			return Ok (());
 
 		}
 
+		if let Some ((ref connect_host, connect_port)) = self.connect_to {
+
+			check_result_builder.extra_information (
+				format! (
+					"Connecting to {} port {} instead of {} port {}",
+					connect_host,
+					connect_port,
+					self.address,
+					self.port));
+
+		}
+
 		check_result_builder.extra_information (
 			format! (
-				"Resolved {} to {} hosts in {}",
-				& self.address,
+				"Resolved {} to {} hosts ({} IPv4, {} IPv6) in {}",
+				lookup_name,
 				num_addresses,
+				ipv4_addresses.len (),
+				ipv6_addresses.len (),
 				check_helper::display_duration_long (
 					& lookup_duration)));
 
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"lookup_time",
+				check_helper::duration_as_seconds (
+					& lookup_duration),
+				PerformanceDataUnit::Seconds,
+			).minimum (
+				0.0,
+			));
+
+		check_address_count (
+			check_result_builder,
+			"IPv4",
+			self.expect_ipv4_addresses,
+			ipv4_addresses.len ());
+
+		check_address_count (
+			check_result_builder,
+			"IPv6",
+			self.expect_ipv6_addresses,
+			ipv6_addresses.len ());
+
+		let addresses: Vec <String> =
+			ipv4_addresses.into_iter ().chain (
+				ipv6_addresses.into_iter (),
+			).collect ();
+
+		environment.enter_phase (
+			& format! (
+				"requesting {} from {}",
+				self.path,
+				addresses.join (", ")));
+
 		let request_results =
 			self.perform_requests_for_addresses (
 				addresses,
@@ -300,12 +695,16 @@ impl CheckHttpInstance {
 		let mut num_warnings: u64 = 0;
 		let mut num_criticals: u64 = 0;
 		let mut num_connection_errors: u64 = 0;
+		let mut num_certificate_errors: u64 = 0;
 		let mut num_timeouts: u64 = 0;
 		let mut num_other_errors: u64 = 0;
 
 		let mut durations: Vec <Duration> =
 			Vec::new ();
 
+		let mut all_durations: Vec <Duration> =
+			Vec::new ();
+
 		for (address, result) in request_results {
 
 			match result {
@@ -320,8 +719,27 @@ impl CheckHttpInstance {
 								& ", ".to_string (),
 							)));
 
-					durations.push (
-						result.duration);
+					for detail in result.details.iter () {
+
+						check_result_builder.extra_information (
+							format! (
+								"{}: {}",
+								address,
+								detail));
+
+					}
+
+					if let Some (duration) =
+						self.response_time_statistic.select (
+							& result.durations) {
+
+						durations.push (
+							duration);
+
+					}
+
+					all_durations.extend (
+						result.durations.iter ().cloned ());
 
 					match result.check_status {
 
@@ -373,6 +791,21 @@ impl CheckHttpInstance {
 
 				},
 
+				Err (RequestError::CertificateError (reason)) => {
+
+					check_result_builder.extra_information (
+						format! (
+							"{}: certificate verification failed: {}",
+							address,
+							reason));
+
+					num_certificate_errors += 1;
+
+					check_result_builder.update_status (
+						CheckStatus::Critical);
+
+				},
+
 				Err (RequestError::Timeout) => {
 
 					check_result_builder.extra_information (
@@ -409,12 +842,25 @@ impl CheckHttpInstance {
 		self.check_response_timing (
 			check_result_builder,
 			& durations,
+			& all_durations,
 		);
 
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"hosts_ok",
+				num_successes as f64,
+				PerformanceDataUnit::None,
+			).minimum (
+				0.0,
+			).maximum (
+				num_addresses as f64,
+			));
+
 		for (count, label) in vec! [
 			(num_other_errors, "reported unknown errors"),
 			(num_timeouts, "timed out"),
 			(num_connection_errors, "failed to connect"),
+			(num_certificate_errors, "failed certificate verification"),
 			(num_criticals, "critical"),
 			(num_warnings, "warning"),
 			(num_successes, "ok"),
@@ -439,55 +885,70 @@ impl CheckHttpInstance {
 
 	fn perform_hostname_lookup (
 		& self,
-	) -> (Duration, Vec <String>) {
+		lookup_name: & str,
+	) -> (Duration, Vec <String>, Vec <String>) {
 
 		let start_time =
 			Instant::now ();
 
-		let mut qualified_address =
-			self.address.to_string ();
+		// literal addresses need no lookup
 
-		let last_character =
-			qualified_address.chars ().rev ().next ().unwrap ();
+		let addresses: Vec <IpAddr> =
+			match lookup_name.parse::<IpAddr> () {
 
-		if last_character != '.' {
-			qualified_address.push ('.');
-		}
+				Ok (address) =>
+					vec! [ address ],
 
-		let mut resolver =
-			resolv::Resolver::new ().unwrap ();
+				Err (_) => {
 
-		let mut addresses: Vec <String> =
-			Vec::new ();
+					let mut qualified_address =
+						lookup_name.to_string ();
 
-		if let Ok (mut response) =
-			resolver.query (
-				qualified_address.as_bytes (),
-				resolv::Class::IN,
-				resolv::RecordType::A,
-			) {
+					let last_character =
+						qualified_address.chars ().rev ().next ().unwrap ();
 
-			for index in 0 .. response.get_section_count (
-				resolv::Section::Answer) {
+					if last_character != '.' {
+						qualified_address.push ('.');
+					}
 
-				if let Ok (record) =
-					response.get_record::<resolv::record::A> (
-						resolv::Section::Answer,
-						index,
-					) {
+					let mut resolver =
+						resolv::Resolver::new ().unwrap ();
 
-					addresses.push (
-						format! (
-							"{}",
-							record.data.address));
+					let mut addresses: Vec <IpAddr> =
+						Vec::new ();
 
-				}
+					if self.address_family.includes_ipv4 () {
 
-			}
+						addresses.extend (
+							lookup_addresses::<resolv::record::A> (
+								& mut resolver,
+								& qualified_address,
+								resolv::RecordType::A,
+								|data| IpAddr::V4 (data.address)));
 
-		}
+					}
+
+					if self.address_family.includes_ipv6 () {
+
+						addresses.extend (
+							lookup_addresses::<resolv::record::AAAA> (
+								& mut resolver,
+								& qualified_address,
+								resolv::RecordType::AAAA,
+								|data| IpAddr::V6 (data.address)));
+
+					}
 
-		addresses.sort ();
+					addresses
+
+				},
+
+			};
+
+		let (ipv4_addresses, ipv6_addresses) =
+			split_addresses (
+				self.address_family,
+				addresses);
 
 		let end_time =
 			Instant::now ();
@@ -498,7 +959,8 @@ impl CheckHttpInstance {
 
 		(
 			duration,
-			addresses,
+			ipv4_addresses,
+			ipv6_addresses,
 		)
 
 	}
@@ -562,108 +1024,333 @@ impl CheckHttpInstance {
 		address: & str,
 	) -> RequestResult {
 
-		// connect
+		let mut url =
+			RequestUrl {
+				secure: self.secure,
+				hostname: self.hostname.to_string (),
+				port: self.port,
+				path: self.path.to_string (),
+			};
 
-		let mut http_connection =
-			HttpConnection::connect (
-				address.to_string (),
-				Some (self.port),
-				self.secure,
-				self.address.to_string (),
-			).map_err (
-				|error|
+		let mut connect_address =
+			address.to_string ();
 
-				match error {
+		let mut connect_port =
+			match self.connect_to {
+				Some ((_, connect_port)) => connect_port,
+				None => self.port,
+			};
 
-					HttpError::InvalidUri =>
-						RequestError::InvalidUri,
+		let mut method =
+			self.method;
 
-					HttpError::Timeout =>
-						RequestError::Timeout,
+		let mut body =
+			self.body.clone ();
 
-					HttpError::Unknown (error) =>
-						RequestError::ConnectionError (
-							error.description ().to_string ()),
+		let mut redirect_chain: Vec <String> =
+			vec! [ url.to_string () ];
 
-				}
+		let mut redirect_to: Option <String> =
+			None;
 
-			) ?;
+		let mut redirect_not_followed: Option <String> =
+			None;
 
-		// perform request
+		let mut redirect_duration =
+			Duration::new (0, 0);
 
-		let http_response =
-			self.perform_request (
-				& mut http_connection,
-			).map_err (
-				|error|
+		let (mut http_connection, http_response) = loop {
 
-				match error {
+			// connect
 
-					HttpError::InvalidUri =>
-						RequestError::InvalidUri,
+			let mut http_connection =
+				HttpConnection::connect_to (
+					connect_address.to_string (),
+					connect_port,
+					Some (url.port),
+					url.secure,
+					url.hostname.to_string (),
+					& self.tls_options,
+					None,
+				) ?;
 
-					HttpError::Timeout =>
-						RequestError::Timeout,
+			// perform request
 
-					HttpError::Unknown (error) =>
-						RequestError::ConnectionError (
-							error.description ().to_string ()),
+			let http_response =
+				self.perform_request (
+					& mut http_connection,
+					& url,
+					method,
+					& body,
+				) ?;
 
-				}
+			// follow redirect
 
-			) ?;
+			let location =
+				match redirect_location (& http_response) {
+					Some (location) => location,
+					None => break (http_connection, http_response),
+				};
 
-		// process response
+			let next_url =
+				url.resolve (
+					& location,
+				).map_err (
+					|error|
+					RequestError::OtherError (
+						format! (
+							"invalid redirect to {}: {}",
+							location,
+							error)),
+				) ?;
 
-		self.process_response (
-			& http_connection,
-			& http_response,
-		)
+			if redirect_to.is_none () {
 
-	}
+				redirect_to =
+					Some (next_url.to_string ());
 
-	fn perform_request (
-		& self,
-		connection: & mut HttpConnection,
-	) -> HttpResult <HttpResponse> {
+			}
 
-		let http_request =
-			HttpRequest {
+			if redirect_chain.len () as u64 > self.follow_redirects {
 
-			method: self.method,
-			path: self.path.to_string (),
-			headers: self.send_headers.clone (),
+				redirect_not_followed =
+					Some (next_url.to_string ());
 
-		};
+				break (http_connection, http_response);
 
-		connection.perform (
-			http_request,
-			self.timeout,
-		)
+			}
 
-	}
+			redirect_duration +=
+				http_connection.connect_duration ()
+				+ http_response.duration ();
 
-	fn process_response (
-		& self,
-		http_connection: & HttpConnection,
-		http_response: & HttpResponse,
-	) -> RequestResult {
+			// a different host needs a fresh lookup, the same host is
+			// contacted again at the address we are checking
 
-		let mut success =
+			connect_address =
+				if next_url.hostname == self.hostname {
+					address.to_string ()
+				} else {
+					self.lookup_redirect_address (
+						address,
+						& next_url.hostname,
+					) ?
+				};
+
+			// the connect to port only replaces the original port
+
+			let same_origin =
+				next_url.hostname == self.hostname
+				&& next_url.port == self.port;
+
+			connect_port =
+				match self.connect_to {
+					Some ((_, connect_port)) if same_origin => connect_port,
+					_ => next_url.port,
+				};
+
+			// see and other, and the historical behaviour of moved and found,
+			// turn the request into a plain get
+
+			match http_response.status_code () {
+
+				301 | 302 if method == HttpMethod::Post => {
+					method = HttpMethod::Get;
+					body = Vec::new ();
+				},
+
+				303 if method != HttpMethod::Head => {
+					method = HttpMethod::Get;
+					body = Vec::new ();
+				},
+
+				_ => (),
+
+			}
+
+			redirect_chain.push (
+				next_url.to_string ());
+
+			url =
+				next_url;
+
+		};
+
+		// process response
+
+		let mut success =
+			self.process_response (
+				& http_connection,
+				& http_response,
+				redirect_duration,
+			) ?;
+
+		// state what was actually contacted whenever it isn't simply the
+		// address being checked, in the same form as the lookup details
+
+		if self.connect_to.is_some () || connect_address != address {
+
+			let (peer_host, peer_port) =
+				match http_connection.peer_address () {
+
+				Some (peer_address) => (
+					peer_address.ip ().to_string (),
+					peer_address.port () as u64,
+				),
+
+				None => (
+					connect_address.to_string (),
+					connect_port,
+				),
+
+			};
+
+			success.messages.insert (
+				0,
+				format! (
+					"connected to {} port {}",
+					peer_host,
+					peer_port));
+
+		}
+
+		self.check_redirects (
+			& mut success,
+			& url,
+			& redirect_chain,
+			& redirect_to,
+			& redirect_not_followed,
+		);
+
+		if self.request_count > 1 {
+
+			self.perform_repeated_requests (
+				& mut success,
+				& mut http_connection,
+				& http_response,
+				& url,
+				method,
+				& body,
+				& connect_address,
+				connect_port);
+
+		}
+
+		Ok (success)
+
+	}
+
+	// redirects to other hosts keep to the address family, and prefer the
+	// family of the address being checked
+
+	fn lookup_redirect_address (
+		& self,
+		address: & str,
+		hostname: & str,
+	) -> Result <String, RequestError> {
+
+		let (_, ipv4_addresses, ipv6_addresses) =
+			self.perform_hostname_lookup (
+				hostname);
+
+		let mut addresses: Vec <String> =
+			if address.contains (':') {
+				ipv6_addresses.into_iter ().chain (
+					ipv4_addresses.into_iter (),
+				).collect ()
+			} else {
+				ipv4_addresses.into_iter ().chain (
+					ipv6_addresses.into_iter (),
+				).collect ()
+			};
+
+		if addresses.is_empty () {
+
+			return Err (
+				RequestError::OtherError (
+					format! (
+						"unable to resolve redirect host {}",
+						hostname)));
+
+		}
+
+		Ok (addresses.remove (0))
+
+	}
+
+	fn perform_request (
+		& self,
+		connection: & mut HttpConnection,
+		url: & RequestUrl,
+		method: HttpMethod,
+		body: & [u8],
+	) -> HttpResult <HttpResponse> {
+
+		let mut headers =
+			self.send_headers.clone ();
+
+		// a host header given on the command line only applies to the
+		// original host
+
+		if url.hostname != self.hostname {
+
+			headers.retain (
+				|& (ref header_name, _)|
+				header_name.to_lowercase () != "host");
+
+		}
+
+		if let Some (ref content_type) = self.content_type {
+
+			if ! body.is_empty () {
+
+				headers.push ((
+					"Content-Type".to_string (),
+					content_type.to_string ()));
+
+			}
+
+		}
+
+		let http_request =
+			HttpRequest {
+
+			method: method,
+			path: url.path.to_string (),
+			headers: headers,
+			body: body.to_vec (),
+
+		};
+
+		connection.perform (
+			http_request,
+			self.timeout,
+		)
+
+	}
+
+	fn process_response (
+		& self,
+		http_connection: & HttpConnection,
+		http_response: & HttpResponse,
+		redirect_duration: Duration,
+	) -> RequestResult {
+
+		let mut success =
 			RequestSuccess {
 				check_status: CheckStatus::Ok,
-				duration: http_response.duration (),
+				durations: vec! [
+					http_response.duration (),
+				],
 				messages: Vec::new (),
+				details: Vec::new (),
 			};
 
 		let total_duration =
 			http_connection.connect_duration ()
 			+ http_response.duration ();
 
-		if (self.secure) {
-
-			let tls_duration =
-				http_connection.tls_duration ().unwrap ();
+		if let Some (tls_duration) = http_connection.tls_duration () {
 
 			let tcp_duration =
 				http_connection.connect_duration () - tls_duration;
@@ -698,6 +1385,19 @@ impl CheckHttpInstance {
 
 		}
 
+		// redirects are reported on their own, so the response time
+		// statistics only cover the final url
+
+		if redirect_duration > Duration::new (0, 0) {
+
+			success.messages.push (
+				format! (
+					"redirects took {}",
+					check_helper::display_duration_long (
+						& redirect_duration)));
+
+		}
+
 		self.check_response (
 			& mut success,
 			http_connection,
@@ -714,6 +1414,281 @@ impl CheckHttpInstance {
 
 	}
 
+	// sends the rest of --request-count to the final url, reusing the
+	// connection unless the server asks to close it
+
+	fn perform_repeated_requests (
+		& self,
+		result: & mut RequestSuccess,
+		http_connection: & mut HttpConnection,
+		first_response: & HttpResponse,
+		url: & RequestUrl,
+		method: HttpMethod,
+		body: & [u8],
+		connect_address: & str,
+		connect_port: u64,
+	) {
+
+		let mut connection_closed =
+			response_closes_connection (
+				first_response);
+
+		let mut num_reconnects: u64 = 0;
+
+		'requests: for request_index in 1 .. self.request_count {
+
+			// servers may drop an idle connection without saying so, so a
+			// failure on a reused connection is retried once on a new one
+
+			let mut retry_allowed =
+				! connection_closed;
+
+			let http_response = loop {
+
+				if connection_closed {
+
+					* http_connection =
+						match HttpConnection::connect_to (
+							connect_address.to_string (),
+							connect_port,
+							Some (url.port),
+							url.secure,
+							url.hostname.to_string (),
+							& self.tls_options,
+							None,
+						) {
+
+						Ok (http_connection) =>
+							http_connection,
+
+						Err (error) => {
+
+							result.messages.push (
+								format! (
+									"reconnect for request {} failed: {} (critical)",
+									request_index + 1,
+									error.description ()));
+
+							result.check_status.update (
+								CheckStatus::Critical);
+
+							break 'requests;
+
+						},
+
+					};
+
+					num_reconnects += 1;
+
+				}
+
+				match self.perform_request (
+					http_connection,
+					url,
+					method,
+					body,
+				) {
+
+					Ok (http_response) =>
+						break http_response,
+
+					Err (_) if retry_allowed => {
+
+						retry_allowed = false;
+						connection_closed = true;
+
+					},
+
+					Err (error) => {
+
+						result.messages.push (
+							format! (
+								"request {} failed: {} (critical)",
+								request_index + 1,
+								error.description ()));
+
+						result.check_status.update (
+							CheckStatus::Critical);
+
+						break 'requests;
+
+					},
+
+				}
+
+			};
+
+			if http_response.status_code () != first_response.status_code () {
+
+				result.messages.push (
+					format! (
+						"request {} returned status {} instead of {} (critical)",
+						request_index + 1,
+						http_response.status_code (),
+						first_response.status_code ()));
+
+				result.check_status.update (
+					CheckStatus::Critical);
+
+			}
+
+			result.durations.push (
+				http_response.duration ());
+
+			connection_closed =
+				response_closes_connection (
+					& http_response);
+
+		}
+
+		let statistics: Vec <String> =
+			vec! [
+				ResponseTimeStatistic::Min,
+				ResponseTimeStatistic::Median,
+				ResponseTimeStatistic::P95,
+				ResponseTimeStatistic::Max,
+			].into_iter ().filter_map (
+				|statistic|
+				statistic.select (
+					& result.durations,
+				).map (
+					|duration|
+					format! (
+						"{} {}",
+						statistic.name (),
+						check_helper::display_duration_short (
+							& duration))
+				)
+			).collect ();
+
+		result.messages.push (
+			format! (
+				"{} requests ({})",
+				result.durations.len (),
+				statistics.join (", ")));
+
+		if num_reconnects == 0 {
+
+			result.messages.push (
+				"connection kept alive".to_string ());
+
+		} else {
+
+			result.messages.push (
+				format! (
+					"connection closed by server, reconnected {} {}",
+					num_reconnects,
+					if num_reconnects == 1 { "time" } else { "times" }));
+
+		}
+
+	}
+
+	fn check_redirects (
+		& self,
+		result: & mut RequestSuccess,
+		final_url: & RequestUrl,
+		redirect_chain: & [String],
+		redirect_to: & Option <String>,
+		redirect_not_followed: & Option <String>,
+	) {
+
+		if redirect_chain.len () > 1 {
+
+			result.messages.push (
+				format! (
+					"followed {} {}: {}",
+					redirect_chain.len () - 1,
+					if redirect_chain.len () == 2 { "redirect" } else { "redirects" },
+					redirect_chain.join (" -> ")));
+
+		}
+
+		// without --follow-redirects a redirect is simply the response
+
+		if let Some (ref redirect_not_followed) = * redirect_not_followed {
+
+			if self.follow_redirects > 0 {
+
+				result.messages.push (
+					format! (
+						"redirect limit of {} reached, not following redirect \
+						to {}",
+						self.follow_redirects,
+						redirect_not_followed));
+
+			}
+
+		}
+
+		if let Some (ref expect_redirect_to) = self.expect_redirect_to {
+
+			match * redirect_to {
+
+				Some (ref redirect_to) if redirect_to == expect_redirect_to =>
+					result.messages.push (
+						format! (
+							"redirected to {}",
+							redirect_to)),
+
+				Some (ref redirect_to) => {
+
+					result.messages.push (
+						format! (
+							"redirected to {} instead of {} (critical)",
+							redirect_to,
+							expect_redirect_to));
+
+					result.check_status.update (
+						CheckStatus::Critical);
+
+				},
+
+				None => {
+
+					result.messages.push (
+						format! (
+							"no redirect to {} (critical)",
+							expect_redirect_to));
+
+					result.check_status.update (
+						CheckStatus::Critical);
+
+				},
+
+			}
+
+		}
+
+		if let Some (ref expect_final_url) = self.expect_final_url {
+
+			let final_url =
+				final_url.to_string ();
+
+			if & final_url == expect_final_url {
+
+				result.messages.push (
+					format! (
+						"final url {}",
+						final_url));
+
+			} else {
+
+				result.messages.push (
+					format! (
+						"final url {} instead of {} (critical)",
+						final_url,
+						expect_final_url));
+
+				result.check_status.update (
+					CheckStatus::Critical);
+
+			}
+
+		}
+
+	}
+
 	fn check_response (
 		& self,
 		success: & mut RequestSuccess,
@@ -893,29 +1868,31 @@ impl CheckHttpInstance {
 		http_response: & HttpResponse,
 	) -> Result <(), Box <error::Error>> {
 
-		if self.expect_body_text.is_some () {
+		self.check_response_body_size (
+			result,
+			http_response);
 
-			let expect_body_text =
-				self.expect_body_text.as_ref ().unwrap ();
+		if self.expect_body_text.is_some ()
+			|| ! self.expect_body_regex.is_empty ()
+			|| self.reject_body_text.is_some ()
+			|| self.reject_body_regex.is_some () {
 
 			let body_string =
-				http_response.body_string () ?;
-
-			if body_string.contains (
-				expect_body_text) {
-
-				result.messages.push (
-					"body text matched".to_string ());
+				response_body_text (
+					http_response,
+				) ?;
 
-			} else {
+			self.check_response_body_text (
+				result,
+				& body_string);
 
-				result.messages.push (
-					"body text not matched (critical)".to_string ());
+		}
 
-				result.check_status =
-					CheckStatus::Critical;
+		if ! self.expect_json.is_empty () {
 
-			}
+			self.check_response_body_json (
+				result,
+				http_response);
 
 		}
 
@@ -923,53 +1900,283 @@ impl CheckHttpInstance {
 
 	}
 
-	fn check_certificate_expiry (
+	fn check_response_body_size (
 		& self,
 		result: & mut RequestSuccess,
-		http_connection: & HttpConnection,
-	) -> Result <(), Box <error::Error>> {
+		http_response: & HttpResponse,
+	) {
 
-		if let Some (certificate_expiry) =
-			http_connection.certificate_expiry () {
+		let body_size =
+			http_response.body ().len () as u64;
 
-			let now =
-				Utc::now ().naive_utc ();
+		if let Some (body_max_size) = self.body_max_size {
 
-			let remaining_time =
-				certificate_expiry.signed_duration_since (
-					now);
+			if body_size > body_max_size {
 
-			if remaining_time < ChronoDuration::days (5) {
+				result.messages.push (
+					format! (
+						"body size {} bytes above maximum {} (critical)",
+						body_size,
+						body_max_size));
 
 				result.check_status.update (
 					CheckStatus::Critical);
 
+			} else {
+
 				result.messages.push (
 					format! (
-						"certificate expires in {} hours",
-						remaining_time.num_hours ()));
+						"body size {} bytes within maximum {}",
+						body_size,
+						body_max_size));
+
+			}
+
+		}
+
+		if let Some (body_min_size) = self.body_min_size {
 
-			} else if remaining_time < ChronoDuration::weeks (1) {
+			if body_size < body_min_size {
+
+				result.messages.push (
+					format! (
+						"body size {} bytes below minimum {} (critical)",
+						body_size,
+						body_min_size));
 
 				result.check_status.update (
-					CheckStatus::Warning);
+					CheckStatus::Critical);
+
+			} else {
+
+				result.messages.push (
+					format! (
+						"body size {} bytes within minimum {}",
+						body_size,
+						body_min_size));
+
+			}
+
+		}
+
+	}
+
+	fn check_response_body_text (
+		& self,
+		result: & mut RequestSuccess,
+		body_string: & str,
+	) {
+
+		if let Some (ref expect_body_text) = self.expect_body_text {
+
+			if body_string.contains (
+				expect_body_text.as_str ()) {
+
+				result.messages.push (
+					"body text matched".to_string ());
+
+			} else {
+
+				result.messages.push (
+					"body text not matched (critical)".to_string ());
+
+				result.check_status.update (
+					CheckStatus::Critical);
+
+			}
+
+		}
+
+		for expect_body_regex in self.expect_body_regex.iter () {
+
+			if expect_body_regex.is_match (
+				body_string) {
+
+				result.messages.push (
+					format! (
+						"body matched /{}/",
+						expect_body_regex.as_str ()));
+
+			} else {
+
+				result.messages.push (
+					format! (
+						"body did not match /{}/ (critical)",
+						expect_body_regex.as_str ()));
+
+				result.check_status.update (
+					CheckStatus::Critical);
+
+			}
+
+		}
+
+		if let Some (ref reject_body_text) = self.reject_body_text {
+
+			if body_string.contains (
+				reject_body_text.as_str ()) {
+
+				result.messages.push (
+					format! (
+						"body contains rejected text '{}' (critical)",
+						reject_body_text));
+
+				result.check_status.update (
+					CheckStatus::Critical);
+
+			} else {
 
 				result.messages.push (
 					format! (
-						"certificate expires in {} days (warning)",
-						remaining_time.num_days ()));
+						"body does not contain '{}'",
+						reject_body_text));
+
+			}
+
+		}
+
+		if let Some (ref reject_body_regex) = self.reject_body_regex {
+
+			if reject_body_regex.is_match (
+				body_string) {
+
+				result.messages.push (
+					format! (
+						"body matched rejected /{}/ (critical)",
+						reject_body_regex.as_str ()));
+
+				result.check_status.update (
+					CheckStatus::Critical);
 
 			} else {
 
 				result.messages.push (
 					format! (
-						"certificate expires in {} weeks",
-						remaining_time.num_weeks ()));
+						"body does not match /{}/",
+						reject_body_regex.as_str ()));
+
+			}
+
+		}
+
+	}
+
+	fn check_response_body_json (
+		& self,
+		result: & mut RequestSuccess,
+		http_response: & HttpResponse,
+	) {
+
+		let body_json: serde_json::Value =
+			match serde_json::from_slice (
+				http_response.body (),
+			) {
+
+			Ok (body_json) =>
+				body_json,
+
+			Err (error) => {
+
+				result.messages.push (
+					format! (
+						"body is not valid json: {} (critical)",
+						error));
+
+				result.check_status.update (
+					CheckStatus::Critical);
+
+				return;
+
+			},
+
+		};
+
+		for json_assertion in self.expect_json.iter () {
+
+			match json_assertion.select (& body_json) {
+
+				Some (actual_value)
+					if actual_value == & json_assertion.value => {
+
+					result.messages.push (
+						format! (
+							"json {} is {}",
+							json_assertion.path_string,
+							actual_value));
+
+				},
+
+				Some (actual_value) => {
+
+					result.messages.push (
+						format! (
+							"json {} is {} instead of {} (critical)",
+							json_assertion.path_string,
+							actual_value,
+							json_assertion.value));
+
+					result.check_status.update (
+						CheckStatus::Critical);
+
+				},
+
+				None => {
+
+					result.messages.push (
+						format! (
+							"json {} not found (critical)",
+							json_assertion.path_string));
+
+					result.check_status.update (
+						CheckStatus::Critical);
+
+				},
 
 			}
 
 		}
 
+	}
+
+	fn check_certificate_expiry (
+		& self,
+		result: & mut RequestSuccess,
+		http_connection: & HttpConnection,
+	) -> Result <(), Box <error::Error>> {
+
+		let certificate_chain =
+			inspect_certificate_chain (
+				http_connection.peer_certificates ());
+
+		// plain http has no chain, which only matters if we expected one
+
+		if certificate_chain.is_empty ()
+			&& self.expect_cert_names.is_empty ()
+			&& self.expect_cert_issuer.is_none () {
+
+			return Ok (());
+
+		}
+
+		let (status_messages, details) =
+			check_helper::check_certificate_chain (
+				& certificate_chain,
+				self.cert_expiry_warning,
+				self.cert_expiry_critical,
+				& self.expect_cert_names,
+				& self.expect_cert_issuer,
+				Utc::now ().naive_utc ());
+
+		for status_message in status_messages {
+
+			result.add_message (
+				status_message);
+
+		}
+
+		result.details.extend (
+			details);
+
 		Ok (())
 
 	}
@@ -978,21 +2185,208 @@ impl CheckHttpInstance {
 		& self,
 		check_result_builder: & mut CheckResultBuilder,
 		durations: & [Duration],
+		all_durations: & [Duration],
 	) {
 
 		if let Some (max_duration) =
 			durations.iter ().max () {
 
-			check_helper::check_duration_less_than (
+			check_helper::check_duration_in_range (
 				check_result_builder,
 				& self.response_time_warning,
 				& self.response_time_critical,
-				& format! (
-					"slowest request took {}",
-					check_helper::display_duration_long (
-						& max_duration)),
+				& match self.response_time_statistic {
+
+					ResponseTimeStatistic::Max =>
+						format! (
+							"slowest request took {}",
+							check_helper::display_duration_long (
+								& max_duration)),
+
+					statistic =>
+						format! (
+							"slowest {} response time {}",
+							statistic.name (),
+							check_helper::display_duration_long (
+								& max_duration)),
+
+				},
 				& max_duration);
 
+			check_result_builder.performance_data (
+				PerformanceData::new (
+					"response_time",
+					check_helper::duration_as_seconds (
+						& max_duration),
+					PerformanceDataUnit::Seconds,
+				).warning_range (
+					self.response_time_warning,
+				).critical_range (
+					self.response_time_critical,
+				).minimum (
+					0.0,
+				));
+
+		}
+
+		// statistics over every request to every host
+
+		if self.request_count > 1 {
+
+			for statistic in vec! [
+				ResponseTimeStatistic::Min,
+				ResponseTimeStatistic::Median,
+				ResponseTimeStatistic::P95,
+				ResponseTimeStatistic::Max,
+			] {
+
+				if let Some (duration) =
+					statistic.select (
+						all_durations) {
+
+					check_result_builder.performance_data (
+						PerformanceData::new (
+							format! (
+								"response_time_{}",
+								statistic.name ()),
+							check_helper::duration_as_seconds (
+								& duration),
+							PerformanceDataUnit::Seconds,
+						).minimum (
+							0.0,
+						));
+
+				}
+
+			}
+
+		}
+
+	}
+
+}
+
+#[ derive (Clone, Copy, Debug, PartialEq) ]
+enum AddressFamily {
+	Ipv4,
+	Ipv6,
+	Both,
+}
+
+impl AddressFamily {
+
+	fn includes_ipv4 (
+		& self,
+	) -> bool {
+		* self != AddressFamily::Ipv6
+	}
+
+	fn includes_ipv6 (
+		& self,
+	) -> bool {
+		* self != AddressFamily::Ipv4
+	}
+
+}
+
+// ---------- response time statistics
+
+#[ derive (Clone, Copy, Debug, PartialEq) ]
+enum ResponseTimeStatistic {
+	Min,
+	Median,
+	P95,
+	Max,
+}
+
+impl ResponseTimeStatistic {
+
+	fn name (
+		& self,
+	) -> & 'static str {
+
+		match * self {
+			ResponseTimeStatistic::Min => "min",
+			ResponseTimeStatistic::Median => "median",
+			ResponseTimeStatistic::P95 => "p95",
+			ResponseTimeStatistic::Max => "max",
+		}
+
+	}
+
+	// percentiles use the nearest rank, so the result is always one of the
+	// measured durations
+
+	fn select (
+		& self,
+		durations: & [Duration],
+	) -> Option <Duration> {
+
+		if durations.is_empty () {
+			return None;
+		}
+
+		let mut sorted_durations =
+			durations.to_vec ();
+
+		sorted_durations.sort ();
+
+		let percent =
+			match * self {
+				ResponseTimeStatistic::Min => 0,
+				ResponseTimeStatistic::Median => 50,
+				ResponseTimeStatistic::P95 => 95,
+				ResponseTimeStatistic::Max => 100,
+			};
+
+		let rank =
+			(sorted_durations.len () * percent + 99) / 100;
+
+		Some (
+			sorted_durations [
+				if rank > 0 { rank - 1 } else { 0 }
+			]
+		)
+
+	}
+
+}
+
+impl arg_helper::EnumArg for ResponseTimeStatistic {
+
+	fn from_string (
+		string_value: & str,
+	) -> Option <ResponseTimeStatistic> {
+
+		match string_value {
+
+			"min" => Some (ResponseTimeStatistic::Min),
+			"median" => Some (ResponseTimeStatistic::Median),
+			"p95" => Some (ResponseTimeStatistic::P95),
+			"max" => Some (ResponseTimeStatistic::Max),
+
+			_ => None,
+
+		}
+
+	}
+
+}
+
+impl arg_helper::EnumArg for AddressFamily {
+
+	fn from_string (
+		string_value: & str,
+	) -> Option <AddressFamily> {
+
+		match string_value {
+
+			"ipv4" => Some (AddressFamily::Ipv4),
+			"ipv6" => Some (AddressFamily::Ipv6),
+			"both" => Some (AddressFamily::Both),
+
+			_ => None,
+
 		}
 
 	}
@@ -1001,13 +2395,32 @@ impl CheckHttpInstance {
 
 struct RequestSuccess {
 	check_status: CheckStatus,
-	duration: Duration,
+	durations: Vec <Duration>,
 	messages: Vec <String>,
+	details: Vec <String>,
+}
+
+impl RequestSuccess {
+
+	fn add_message (
+		& mut self,
+		status_message: CheckStatusMessage,
+	) {
+
+		self.check_status.update (
+			* status_message.status ());
+
+		self.messages.push (
+			status_message.message ().to_string ());
+
+	}
+
 }
 
 enum RequestError {
 	InvalidUri,
 	ConnectionError (String),
+	CertificateError (String),
 	Timeout,
 	OtherError (String),
 }
@@ -1015,63 +2428,1103 @@ enum RequestError {
 type RequestResult =
 	Result <RequestSuccess, RequestError>;
 
-fn parse_headers (
-	header_strings: Vec <String>,
-) -> Result <Vec <(String, String)>, Box <error::Error>> {
+impl From <HttpError> for RequestError {
 
-	let mut header_tuples: Vec <(String, String)> =
-		vec! [];
+	fn from (
+		error: HttpError,
+	) -> RequestError {
 
-	for header_string in header_strings.iter () {
+		match error {
 
-		header_tuples.push (
-			parse_header (
-				header_string,
-			) ?,
-		);
+			HttpError::InvalidUri =>
+				RequestError::InvalidUri,
 
-	}
+			HttpError::Timeout =>
+				RequestError::Timeout,
 
-	Ok (header_tuples)
+			HttpError::Certificate (reason) =>
+				RequestError::CertificateError (reason),
 
-}
+			HttpError::Unknown (error) =>
+				RequestError::ConnectionError (
+					error.description ().to_string ()),
 
-fn parse_header (
-	header_string: & str,
-) -> Result <(String, String), Box <error::Error>> {
+		}
 
-	let split_position =
-		match header_string.find (
-			':') {
+	}
 
-		Some (pos) => pos,
+}
 
-		None =>
-			return Err (
+// ---------- json assertions
 
-			Box::new (
-				SimpleError::from (
-					"Header strings must be 'name:value' format"))
+// selects a value with a simple subset of jsonpath, eg "$.items[0].status",
+// and compares it to a json value, or a plain string if it is not valid json
 
-		),
+#[ derive (Clone, Debug) ]
+struct JsonAssertion {
+	path_string: String,
+	path: Vec <JsonPathSegment>,
+	value: serde_json::Value,
+}
 
-	};
+#[ derive (Clone, Debug, PartialEq) ]
+enum JsonPathSegment {
+	Key (String),
+	Index (usize),
+}
 
-	let (name_raw, rest_raw) =
-		header_string.split_at (
-			split_position);
+impl JsonAssertion {
 
-	let value_raw =
-		& rest_raw [1..];
+	fn parse (
+		assertion_string: & str,
+	) -> Result <JsonAssertion, String> {
 
-	Ok (
+		let mut assertion_parts =
+			assertion_string.splitn (2, '=');
 
-		(
-			name_raw.trim ().to_string (),
-			value_raw.trim ().to_string (),
+		let path_string =
+			assertion_parts.next ().unwrap ().trim ();
+
+		let value_string =
+			assertion_parts.next ().ok_or_else (
+				|| format! (
+					"Invalid json assertion, expected PATH=VALUE: {}",
+					assertion_string),
+			) ?;
+
+		Ok (JsonAssertion {
+
+			path_string:
+				path_string.to_string (),
+
+			path:
+				parse_json_path (
+					path_string,
+				).map_err (
+					|error|
+					format! (
+						"Invalid json path {}: {}",
+						path_string,
+						error),
+				) ?,
+
+			value:
+				serde_json::from_str (
+					value_string,
+				).unwrap_or_else (
+					|_| serde_json::Value::String (
+						value_string.to_string ()),
+				),
+
+		})
+
+	}
+
+	fn select <'a> (
+		& self,
+		value: & 'a serde_json::Value,
+	) -> Option <& 'a serde_json::Value> {
+
+		let mut current =
+			value;
+
+		for segment in self.path.iter () {
+
+			let next =
+				match * segment {
+
+				JsonPathSegment::Key (ref key) =>
+					current.as_object ().and_then (
+						|object| object.get (key),
+					),
+
+				JsonPathSegment::Index (index) =>
+					current.as_array ().and_then (
+						|array| array.get (index),
+					),
+
+			};
+
+			current =
+				match next {
+					Some (next) => next,
+					None => return None,
+				};
+
+		}
+
+		Some (current)
+
+	}
+
+}
+
+fn parse_json_path (
+	path_string: & str,
+) -> Result <Vec <JsonPathSegment>, String> {
+
+	let mut segments: Vec <JsonPathSegment> =
+		Vec::new ();
+
+	let mut remaining =
+		if path_string.starts_with ('$') {
+			& path_string [1 ..]
+		} else if path_string.starts_with ('.') || path_string.starts_with ('[') {
+			path_string
+		} else {
+			return parse_json_path (
+				& format! (".{}", path_string));
+		};
+
+	while ! remaining.is_empty () {
+
+		if remaining.starts_with ('.') {
+
+			let key_end =
+				remaining [1 ..].find (
+					|character: char| character == '.' || character == '[',
+				).map (
+					|position| position + 1,
+				).unwrap_or (
+					remaining.len (),
+				);
+
+			let key =
+				& remaining [1 .. key_end];
+
+			if key.is_empty () {
+				return Err ("empty key".to_string ());
+			}
+
+			segments.push (
+				JsonPathSegment::Key (
+					key.to_string ()));
+
+			remaining =
+				& remaining [key_end ..];
+
+		} else if remaining.starts_with ('[') {
+
+			let bracket_end =
+				remaining.find (']').ok_or_else (
+					|| "unterminated '['".to_string (),
+				) ?;
+
+			let subscript =
+				& remaining [1 .. bracket_end];
+
+			if subscript.len () >= 2
+				&& (subscript.starts_with ('\'') && subscript.ends_with ('\'')
+					|| subscript.starts_with ('"') && subscript.ends_with ('"')) {
+
+				segments.push (
+					JsonPathSegment::Key (
+						subscript [1 .. subscript.len () - 1].to_string ()));
+
+			} else {
+
+				segments.push (
+					JsonPathSegment::Index (
+						subscript.parse ().map_err (
+							|_| format! (
+								"invalid array index: {}",
+								subscript),
+						) ?));
+
+			}
+
+			remaining =
+				& remaining [bracket_end + 1 ..];
+
+		} else {
+
+			return Err (
+				format! (
+					"unexpected '{}'",
+					remaining));
+
+		}
+
+	}
+
+	Ok (segments)
+
+}
+
+fn parse_json_assertions (
+	assertion_strings: Vec <String>,
+) -> Result <Vec <JsonAssertion>, Box <error::Error>> {
+
+	let mut json_assertions: Vec <JsonAssertion> =
+		Vec::new ();
+
+	for assertion_string in assertion_strings {
+
+		json_assertions.push (
+			JsonAssertion::parse (
+				& assertion_string,
+			) ?);
+
+	}
+
+	Ok (json_assertions)
+
+}
+
+// a response without a declared charset is treated as utf-8
+
+fn response_body_text (
+	http_response: & HttpResponse,
+) -> Result <String, Box <error::Error>> {
+
+	if http_response.body_encoding ().is_some () {
+
+		Ok (
+			http_response.body_string () ?
 		)
 
-	)
+	} else {
+
+		Ok (
+			String::from_utf8_lossy (
+				http_response.body (),
+			).into_owned ()
+		)
+
+	}
+
+}
+
+// ---------- redirects
+
+#[ derive (Clone, Debug, PartialEq) ]
+struct RequestUrl {
+	secure: bool,
+	hostname: String,
+	port: u64,
+	path: String,
+}
+
+impl RequestUrl {
+
+	fn default_port (
+		& self,
+	) -> u64 {
+		if self.secure { 443 } else { 80 }
+	}
+
+	fn to_string (
+		& self,
+	) -> String {
+
+		let hostname =
+			if self.hostname.contains (':') {
+				format! ("[{}]", self.hostname)
+			} else {
+				self.hostname.to_string ()
+			};
+
+		if self.port == self.default_port () {
+
+			format! (
+				"{}://{}{}",
+				if self.secure { "https" } else { "http" },
+				hostname,
+				self.path)
+
+		} else {
+
+			format! (
+				"{}://{}:{}{}",
+				if self.secure { "https" } else { "http" },
+				hostname,
+				self.port,
+				self.path)
+
+		}
+
+	}
+
+	// resolves a location header against this url
+
+	fn resolve (
+		& self,
+		location: & str,
+	) -> Result <RequestUrl, String> {
+
+		if location.starts_with ("//") {
+
+			return self.resolve (
+				& format! (
+					"{}:{}",
+					if self.secure { "https" } else { "http" },
+					location));
+
+		}
+
+		if location.starts_with ("/") {
+
+			return Ok (RequestUrl {
+				path: location.to_string (),
+				.. self.clone ()
+			});
+
+		}
+
+		if ! location.contains ("://") {
+
+			let base_path =
+				match self.path.split (|character: char|
+					character == '?' || character == '#'
+				).next ().unwrap ().rfind ('/') {
+					Some (position) => & self.path [0 .. position + 1],
+					None => "/",
+				};
+
+			return Ok (RequestUrl {
+				path: format! ("{}{}", base_path, location),
+				.. self.clone ()
+			});
+
+		}
+
+		let uri: Uri =
+			location.parse ().map_err (
+				|_| "invalid url".to_string (),
+			) ?;
+
+		let secure =
+			match uri.scheme () {
+				Some ("http") => false,
+				Some ("https") => true,
+				_ => return Err ("unsupported scheme".to_string ()),
+			};
+
+		let hostname =
+			uri.host ().ok_or_else (
+				|| "missing host".to_string (),
+			) ?.trim_matches (
+				|character: char| character == '[' || character == ']',
+			).to_string ();
+
+		let path =
+			match uri.query () {
+				Some (query) => format! ("{}?{}", uri.path (), query),
+				None => uri.path ().to_string (),
+			};
+
+		Ok (RequestUrl {
+			secure: secure,
+			hostname: hostname,
+			port: uri.port ().map (
+				|port| port as u64,
+			).unwrap_or (
+				if secure { 443 } else { 80 },
+			),
+			path: if path.is_empty () { "/".to_string () } else { path },
+		})
+
+	}
+
+}
+
+fn redirect_location (
+	http_response: & HttpResponse,
+) -> Option <String> {
+
+	match http_response.status_code () {
+
+		301 | 302 | 303 | 307 | 308 =>
+			http_response.headers ().iter ().find (
+				|& & (ref header_name, _)|
+				header_name.to_lowercase () == "location"
+			).map (
+				|& (_, ref header_value)|
+				header_value.trim ().to_string ()
+			),
+
+		_ =>
+			None,
+
+	}
+
+}
+
+fn lookup_addresses <Data: resolv::record::RecordData> (
+	resolver: & mut resolv::Resolver,
+	qualified_address: & str,
+	record_type: resolv::RecordType,
+	record_address: fn (& Data) -> IpAddr,
+) -> Vec <IpAddr> {
+
+	let mut addresses: Vec <IpAddr> =
+		Vec::new ();
+
+	if let Ok (mut response) =
+		resolver.query (
+			qualified_address.as_bytes (),
+			resolv::Class::IN,
+			record_type,
+		) {
+
+		for index in 0 .. response.get_section_count (
+			resolv::Section::Answer) {
+
+			if let Ok (record) =
+				response.get_record::<Data> (
+					resolv::Section::Answer,
+					index,
+				) {
+
+				addresses.push (
+					record_address (
+						& record.data));
+
+			}
+
+		}
+
+	}
+
+	addresses
+
+}
+
+fn split_addresses (
+	address_family: AddressFamily,
+	addresses: Vec <IpAddr>,
+) -> (Vec <String>, Vec <String>) {
+
+	let mut ipv4_addresses: Vec <String> =
+		Vec::new ();
+
+	let mut ipv6_addresses: Vec <String> =
+		Vec::new ();
+
+	for address in addresses {
+
+		match address {
+
+			IpAddr::V4 (address) =>
+				if address_family.includes_ipv4 () {
+					ipv4_addresses.push (address.to_string ());
+				},
+
+			IpAddr::V6 (address) =>
+				if address_family.includes_ipv6 () {
+					ipv6_addresses.push (address.to_string ());
+				},
+
+		}
+
+	}
+
+	ipv4_addresses.sort ();
+	ipv6_addresses.sort ();
+
+	(ipv4_addresses, ipv6_addresses)
+
+}
+
+fn check_address_count (
+	check_result_builder: & mut CheckResultBuilder,
+	family_name: & str,
+	expect_addresses: Option <u64>,
+	num_addresses: usize,
+) {
+
+	check_result_builder.performance_data (
+		PerformanceData::new (
+			format! (
+				"{}_addresses",
+				family_name.to_lowercase ()),
+			num_addresses as f64,
+			PerformanceDataUnit::None,
+		).critical_below (
+			expect_addresses.map (
+				|expect_addresses| expect_addresses as f64),
+		).minimum (
+			0.0,
+		));
+
+	if let Some (expect_addresses) = expect_addresses {
+
+		if (num_addresses as u64) < expect_addresses {
+
+			check_result_builder.critical (
+				format! (
+					"{} {} addresses (critical is below {})",
+					num_addresses,
+					family_name,
+					expect_addresses));
+
+		}
+
+	}
+
+}
+
+// hyper drops the connection after a response which asks to close it, so the
+// next request needs a new one
+
+fn response_closes_connection (
+	http_response: & HttpResponse,
+) -> bool {
+
+	http_response.headers ().iter ().any (
+		|& (ref header_name, ref header_value)|
+		header_name.to_lowercase () == "connection"
+		&& header_value.split (',').any (
+			|token| token.trim ().to_lowercase () == "close"))
+
+}
+
+fn parse_connect_to (
+	connect_to_string: Option <String>,
+) -> Result <Option <(String, u64)>, Box <error::Error>> {
+
+	let connect_to_string =
+		match connect_to_string {
+			Some (connect_to_string) => connect_to_string,
+			None => return Ok (None),
+		};
+
+	let port_position =
+		connect_to_string.rfind (':').ok_or_else (
+			|| format! (
+				"Invalid value for connect-to, expected HOST:PORT: {}",
+				connect_to_string),
+		) ?;
+
+	let connect_host_string =
+		& connect_to_string [0 .. port_position];
+
+	// ipv6 addresses must be bracketed, otherwise the port is ambiguous
+
+	let connect_host =
+		if connect_host_string.starts_with ('[')
+			&& connect_host_string.ends_with (']') {
+
+			& connect_host_string [1 .. connect_host_string.len () - 1]
+
+		} else if connect_host_string.contains (|character: char|
+			character == ':' || character == '[' || character == ']') {
+
+			""
+
+		} else {
+
+			connect_host_string
+
+		};
+
+	let connect_port: u64 =
+		match connect_to_string [port_position + 1 ..].parse () {
+
+		Ok (connect_port) if connect_port > 0 && connect_port < 65536 =>
+			connect_port,
+
+		_ =>
+			return Err (Box::new (
+				SimpleError::from (
+					format! (
+						"Invalid port for connect-to: {}",
+						connect_to_string)))),
+
+	};
+
+	if connect_host.is_empty () {
+
+		return Err (Box::new (
+			SimpleError::from (
+				format! (
+					"Invalid value for connect-to, expected HOST:PORT: {}",
+					connect_to_string))));
+
+	}
+
+	Ok (Some ((
+		connect_host.to_string (),
+		connect_port,
+	)))
+
+}
+
+fn parse_body (
+	options_matches: & getopts::Matches,
+) -> Result <Vec <u8>, Box <error::Error>> {
+
+	match (
+		options_matches.opt_str ("body"),
+		options_matches.opt_str ("body-file"),
+	) {
+
+		(Some (_), Some (_)) =>
+			Err (Box::new (
+				SimpleError::from (
+					"Can't specify both --body and --body-file"))),
+
+		(Some (body), None) =>
+			Ok (body.into_bytes ()),
+
+		(None, Some (body_file)) => {
+
+			let mut body =
+				Vec::new ();
+
+			File::open (
+				& body_file,
+			).and_then (
+				|mut file|
+				file.read_to_end (
+					& mut body)
+			).map_err (
+				|error|
+				format! (
+					"unable to read request body from {}: {}",
+					body_file,
+					error),
+			) ?;
+
+			Ok (body)
+
+		},
+
+		(None, None) =>
+			Ok (Vec::new ()),
+
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::error::Error;
+	use std::io::BufRead;
+	use std::io::BufReader;
+	use std::io::Write;
+	use std::net::IpAddr;
+	use std::net::TcpListener;
+	use std::thread;
+	use std::time::Duration;
+
+	use logic::*;
+	use logic::testing::*;
+
+	use super::AddressFamily;
+	use super::JsonAssertion;
+	use super::JsonPathSegment;
+	use super::RequestUrl;
+	use super::ResponseTimeStatistic;
+	use super::check_address_count;
+	use super::parse_connect_to;
+	use super::parse_json_path;
+	use super::split_addresses;
+
+	#[ test ]
+	fn json_path_segments () {
+
+		assert_eq! (
+			parse_json_path ("$.items[2]['content-type'].name").unwrap (),
+			vec! [
+				JsonPathSegment::Key ("items".to_string ()),
+				JsonPathSegment::Index (2),
+				JsonPathSegment::Key ("content-type".to_string ()),
+				JsonPathSegment::Key ("name".to_string ()),
+			]);
+
+		assert_eq! (
+			parse_json_path ("status").unwrap (),
+			vec! [
+				JsonPathSegment::Key ("status".to_string ()),
+			]);
+
+		assert! (
+			parse_json_path ("$.items[x]").is_err ());
+
+	}
+
+	#[ test ]
+	fn json_assertion_values () {
+
+		let body =
+			json! ({
+				"status": "ok",
+				"checks": [ { "healthy": true, "count": 3 } ],
+			});
+
+		let status =
+			JsonAssertion::parse ("$.status=ok").unwrap ();
+
+		assert_eq! (
+			status.select (& body),
+			Some (& json! ("ok")));
+
+		assert_eq! (
+			status.value,
+			json! ("ok"));
+
+		let healthy =
+			JsonAssertion::parse ("$.checks[0].healthy=true").unwrap ();
+
+		assert_eq! (
+			healthy.select (& body),
+			Some (& healthy.value));
+
+		let missing =
+			JsonAssertion::parse ("$.checks[1].count=3").unwrap ();
+
+		assert_eq! (
+			missing.select (& body),
+			None);
+
+		assert! (
+			JsonAssertion::parse ("$.status").is_err ());
+
+	}
+
+	#[ test ]
+	fn redirect_urls () {
+
+		let url =
+			RequestUrl {
+				secure: false,
+				hostname: "example.com".to_string (),
+				port: 80,
+				path: "/a/b?c=d".to_string (),
+			};
+
+		assert_eq! (
+			url.to_string (),
+			"http://example.com/a/b?c=d");
+
+		assert_eq! (
+			url.resolve ("https://www.example.com/").unwrap ().to_string (),
+			"https://www.example.com/");
+
+		assert_eq! (
+			url.resolve ("//www.example.com:8080/x").unwrap ().to_string (),
+			"http://www.example.com:8080/x");
+
+		assert_eq! (
+			url.resolve ("/login").unwrap ().to_string (),
+			"http://example.com/login");
+
+		assert_eq! (
+			url.resolve ("e").unwrap ().to_string (),
+			"http://example.com/a/e");
+
+		assert! (
+			url.resolve ("ftp://example.com/").is_err ());
+
+	}
+
+	#[ test ]
+	fn response_time_statistics () {
+
+		let durations: Vec <Duration> =
+			vec! [ 7, 3, 9, 1, 5, 2, 8, 4, 6, 10 ].into_iter ().map (
+				|millis| Duration::from_millis (millis)
+			).collect ();
+
+		for & (statistic, expected_millis) in [
+			(ResponseTimeStatistic::Min, 1),
+			(ResponseTimeStatistic::Median, 5),
+			(ResponseTimeStatistic::P95, 10),
+			(ResponseTimeStatistic::Max, 10),
+		].iter () {
+
+			assert_eq! (
+				statistic.select (& durations),
+				Some (Duration::from_millis (expected_millis)));
+
+		}
+
+		assert_eq! (
+			ResponseTimeStatistic::Median.select (& []),
+			None);
+
+	}
+
+	#[ test ]
+	fn connect_to_values () {
+
+		assert_eq! (
+			parse_connect_to (None).unwrap (),
+			None);
+
+		assert_eq! (
+			parse_connect_to (Some ("backend.example.com:8080".to_string ())).unwrap (),
+			Some (("backend.example.com".to_string (), 8080)));
+
+		assert_eq! (
+			parse_connect_to (Some ("192.0.2.1:443".to_string ())).unwrap (),
+			Some (("192.0.2.1".to_string (), 443)));
+
+		assert_eq! (
+			parse_connect_to (Some ("[2001:db8::1]:443".to_string ())).unwrap (),
+			Some (("2001:db8::1".to_string (), 443)));
+
+		for & (connect_to, expected_error) in [
+			("backend.example.com",
+				"Invalid value for connect-to, expected HOST:PORT: \
+				backend.example.com"),
+			("backend.example.com:",
+				"Invalid port for connect-to: backend.example.com:"),
+			("backend.example.com:http",
+				"Invalid port for connect-to: backend.example.com:http"),
+			("backend.example.com:65536",
+				"Invalid port for connect-to: backend.example.com:65536"),
+			(":443",
+				"Invalid value for connect-to, expected HOST:PORT: :443"),
+			("2001:db8::1:443",
+				"Invalid value for connect-to, expected HOST:PORT: \
+				2001:db8::1:443"),
+		].iter () {
+
+			assert_eq! (
+				parse_connect_to (
+					Some (connect_to.to_string ()),
+				).unwrap_err ().description (),
+				expected_error);
+
+		}
+
+	}
+
+	// answers a number of requests on each connection and then hangs up
+	// without warning, as a server does when a keep alive times out
+
+	fn start_http_server (
+		requests_per_connection: usize,
+		num_connections: usize,
+	) -> u16 {
+
+		let listener =
+			TcpListener::bind (
+				"127.0.0.1:0",
+			).unwrap ();
+
+		let port =
+			listener.local_addr ().unwrap ().port ();
+
+		thread::spawn (
+			move || {
+
+			for _ in 0 .. num_connections {
+
+				let (mut stream, _) =
+					listener.accept ().unwrap ();
+
+				let mut reader =
+					BufReader::new (
+						stream.try_clone ().unwrap ());
+
+				for _ in 0 .. requests_per_connection {
+
+					if ! read_request (& mut reader) {
+						break;
+					}
+
+					if stream.write_all (
+						b"HTTP/1.1 200 OK\r\n\
+						Content-Length: 2\r\n\
+						\r\n\
+						ok",
+					).is_err () {
+						break;
+					}
+
+				}
+
+			}
+
+		});
+
+		port
+
+	}
+
+	fn read_request (
+		reader: & mut BufRead,
+	) -> bool {
+
+		loop {
+
+			let mut line =
+				String::new ();
+
+			if reader.read_line (& mut line).unwrap_or (0) == 0 {
+				return false;
+			}
+
+			if line.trim_right ().is_empty () {
+				return true;
+			}
+
+		}
+
+	}
+
+	fn request_messages (
+		port: u16,
+	) -> (CheckStatus, String) {
+
+		let port =
+			port.to_string ();
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--address", "127.0.0.1",
+					"--port", & port,
+					"--request-count", "3",
+					"--timeout", "5s",
+				]);
+
+		(
+			* check_result.status (),
+			check_result.extra_information ().join ("\n"),
+		)
+
+	}
+
+	#[ test ]
+	fn repeated_requests_keep_alive () {
+
+		let (status, extra_information) =
+			request_messages (
+				start_http_server (3, 1));
+
+		assert_eq! (
+			status,
+			CheckStatus::Ok,
+			"{}",
+			extra_information);
+
+		assert! (
+			extra_information.contains (", 3 requests (min "),
+			"{}",
+			extra_information);
+
+		assert! (
+			extra_information.contains ("connection kept alive"),
+			"{}",
+			extra_information);
+
+	}
+
+	#[ test ]
+	fn repeated_requests_retry_dropped_connections () {
+
+		let (status, extra_information) =
+			request_messages (
+				start_http_server (1, 3));
+
+		assert_eq! (
+			status,
+			CheckStatus::Ok,
+			"{}",
+			extra_information);
+
+		assert! (
+			extra_information.contains (", 3 requests (min "),
+			"{}",
+			extra_information);
+
+		assert! (
+			extra_information.contains (
+				"connection closed by server, reconnected 2 times"),
+			"{}",
+			extra_information);
+
+	}
+
+	#[ test ]
+	fn address_families () {
+
+		let addresses: Vec <IpAddr> =
+			vec! [ "192.0.2.2", "2001:db8::1", "192.0.2.1" ].into_iter ().map (
+				|address| address.parse ().unwrap ()
+			).collect ();
+
+		assert_eq! (
+			split_addresses (AddressFamily::Both, addresses.clone ()),
+			(
+				vec! [ "192.0.2.1".to_string (), "192.0.2.2".to_string () ],
+				vec! [ "2001:db8::1".to_string () ],
+			));
+
+		assert_eq! (
+			split_addresses (AddressFamily::Ipv4, addresses.clone ()),
+			(
+				vec! [ "192.0.2.1".to_string (), "192.0.2.2".to_string () ],
+				vec! [],
+			));
+
+		assert_eq! (
+			split_addresses (AddressFamily::Ipv6, addresses),
+			(
+				vec! [],
+				vec! [ "2001:db8::1".to_string () ],
+			));
+
+	}
+
+	#[ test ]
+	fn address_counts () {
+
+		let plugin_provider =
+			super::new ();
+
+		let mut check_result_builder =
+			CheckResultBuilder::new ();
+
+		check_address_count (
+			& mut check_result_builder,
+			"IPv4",
+			Some (2),
+			2);
+
+		check_address_count (
+			& mut check_result_builder,
+			"IPv6",
+			None,
+			0);
+
+		let check_result =
+			check_result_builder.into_check_result (
+				& * plugin_provider);
+
+		assert_eq! (
+			check_result.status (),
+			& CheckStatus::Ok);
+
+		assert_performance_data (
+			& check_result,
+			& [ "ipv4_addresses=2;;2:;0", "ipv6_addresses=0;;;0" ]);
+
+		let mut check_result_builder =
+			CheckResultBuilder::new ();
+
+		check_address_count (
+			& mut check_result_builder,
+			"IPv6",
+			Some (2),
+			1);
+
+		let check_result =
+			check_result_builder.into_check_result (
+				& * plugin_provider);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [ "1 IPv6 addresses (critical is below 2)" ]);
+
+	}
 
 }
 
@@ -1,7 +1,7 @@
 extern crate getopts;
 
 use std::error;
-use std::fs;
+use std::path::Path;
 
 use logic::*;
 
@@ -65,15 +65,18 @@ check! {
 
 	},
 
-	perform = |self, plugin_provider, check_result_builder| {
+	perform = |self, plugin_provider, environment, check_result_builder| {
 
 		let container_path =
 			format! (
 				"/var/lib/lxc/{}",
 				self.container_name);
 
-		match fs::metadata (
-			container_path) {
+		environment.enter_phase (
+			"looking for container");
+
+		match environment.file_metadata (
+			Path::new (& container_path)) {
 
 			Err (_) => {
 
@@ -167,4 +170,75 @@ fn container_state_from_str (
 
 }
 
+#[ cfg (test) ]
+mod tests {
+
+	use logic::*;
+	use logic::testing::*;
+
+	#[ test ]
+	fn present () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"lxc-container/present",
+				& [
+					"--container-name", "web",
+					"--critical-state", "not-present",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Ok,
+			& [
+				"container web present",
+			]);
+
+	}
+
+	#[ test ]
+	fn present_is_critical () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"lxc-container/present",
+				& [
+					"--container-name", "web",
+					"--critical-state", "present",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [
+				"container web present",
+			]);
+
+	}
+
+	#[ test ]
+	fn not_present () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"lxc-container/present",
+				& [
+					"--container-name", "db",
+					"--critical-state", "not-present",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [
+				"container db not present",
+			]);
+
+	}
+
+}
+
 // ex: noet ts=4 filetype=rust
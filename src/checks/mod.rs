@@ -1,3 +1,5 @@
+use logic::*;
+
 pub mod apt;
 pub mod authsmtp;
 pub mod btrfs;
@@ -7,5 +9,24 @@ pub mod http;
 pub mod lxc_container;
 pub mod snapshots;
 pub mod systemd;
+pub mod tls;
+
+pub fn all_plugin_providers (
+) -> Vec <Box <PluginProvider>> {
+
+	vec! [
+		apt::new (),
+		authsmtp::new (),
+		btrfs::new (),
+		disk_space::new (),
+		generic::new (),
+		http::new (),
+		lxc_container::new (),
+		snapshots::new (),
+		systemd::new (),
+		tls::new (),
+	]
+
+}
 
 // ex: noet ts=4 filetype=rust
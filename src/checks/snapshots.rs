@@ -1,12 +1,12 @@
 #![ allow (unused_parens) ]
 
 extern crate getopts;
-extern crate glob;
 extern crate libc;
 extern crate time;
 
 use std::error;
 use std::time::Duration;
+use std::time::UNIX_EPOCH;
 
 use logic::*;
 use logic::check_helper::*;
@@ -161,18 +161,31 @@ check! {
 
 	},
 
-	perform = |self, plugin_provider, check_result_builder| {
+	perform = |self, plugin_provider, environment, check_result_builder| {
 
 		let now =
-			time::now ();
+			match environment.now ().duration_since (UNIX_EPOCH) {
+				Ok (duration) =>
+					time::at (
+						time::Timespec::new (
+							duration.as_secs () as i64,
+							duration.subsec_nanos () as i32)),
+				Err (_) =>
+					time::at (
+						time::Timespec::new (0, 0)),
+			};
 
 		// check for snapshots
 
 		let most_recent_local_snapshot = if (
 			self.local_pattern.is_some ()
 		) {
+			environment.enter_phase (
+				"looking for local snapshots");
+
 			Self::most_recent_snapshot (
-				self.local_pattern.as_ref ().unwrap ())
+				environment,
+				self.local_pattern.as_ref ().unwrap ()) ?
 		} else {
 			None
 		};
@@ -180,8 +193,12 @@ check! {
 		let most_recent_archive_snapshot = if (
 			self.archive_pattern.is_some ()
 		) {
+			environment.enter_phase (
+				"looking for archive snapshots");
+
 			Self::most_recent_snapshot (
-				self.archive_pattern.as_ref ().unwrap ())
+				environment,
+				self.archive_pattern.as_ref ().unwrap ()) ?
 		} else {
 			None
 		};
@@ -213,6 +230,13 @@ check! {
 					).unwrap ()),
 				& most_recent_time);
 
+			check_result_builder.performance_data (
+				age_performance_data (
+					"age",
+					& most_recent_time,
+					& self.warning_time,
+					& self.critical_time));
+
 			// local
 
 			if most_recent_local_snapshot.is_some () {
@@ -233,6 +257,13 @@ check! {
 						).unwrap ()),
 					& most_recent_local_time);
 
+				check_result_builder.performance_data (
+					age_performance_data (
+						"local_age",
+						& most_recent_local_time,
+						& self.local_warning_time,
+						& self.local_critical_time));
+
 			} else if self.local_pattern.is_some () {
 
 				if self.local_critical_time.is_some () {
@@ -274,6 +305,13 @@ check! {
 						).unwrap ()),
 					& most_recent_archive_time);
 
+				check_result_builder.performance_data (
+					age_performance_data (
+						"archive_age",
+						& most_recent_archive_time,
+						& self.archive_warning_time,
+						& self.archive_critical_time));
+
 			} else if self.archive_pattern.is_some () {
 
 				if self.archive_critical_time.is_some () {
@@ -327,8 +365,9 @@ check! {
 impl CheckSnapshotsInstance {
 
 	fn most_recent_snapshot (
+		environment: & Environment,
 		pattern: & str,
-	) -> Option <time::Tm> {
+	) -> Result <Option <time::Tm>, Box <error::Error>> {
 
 		let mut pattern_parts =
 			pattern.splitn (
@@ -349,50 +388,133 @@ impl CheckSnapshotsInstance {
 
 		let mut most_recent = None;
 
-		for path_result in (
-			glob::glob (
+		for path in (
+			environment.glob (
 				& pattern_glob,
-			).unwrap ()
+			) ?
 		) {
 
-			if let Ok (path) = path_result {
-
-				if let Some (path_string) = path.to_str () {
-
-					let date_string =
-						& path_string [
-							path_string.len ()
-							- pattern_suffix.len ()
-							- 10
-						..
-							path_string.len ()
-							- pattern_suffix.len ()
-						];
-
-					if let Ok (time) = (
-						time::strptime (
-							date_string,
-							"%Y-%m-%d")
-					) {
-
-						if let Some (most_recent_value) = most_recent {
-							if time < most_recent_value {
-								continue;
-							}
+			if let Some (path_string) = path.to_str () {
+
+				let date_string =
+					& path_string [
+						path_string.len ()
+						- pattern_suffix.len ()
+						- 10
+					..
+						path_string.len ()
+						- pattern_suffix.len ()
+					];
+
+				if let Ok (time) = (
+					time::strptime (
+						date_string,
+						"%Y-%m-%d")
+				) {
+
+					if let Some (most_recent_value) = most_recent {
+						if time < most_recent_value {
+							continue;
 						}
-
-						most_recent =
-							Some (time);
-
 					}
 
+					most_recent =
+						Some (time);
+
 				}
 
 			}
 
 		}
 
-		most_recent
+		Ok (most_recent)
+
+	}
+
+}
+
+fn age_performance_data (
+	label: & str,
+	age: & Duration,
+	warning_time: & Option <Duration>,
+	critical_time: & Option <Duration>,
+) -> PerformanceData {
+
+	PerformanceData::new (
+		label,
+		age.as_secs () as f64,
+		PerformanceDataUnit::Seconds,
+	).warning_above (
+		warning_time.map (
+			|warning_time| warning_time.as_secs () as f64),
+	).critical_above (
+		critical_time.map (
+			|critical_time| critical_time.as_secs () as f64),
+	).minimum (
+		0.0,
+	)
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use logic::*;
+	use logic::testing::*;
+
+	#[ test ]
+	fn old_archive () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"snapshots/daily",
+				& [
+					"--local-pattern", "/srv/snapshots/local/{date}",
+					"--archive-pattern", "/srv/snapshots/archive/{date}",
+					"--warning", "2d",
+					"--critical", "4d",
+					"--archive-warning", "7d",
+					"--archive-critical", "21d",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Warning,
+			& [
+				"snapshot on 2017-06-14",
+				"local snapshot on 2017-06-14",
+				"archive snapshot on 2017-06-01 (warning is above 7.00d)",
+			]);
+
+		assert_performance_data (
+			& check_result,
+			& [
+				"age=129600s;~:172800;~:345600;0",
+				"local_age=129600s;;;0",
+				"archive_age=1252800s;~:604800;~:1814400;0",
+			]);
+
+	}
+
+	#[ test ]
+	fn no_snapshots () {
+
+		let check_result =
+			run_with_fixture (
+				& * super::new (),
+				"snapshots/daily",
+				& [
+					"--local-pattern", "/srv/missing/{date}",
+					"--critical", "4d",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [
+				"no snapshots (critical)",
+			]);
 
 	}
 
@@ -12,7 +12,7 @@ use logic::*;
 check! {
 
 	new = new,
-	name = "systemd",
+	name = "check-systemd",
 	prefix = "SYSTEMD",
 
 	provider = CheckSystemdProvider,
@@ -30,7 +30,10 @@ check! {
 
 	},
 
-	perform = |self, plugin_provider, check_result_builder| {
+	perform = |self, plugin_provider, environment, check_result_builder| {
+
+		environment.enter_phase (
+			"connecting to systemd");
 
 		let systemd_connection =
 			Rc::new (RefCell::new (
@@ -42,6 +45,9 @@ check! {
 				systemd_connection,
 			);
 
+		environment.enter_phase (
+			"listing systemd units");
+
 		let mut systemd_units =
 			systemd_manager.list_units () ?;
 
@@ -114,6 +120,15 @@ check! {
 
 			}
 
+			check_result_builder.performance_data (
+				PerformanceData::new (
+					unit_state.as_str (),
+					num_units as f64,
+					PerformanceDataUnit::None,
+				).minimum (
+					0.0,
+				));
+
 			let num_units_other =
 				systemd_units.iter ().filter (
 					|systemd_unit|
@@ -0,0 +1,910 @@
+extern crate getopts;
+
+use std::error;
+use std::error::Error;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::time::Duration;
+
+use chrono::offset::Utc;
+
+use rustls;
+
+use logic::*;
+use lowlevel::http::*;
+
+// sent with EHLO, servers only use it for logging before STARTTLS
+
+const SMTP_CLIENT_NAME: & str =
+	"localhost";
+
+check! {
+
+	new = new,
+	name = "check-tls",
+	prefix = "TLS",
+
+	provider = CheckTlsProvider,
+
+	instance = CheckTlsInstance {
+
+		address: String,
+		port: u16,
+		hostname: String,
+		starttls: StartTls,
+
+		tls_options: HttpTlsOptions,
+
+		cert_expiry_warning: Duration,
+		cert_expiry_critical: Duration,
+
+		handshake_time_warning: Option <Duration>,
+		handshake_time_critical: Option <Duration>,
+
+		timeout: Duration,
+
+	},
+
+	options_spec = |options_spec| {
+
+		// connection
+
+		options_spec.reqopt (
+			"",
+			"address",
+			"address to connect to",
+			"ADDRESS");
+
+		options_spec.optopt (
+			"",
+			"port",
+			"port to connect to, defaults to 443, or the standard port for \
+			the starttls protocol",
+			"PORT");
+
+		options_spec.optopt (
+			"",
+			"hostname",
+			"hostname to send and verify, defaults to the address",
+			"HOSTNAME");
+
+		options_spec.optopt (
+			"",
+			"starttls",
+			"protocol to upgrade with STARTTLS: smtp, imap, pop3 or none \
+			(default)",
+			"PROTOCOL");
+
+		// tls
+
+		arg_helper::tls_options_spec (
+			& mut options_spec);
+
+		// certificate
+
+		options_spec.optopt (
+			"",
+			"cert-expiry-warning",
+			"warning threshold for certificate expiry, defaults to 1 week",
+			"DURATION");
+
+		options_spec.optopt (
+			"",
+			"cert-expiry-critical",
+			"critical threshold for certificate expiry, defaults to 5 days",
+			"DURATION");
+
+		// timings
+
+		options_spec.optopt (
+			"",
+			"handshake-time-warning",
+			"handshake time warning duration",
+			"DURATION");
+
+		options_spec.optopt (
+			"",
+			"handshake-time-critical",
+			"handshake time critical duration",
+			"DURATION");
+
+		options_spec.optopt (
+			"",
+			"timeout",
+			"maximum time to wait for the server",
+			"DURATION");
+
+	},
+
+	options_parse = |options_matches| {
+
+		let address =
+			arg_helper::parse_string_required (
+				options_matches,
+				"address",
+			) ?;
+
+		let starttls =
+			arg_helper::parse_enum (
+				options_matches,
+				"starttls",
+			) ?.unwrap_or (
+				StartTls::None,
+			);
+
+		let (cert_expiry_warning, cert_expiry_critical) =
+			arg_helper::parse_cert_expiry (
+				options_matches,
+			) ?;
+
+		CheckTlsInstance {
+
+			port:
+				arg_helper::parse_port_or_default (
+					options_matches,
+					"port",
+					starttls.default_port (),
+				) ?,
+
+			hostname:
+				arg_helper::parse_string_or_default (
+					options_matches,
+					"hostname",
+					& address,
+				) ?,
+
+			address:
+				address,
+
+			starttls:
+				starttls,
+
+			tls_options:
+				arg_helper::parse_tls_options (
+					options_matches,
+				) ?,
+
+			cert_expiry_warning:
+				cert_expiry_warning,
+
+			cert_expiry_critical:
+				cert_expiry_critical,
+
+			handshake_time_warning:
+				arg_helper::parse_duration (
+					options_matches,
+					"handshake-time-warning",
+				) ?,
+
+			handshake_time_critical:
+				arg_helper::parse_duration (
+					options_matches,
+					"handshake-time-critical",
+				) ?,
+
+			timeout:
+				arg_helper::parse_duration_or_default (
+					options_matches,
+					"timeout",
+					& Duration::new (60, 0),
+				) ?,
+
+		}
+
+	},
+
+	perform = |self, plugin_provider, environment, check_result_builder| {
+
+		self.check_tls (
+			& mut check_result_builder,
+			environment,
+		) ?;
+
+	},
+
+}
+
+impl CheckTlsInstance {
+
+	fn check_tls (
+		& self,
+		check_result_builder: & mut CheckResultBuilder,
+		environment: & Environment,
+	) -> Result <(), Box <error::Error>> {
+
+		// connect
+
+		environment.enter_phase (
+			& format! (
+				"connecting to {} port {}",
+				self.address,
+				self.port));
+
+		let mut tcp_stream =
+			match self.connect () {
+
+			Ok (tcp_stream) =>
+				tcp_stream,
+
+			Err (error) => {
+
+				check_result_builder.critical (
+					format! (
+						"unable to connect to {} port {}: {}",
+						self.address,
+						self.port,
+						error));
+
+				return Ok (());
+
+			},
+
+		};
+
+		tcp_stream.set_read_timeout (
+			Some (self.timeout),
+		) ?;
+
+		tcp_stream.set_write_timeout (
+			Some (self.timeout),
+		) ?;
+
+		// upgrade
+
+		if self.starttls != StartTls::None {
+
+			environment.enter_phase (
+				& format! (
+					"performing {} STARTTLS",
+					self.starttls.name ()));
+
+		}
+
+		if let Err (error) =
+			self.starttls.upgrade (
+				& tcp_stream) {
+
+			check_result_builder.critical (
+				format! (
+					"{} STARTTLS failed: {}",
+					self.starttls.name (),
+					error));
+
+			return Ok (());
+
+		}
+
+		// handshake
+
+		environment.enter_phase (
+			& format! (
+				"performing TLS handshake with {}",
+				self.hostname));
+
+		let tls_handshake =
+			match tls_handshake (
+				& mut tcp_stream,
+				& self.hostname,
+				& self.tls_options,
+			) {
+
+			Ok (tls_handshake) =>
+				tls_handshake,
+
+			Err (HttpError::Certificate (reason)) => {
+
+				check_result_builder.critical (
+					format! (
+						"certificate verification failed: {}",
+						reason));
+
+				return Ok (());
+
+			},
+
+			Err (error) => {
+
+				check_result_builder.critical (
+					format! (
+						"handshake failed: {}",
+						error.description ()));
+
+				return Ok (());
+
+			},
+
+		};
+
+		check_result_builder.ok (
+			format! (
+				"{} with {}",
+				tls_handshake.protocol_version,
+				tls_handshake.cipher_suite));
+
+		check_helper::check_duration_less_than (
+			check_result_builder,
+			& self.handshake_time_warning,
+			& self.handshake_time_critical,
+			& format! (
+				"handshake took {}",
+				check_helper::display_duration_short (
+					& tls_handshake.duration)),
+			& tls_handshake.duration);
+
+		check_result_builder.performance_data (
+			PerformanceData::new (
+				"handshake_time",
+				check_helper::duration_as_seconds (
+					& tls_handshake.duration),
+				PerformanceDataUnit::Seconds,
+			).minimum (
+				0.0,
+			));
+
+		self.check_certificate_chain (
+			check_result_builder,
+			& tls_handshake.peer_certificates);
+
+		Ok (())
+
+	}
+
+	// connect_timeout takes a single address, so we try each resolved address
+	// in turn
+
+	fn connect (
+		& self,
+	) -> Result <TcpStream, String> {
+
+		let socket_addresses: Vec <SocketAddr> =
+			(self.address.as_str (), self.port).to_socket_addrs ().map_err (
+				|error| error.to_string (),
+			) ?.collect ();
+
+		let mut last_error =
+			"no addresses found".to_string ();
+
+		for socket_address in socket_addresses {
+
+			match TcpStream::connect_timeout (
+				& socket_address,
+				self.timeout,
+			) {
+
+				Ok (tcp_stream) =>
+					return Ok (tcp_stream),
+
+				Err (error) =>
+					last_error =
+						format! (
+							"{}: {}",
+							socket_address,
+							error),
+
+			}
+
+		}
+
+		Err (last_error)
+
+	}
+
+	fn check_certificate_chain (
+		& self,
+		check_result_builder: & mut CheckResultBuilder,
+		peer_certificates: & Option <Vec <rustls::Certificate>>,
+	) {
+
+		let certificate_chain =
+			inspect_certificate_chain (
+				peer_certificates);
+
+		let (status_messages, details) =
+			check_helper::check_certificate_chain (
+				& certificate_chain,
+				self.cert_expiry_warning,
+				self.cert_expiry_critical,
+				& [],
+				& None,
+				Utc::now ().naive_utc ());
+
+		for status_message in status_messages {
+
+			check_result_builder.status_message (
+				status_message);
+
+		}
+
+		for detail in details {
+
+			check_result_builder.extra_information (
+				detail);
+
+		}
+
+	}
+
+}
+
+// ---------- starttls
+
+#[ derive (Clone, Copy, Debug, PartialEq) ]
+enum StartTls {
+	None,
+	Smtp,
+	Imap,
+	Pop3,
+}
+
+impl StartTls {
+
+	fn name (
+		& self,
+	) -> & 'static str {
+
+		match * self {
+			StartTls::None => "TLS",
+			StartTls::Smtp => "SMTP",
+			StartTls::Imap => "IMAP",
+			StartTls::Pop3 => "POP3",
+		}
+
+	}
+
+	fn default_port (
+		& self,
+	) -> u16 {
+
+		match * self {
+			StartTls::None => 443,
+			StartTls::Smtp => 25,
+			StartTls::Imap => 143,
+			StartTls::Pop3 => 110,
+		}
+
+	}
+
+	// talks the plain text protocol until the server is ready for the
+	// handshake, the server says nothing more until we send our hello
+
+	fn upgrade (
+		& self,
+		tcp_stream: & TcpStream,
+	) -> Result <(), String> {
+
+		let mut reader =
+			BufReader::new (
+				tcp_stream);
+
+		match * self {
+
+			StartTls::None =>
+				(),
+
+			StartTls::Smtp => {
+
+				expect_smtp_reply (
+					& mut reader,
+					"greeting",
+					"220",
+				) ?;
+
+				send_command (
+					tcp_stream,
+					& format! (
+						"EHLO {}",
+						SMTP_CLIENT_NAME),
+				) ?;
+
+				let ehlo_lines =
+					expect_smtp_reply (
+						& mut reader,
+						"EHLO reply",
+						"250",
+					) ?;
+
+				if ! ehlo_lines.iter ().any (
+					|ehlo_line|
+					ehlo_line.len () > 4
+					&& ehlo_line [4 ..].trim ().to_uppercase () == "STARTTLS"
+				) {
+
+					return Err (
+						"not offered".to_string ());
+
+				}
+
+				send_command (
+					tcp_stream,
+					"STARTTLS",
+				) ?;
+
+				expect_smtp_reply (
+					& mut reader,
+					"STARTTLS reply",
+					"220",
+				) ?;
+
+			},
+
+			StartTls::Imap => {
+
+				let greeting =
+					read_line (
+						& mut reader,
+					) ?;
+
+				if ! greeting.starts_with ("* OK") {
+
+					return Err (
+						format! (
+							"unexpected greeting: {}",
+							greeting));
+
+				}
+
+				send_command (
+					tcp_stream,
+					"a1 STARTTLS",
+				) ?;
+
+				loop {
+
+					let line =
+						read_line (
+							& mut reader,
+						) ?;
+
+					if line.starts_with ("* ") {
+						continue;
+					}
+
+					if line.starts_with ("a1 OK") {
+						break;
+					}
+
+					return Err (
+						format! (
+							"unexpected STARTTLS reply: {}",
+							line));
+
+				}
+
+			},
+
+			StartTls::Pop3 => {
+
+				let greeting =
+					read_line (
+						& mut reader,
+					) ?;
+
+				if ! greeting.starts_with ("+OK") {
+
+					return Err (
+						format! (
+							"unexpected greeting: {}",
+							greeting));
+
+				}
+
+				send_command (
+					tcp_stream,
+					"STLS",
+				) ?;
+
+				let line =
+					read_line (
+						& mut reader,
+					) ?;
+
+				if ! line.starts_with ("+OK") {
+
+					return Err (
+						format! (
+							"unexpected STLS reply: {}",
+							line));
+
+				}
+
+			},
+
+		}
+
+		Ok (())
+
+	}
+
+}
+
+impl arg_helper::EnumArg for StartTls {
+
+	fn from_string (
+		string_value: & str,
+	) -> Option <StartTls> {
+
+		match string_value {
+
+			"none" => Some (StartTls::None),
+			"smtp" => Some (StartTls::Smtp),
+			"imap" => Some (StartTls::Imap),
+			"pop3" => Some (StartTls::Pop3),
+
+			_ => None,
+
+		}
+
+	}
+
+}
+
+fn send_command (
+	mut tcp_stream: & TcpStream,
+	command: & str,
+) -> Result <(), String> {
+
+	tcp_stream.write_all (
+		format! (
+			"{}\r\n",
+			command,
+		).as_bytes (),
+	).map_err (
+		|error|
+		format! (
+			"error sending {}: {}",
+			command,
+			error),
+	)
+
+}
+
+fn read_line (
+	reader: & mut BufRead,
+) -> Result <String, String> {
+
+	let mut line =
+		String::new ();
+
+	let bytes_read =
+		reader.read_line (
+			& mut line,
+		).map_err (
+			|error|
+			format! (
+				"error reading from server: {}",
+				error),
+		) ?;
+
+	if bytes_read == 0 {
+
+		return Err (
+			"connection closed by server".to_string ());
+
+	}
+
+	Ok (
+		line.trim_right ().to_string ()
+	)
+
+}
+
+// reads a possibly multi-line reply, where every line but the last has a dash
+// after the code
+
+fn expect_smtp_reply (
+	reader: & mut BufRead,
+	description: & str,
+	expect_code: & str,
+) -> Result <Vec <String>, String> {
+
+	let mut lines: Vec <String> =
+		Vec::new ();
+
+	loop {
+
+		let line =
+			read_line (
+				reader,
+			) ?;
+
+		let last_line =
+			line.as_bytes ().get (3) != Some (& b'-');
+
+		lines.push (
+			line);
+
+		if last_line {
+			break;
+		}
+
+	}
+
+	if ! lines.iter ().all (
+		|line| line.starts_with (expect_code)) {
+
+		return Err (
+			format! (
+				"unexpected {}: {}",
+				description,
+				lines.join (" / ")));
+
+	}
+
+	Ok (lines)
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::io::BufRead;
+	use std::io::BufReader;
+	use std::io::Write;
+	use std::net::TcpStream;
+	use std::sync::Arc;
+
+	use rustls;
+	use rustls::Session;
+
+	use logic::*;
+	use logic::testing::*;
+
+	// a mail server which offers STARTTLS and then completes a handshake with
+	// a self signed certificate for mail.example.com
+
+	fn start_smtp_server (
+	) -> u16 {
+
+		start_stand_in_server (
+			|mut stream| {
+
+			let mut reader =
+				BufReader::new (
+					stream.try_clone ().unwrap ());
+
+			let mut line =
+				String::new ();
+
+			stream.write_all (
+				b"220 mail.example.com ESMTP\r\n",
+			).unwrap ();
+
+			reader.read_line (& mut line).unwrap ();
+
+			stream.write_all (
+				b"250-mail.example.com\r\n\
+				250-PIPELINING\r\n\
+				250 STARTTLS\r\n",
+			).unwrap ();
+
+			reader.read_line (& mut line).unwrap ();
+
+			stream.write_all (
+				b"220 ready to start TLS\r\n",
+			).unwrap ();
+
+			serve_handshake (
+				& mut stream);
+
+		})
+
+	}
+
+	fn serve_handshake (
+		stream: & mut TcpStream,
+	) {
+
+		let mut server_config =
+			rustls::ServerConfig::new ();
+
+		server_config.set_single_cert (
+			vec! [
+				rustls::Certificate (
+					include_bytes! (
+						"../../fixtures/certificates/mail-server.der",
+					).to_vec ()),
+			],
+			rustls::PrivateKey (
+				include_bytes! (
+					"../../fixtures/certificates/mail-server.key.der",
+				).to_vec ()));
+
+		let mut server_session =
+			rustls::ServerSession::new (
+				& Arc::new (server_config));
+
+		// the client may hang up part way, so errors just end the handshake
+
+		while server_session.is_handshaking () {
+
+			while server_session.wants_write () {
+				if server_session.write_tls (stream).is_err () {
+					return;
+				}
+			}
+
+			if server_session.read_tls (stream).unwrap_or (0) == 0 {
+				return;
+			}
+
+			if server_session.process_new_packets ().is_err () {
+				return;
+			}
+
+		}
+
+		while server_session.wants_write () {
+			if server_session.write_tls (stream).is_err () {
+				return;
+			}
+		}
+
+	}
+
+	#[ test ]
+	fn smtp_starttls () {
+
+		let port =
+			start_smtp_server ().to_string ();
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--address", "127.0.0.1",
+					"--port", & port,
+					"--hostname", "mail.example.com",
+					"--starttls", "smtp",
+					"--insecure",
+				]);
+
+		assert_eq! (
+			* check_result.status (),
+			CheckStatus::Ok);
+
+		assert! (
+			check_result.status_messages () [0].message ().starts_with (
+				"TLSv1.2 with TLS_"));
+
+		assert! (
+			check_result.extra_information ().contains (
+				& "certificate 0: subject CN=mail.example.com, \
+				issuer CN=mail.example.com, serial 2E:02:05:28:9E:88:6C:D7:D0:\
+				45:B7:29:11:5C:21:27:7C:1B:75:35, sha256WithRSAEncryption, \
+				2048 bit RSA key, valid until 2126-09-24 06:56:03"
+					.to_string ()));
+
+	}
+
+	#[ test ]
+	fn untrusted_certificate () {
+
+		let port =
+			start_smtp_server ().to_string ();
+
+		let check_result =
+			run_without_fixture (
+				& * super::new (),
+				& [
+					"--address", "127.0.0.1",
+					"--port", & port,
+					"--hostname", "mail.example.com",
+					"--starttls", "smtp",
+				]);
+
+		assert_status_messages (
+			& check_result,
+			CheckStatus::Critical,
+			& [
+				"certificate verification failed: untrusted issuer",
+			]);
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
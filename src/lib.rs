@@ -3,25 +3,29 @@ extern crate chrono;
 extern crate encoding;
 extern crate futures;
 extern crate getopts;
-extern crate der_parser;
 extern crate hyper;
 extern crate hyper_tls;
 extern crate hyper_rustls;
 extern crate itertools;
-extern crate nom;
+extern crate regex;
 extern crate resolv;
 extern crate rustls;
+extern crate serde;
 extern crate tokio_core;
 extern crate tokio_io;
 extern crate tokio_rustls;
 extern crate tokio_service;
+extern crate webpki;
 extern crate webpki_roots;
 
 #[ macro_use ]
-pub mod logic;
+extern crate serde_derive;
 
 #[ macro_use ]
-extern crate serde_derive;
+extern crate serde_json;
+
+#[ macro_use ]
+pub mod logic;
 
 pub mod checks;
 pub mod lowlevel;
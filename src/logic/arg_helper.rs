@@ -1,11 +1,16 @@
 use getopts;
 
+use regex::Regex;
+
 use std::error;
 use std::marker;
 use std::time;
 
+use logic::check_helper;
 use logic::simple_error::*;
 
+use lowlevel::http::HttpTlsOptions;
+
 // ==================== boolean arguments
 
 pub fn check_if_present (
@@ -96,6 +101,137 @@ pub fn parse_string_multiple (
 
 }
 
+// ==================== regex arguments
+
+pub fn parse_regex (
+	option_matches: & getopts::Matches,
+	option_name: & str,
+) -> Result <Option <Regex>, Box <error::Error>> {
+
+	match option_matches.opt_str (
+		option_name) {
+
+		None =>
+			Ok (None),
+
+		Some (option_string) =>
+			Ok (Some (
+				compile_regex (
+					option_name,
+					& option_string,
+				) ?
+			)),
+
+	}
+
+}
+
+pub fn parse_regex_multiple (
+	option_matches: & getopts::Matches,
+	option_name: & str,
+) -> Result <Vec <Regex>, Box <error::Error>> {
+
+	let mut return_values: Vec <Regex> =
+		vec! [];
+
+	for option_string in option_matches.opt_strs (
+		option_name) {
+
+		return_values.push (
+			compile_regex (
+				option_name,
+				& option_string,
+			) ?);
+
+	}
+
+	Ok (
+		return_values
+	)
+
+}
+
+fn compile_regex (
+	option_name: & str,
+	option_string: & str,
+) -> Result <Regex, Box <error::Error>> {
+
+	Ok (
+
+		Regex::new (
+			option_string,
+		).map_err (
+			|error|
+			format! (
+				"Parameter {} must be a valid regular expression: {}",
+				option_name,
+				error),
+		) ?
+
+	)
+
+}
+
+// ==================== header arguments
+
+pub fn parse_header_multiple (
+	option_matches: & getopts::Matches,
+	option_name: & str,
+) -> Result <Vec <(String, String)>, Box <error::Error>> {
+
+	let mut return_values: Vec <(String, String)> =
+		vec! [];
+
+	for option_string in option_matches.opt_strs (
+		option_name) {
+
+		return_values.push (
+			parse_header (
+				option_name,
+				& option_string,
+			) ?);
+
+	}
+
+	Ok (
+		return_values
+	)
+
+}
+
+fn parse_header (
+	option_name: & str,
+	option_string: & str,
+) -> Result <(String, String), Box <error::Error>> {
+
+	let split_position =
+		match option_string.find (
+			':') {
+
+		Some (pos) => pos,
+
+		None =>
+			return Err (Box::new (
+				SimpleError::from (
+					format! (
+						"Parameter {} must be in 'name:value' format",
+						option_name)))),
+
+	};
+
+	let (name_raw, rest_raw) =
+		option_string.split_at (
+			split_position);
+
+	Ok (
+		(
+			name_raw.trim ().to_string (),
+			rest_raw [1 ..].trim ().to_string (),
+		)
+	)
+
+}
+
 // ==================== enum arguments
 
 pub trait EnumArg where Self: marker::Sized {
@@ -146,12 +282,13 @@ pub fn parse_enum <EnumType: EnumArg> (
 
 }
 
-// ==================== fractional arguments
+// ==================== range arguments
 
-pub fn parse_decimal_fraction (
+pub fn parse_check_range (
 	options_matches: & getopts::Matches,
 	option_name: & str,
-) -> Result <Option <f64>, Box <error::Error>> {
+	bare_value: check_helper::CheckRangeBareValue,
+) -> Result <Option <check_helper::CheckRange>, Box <error::Error>> {
 
 	match options_matches.opt_str (
 		option_name) {
@@ -159,19 +296,18 @@ pub fn parse_decimal_fraction (
 		None =>
 			Ok (None),
 
-		Some (option_string) => {
-
+		Some (option_string) =>
 			Ok (Some (
-				try! (
-					option_string.parse::<f64> (
-					).map_err (
-						|_|
-						format! (
-							"Invalid value for {}",
-							option_name),
-					))))
-
-		},
+				check_helper::CheckRange::parse_number (
+					& option_string,
+					bare_value,
+				).map_err (
+					|error|
+					format! (
+						"Invalid value for {}: {}",
+						option_name,
+						error.description ()),
+				) ?)),
 
 	}
 
@@ -248,6 +384,36 @@ pub fn parse_positive_integer_or_default (
 
 }
 
+pub fn parse_port_or_default (
+	options_matches: & getopts::Matches,
+	option_name: & str,
+	default_value: u16,
+) -> Result <u16, Box <error::Error>> {
+
+	match try! (
+		parse_positive_integer (
+			options_matches,
+			option_name)) {
+
+		None =>
+			Ok (default_value),
+
+		Some (value) if value <= 65535 =>
+			Ok (value as u16),
+
+		Some (value) =>
+			Err (
+				Box::new (
+					SimpleError::from (
+						format! (
+							"Parameter {} must be a port number, but got {}",
+							option_name,
+							value)))),
+
+	}
+
+}
+
 pub fn parse_positive_integer_multiple (
 	option_matches: & getopts::Matches,
 	option_name: & str,
@@ -331,49 +497,59 @@ pub fn parse_duration (
 		None =>
 			Ok (None),
 
-		Some (option_string) => {
+		Some (option_string) =>
+			Ok (Some (
+				parse_duration_string (
+					& option_string,
+					option_name,
+				) ?)),
 
-			let (multiplier, suffix_length) =
-				if option_string.ends_with ("ms") {
-					(1, 2)
-				} else if option_string.ends_with ("s") {
-					(1000, 1)
-				} else if option_string.ends_with ("m") {
-					(1000 * 60, 1)
-				} else if option_string.ends_with ("h") {
-					(1000 * 60 * 60, 1)
-				} else if option_string.ends_with ("d") {
-					(1000 * 60 * 60 * 24, 1)
-				} else {
-					return Err (Box::new (
-						SimpleError::from (
-							format! (
-								"units not specified or recognised for --{}",
-								option_name))));
-				};
+	}
 
-			let quantity_string =
-				& option_string [
-					0 ..
-					option_string.len () - suffix_length];
+}
 
-			let quantity_integer =
-				try! (
-					quantity_string.parse::<u64> (
-					).map_err (
-						|_|
-						format! (
-							"unable to parse value for --{}",
-							option_name)
-					));
+pub fn parse_duration_string (
+	option_string: & str,
+	option_name: & str,
+) -> Result <time::Duration, Box <error::Error>> {
 
-			Ok (Some (
-				time::Duration::from_millis (
-					multiplier * quantity_integer)))
+	let (multiplier, suffix_length) =
+		if option_string.ends_with ("ms") {
+			(1, 2)
+		} else if option_string.ends_with ("s") {
+			(1000, 1)
+		} else if option_string.ends_with ("m") {
+			(1000 * 60, 1)
+		} else if option_string.ends_with ("h") {
+			(1000 * 60 * 60, 1)
+		} else if option_string.ends_with ("d") {
+			(1000 * 60 * 60 * 24, 1)
+		} else {
+			return Err (Box::new (
+				SimpleError::from (
+					format! (
+						"units not specified or recognised for --{}",
+						option_name))));
+		};
 
-		}
+	let quantity_string =
+		& option_string [
+			0 ..
+			option_string.len () - suffix_length];
 
-	}
+	let quantity_integer =
+		try! (
+			quantity_string.parse::<u64> (
+			).map_err (
+				|_|
+				format! (
+					"unable to parse value for --{}",
+					option_name)
+			));
+
+	Ok (
+		time::Duration::from_millis (
+			multiplier * quantity_integer))
 
 }
 
@@ -397,4 +573,296 @@ pub fn parse_duration_or_default (
 
 }
 
+pub fn parse_duration_range (
+	options_matches: & getopts::Matches,
+	option_name: & str,
+	bare_value: check_helper::CheckRangeBareValue,
+) -> Result <Option <check_helper::CheckRange>, Box <error::Error>> {
+
+	match options_matches.opt_str (
+		option_name) {
+
+		None =>
+			Ok (None),
+
+		Some (option_string) =>
+			Ok (Some (
+				check_helper::CheckRange::parse (
+					& option_string,
+					bare_value,
+					|value_string|
+
+					Ok (check_helper::duration_as_seconds (
+						& parse_duration_string (
+							value_string,
+							option_name,
+						) ?))
+
+				) ?)),
+
+	}
+
+}
+
+// ==================== certificate arguments
+
+pub fn parse_cert_expiry (
+	options_matches: & getopts::Matches,
+) -> Result <(time::Duration, time::Duration), Box <error::Error>> {
+
+	let cert_expiry_warning =
+		parse_duration (
+			options_matches,
+			"cert-expiry-warning",
+		) ?.unwrap_or (
+			time::Duration::from_secs (7 * 24 * 60 * 60),
+		);
+
+	let cert_expiry_critical =
+		parse_duration (
+			options_matches,
+			"cert-expiry-critical",
+		) ?.unwrap_or (
+			time::Duration::from_secs (5 * 24 * 60 * 60),
+		);
+
+	if cert_expiry_critical > cert_expiry_warning {
+
+		return Err (Box::new (
+			SimpleError::from (
+				"Option 'cert-expiry-critical' must not be longer than \
+				'cert-expiry-warning'")));
+
+	}
+
+	Ok ((cert_expiry_warning, cert_expiry_critical))
+
+}
+
+// ==================== tls arguments
+
+pub fn tls_options_spec (
+	options_spec: & mut getopts::Options,
+) {
+
+	options_spec.optopt (
+		"",
+		"ca-file",
+		"trust certificates signed by the authorities in this PEM file \
+		instead of the default ones",
+		"PATH");
+
+	options_spec.optopt (
+		"",
+		"client-cert",
+		"PEM file containing client certificate to present",
+		"PATH");
+
+	options_spec.optopt (
+		"",
+		"client-key",
+		"PEM file containing private key for client certificate",
+		"PATH");
+
+	options_spec.optflag (
+		"",
+		"insecure",
+		"do not verify the server certificate");
+
+}
+
+pub fn parse_tls_options (
+	options_matches: & getopts::Matches,
+) -> Result <HttpTlsOptions, Box <error::Error>> {
+
+	let tls_options =
+		HttpTlsOptions {
+
+		ca_file:
+			parse_string (
+				options_matches,
+				"ca-file",
+			) ?,
+
+		client_cert:
+			parse_string (
+				options_matches,
+				"client-cert",
+			) ?,
+
+		client_key:
+			parse_string (
+				options_matches,
+				"client-key",
+			) ?,
+
+		insecure:
+			check_if_present (
+				options_matches,
+				"insecure",
+			) ?,
+
+	};
+
+	if tls_options.client_cert.is_some ()
+		!= tls_options.client_key.is_some () {
+
+		return Err (Box::new (
+			SimpleError::from (
+				"Options 'client-cert' and 'client-key' must be used together")));
+
+	}
+
+	Ok (tls_options)
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use getopts;
+
+	use std::error::Error;
+	use std::time::Duration;
+
+	use super::*;
+
+	fn options_spec (
+	) -> getopts::Options {
+
+		let mut options_spec =
+			getopts::Options::new ();
+
+		options_spec.optopt ("", "port", "", "");
+		options_spec.optopt ("", "cert-expiry-warning", "", "");
+		options_spec.optopt ("", "cert-expiry-critical", "", "");
+
+		tls_options_spec (
+			& mut options_spec);
+
+		options_spec
+
+	}
+
+	#[ test ]
+	fn ports () {
+
+		let options_spec =
+			options_spec ();
+
+		assert_eq! (
+			parse_port_or_default (
+				& options_spec.parse (& [] as & [& str]).unwrap (),
+				"port",
+				443,
+			).unwrap (),
+			443);
+
+		assert_eq! (
+			parse_port_or_default (
+				& options_spec.parse (& [ "--port", "65535" ]).unwrap (),
+				"port",
+				443,
+			).unwrap (),
+			65535);
+
+		for & (port, expected_error) in [
+			("0", "Parameter port must be a positive integer, but got 0"),
+			("65536", "Parameter port must be a port number, but got 65536"),
+			("https", "Parameter port must be a positive integer"),
+		].iter () {
+
+			assert_eq! (
+				parse_port_or_default (
+					& options_spec.parse (& [ "--port", port ]).unwrap (),
+					"port",
+					443,
+				).unwrap_err ().description (),
+				expected_error);
+
+		}
+
+	}
+
+	#[ test ]
+	fn cert_expiry () {
+
+		let options_spec =
+			options_spec ();
+
+		assert_eq! (
+			parse_cert_expiry (
+				& options_spec.parse (& [] as & [& str]).unwrap (),
+			).unwrap (),
+			(
+				Duration::from_secs (7 * 24 * 60 * 60),
+				Duration::from_secs (5 * 24 * 60 * 60),
+			));
+
+		assert_eq! (
+			parse_cert_expiry (
+				& options_spec.parse (& [
+					"--cert-expiry-warning", "30d",
+					"--cert-expiry-critical", "30d",
+				]).unwrap (),
+			).unwrap (),
+			(
+				Duration::from_secs (30 * 24 * 60 * 60),
+				Duration::from_secs (30 * 24 * 60 * 60),
+			));
+
+		assert_eq! (
+			parse_cert_expiry (
+				& options_spec.parse (& [
+					"--cert-expiry-warning", "7d",
+					"--cert-expiry-critical", "14d",
+				]).unwrap (),
+			).unwrap_err ().description (),
+			"Option 'cert-expiry-critical' must not be longer than \
+			'cert-expiry-warning'");
+
+	}
+
+	#[ test ]
+	fn tls_options () {
+
+		let options_spec =
+			options_spec ();
+
+		let tls_options =
+			parse_tls_options (
+				& options_spec.parse (& [
+					"--ca-file", "ca.pem",
+					"--client-cert", "client.pem",
+					"--client-key", "client.key",
+					"--insecure",
+				]).unwrap (),
+			).unwrap ();
+
+		assert_eq! (
+			(
+				tls_options.ca_file,
+				tls_options.client_cert,
+				tls_options.client_key,
+				tls_options.insecure,
+			),
+			(
+				Some ("ca.pem".to_string ()),
+				Some ("client.pem".to_string ()),
+				Some ("client.key".to_string ()),
+				true,
+			));
+
+		assert_eq! (
+			parse_tls_options (
+				& options_spec.parse (& [
+					"--client-cert", "client.pem",
+				]).unwrap (),
+			).unwrap_err ().description (),
+			"Options 'client-cert' and 'client-key' must be used together");
+
+	}
+
+}
+
 // ex: noet ts=4 filetype=rust
@@ -0,0 +1,563 @@
+use std::time;
+
+use chrono::Duration as ChronoDuration;
+use chrono::NaiveDateTime;
+
+use logic::*;
+use lowlevel::http::CertificateInfo;
+
+// evaluates a presented chain for check-http and check-tls, returning status
+// messages and per certificate details for the caller to report in its own way
+
+pub fn check_certificate_chain (
+	certificate_chain: & [Result <CertificateInfo, String>],
+	cert_expiry_warning: time::Duration,
+	cert_expiry_critical: time::Duration,
+	expect_cert_names: & [String],
+	expect_cert_issuer: & Option <String>,
+	now: NaiveDateTime,
+) -> (Vec <CheckStatusMessage>, Vec <String>) {
+
+	let mut status_messages: Vec <CheckStatusMessage> =
+		Vec::new ();
+
+	let mut details: Vec <String> =
+		Vec::new ();
+
+	if certificate_chain.is_empty () {
+
+		status_messages.push (
+			CheckStatusMessage::new (
+				CheckStatus::Critical,
+				"no certificate presented (critical)"));
+
+		return (status_messages, details);
+
+	}
+
+	let mut earliest_intermediate: Option <& CertificateInfo> =
+		None;
+
+	for (index, certificate_result)
+		in certificate_chain.iter ().enumerate () {
+
+		let certificate_info =
+			match * certificate_result {
+
+			Ok (ref certificate_info) =>
+				certificate_info,
+
+			Err (ref error) => {
+
+				details.push (
+					format! (
+						"certificate {}: unable to inspect: {}",
+						index,
+						error));
+
+				// without the leaf we can't check names or expiry at all
+
+				if index == 0 {
+
+					status_messages.push (
+						CheckStatusMessage::new (
+							CheckStatus::Unknown,
+							format! (
+								"unable to inspect certificate: {}",
+								error)));
+
+				}
+
+				continue;
+
+			},
+
+		};
+
+		details.push (
+			format! (
+				"certificate {}: subject {}, issuer {}, serial {}, {}, \
+				{} key, valid until {}",
+				index,
+				certificate_info.subject,
+				certificate_info.issuer,
+				certificate_info.serial,
+				certificate_info.signature_algorithm,
+				match certificate_info.key_bits {
+					Some (key_bits) =>
+						format! (
+							"{} bit {}",
+							key_bits,
+							certificate_info.key_type),
+					None =>
+						certificate_info.key_type.to_string (),
+				},
+				certificate_info.not_after));
+
+		// a root is trusted because it is in the trust store, so its own
+		// signature and expiry don't matter
+
+		if index > 0 && certificate_info.is_self_signed () {
+			continue;
+		}
+
+		if certificate_info.has_weak_signature () {
+
+			status_messages.push (
+				CheckStatusMessage::new (
+					CheckStatus::Warning,
+					format! (
+						"certificate {} signed with {} (warning)",
+						certificate_info.name (),
+						certificate_info.signature_algorithm)));
+
+		}
+
+		if certificate_info.has_weak_key () {
+
+			status_messages.push (
+				CheckStatusMessage::new (
+					CheckStatus::Warning,
+					format! (
+						"certificate {} has {} bit {} key (warning)",
+						certificate_info.name (),
+						certificate_info.key_bits.unwrap_or (0),
+						certificate_info.key_type)));
+
+		}
+
+		if index == 0 {
+
+			status_messages.extend (
+				check_certificate_names (
+					expect_cert_names,
+					expect_cert_issuer,
+					certificate_info));
+
+			status_messages.push (
+				check_certificate_remaining_time (
+					cert_expiry_warning,
+					cert_expiry_critical,
+					"certificate",
+					certificate_info.not_after.signed_duration_since (
+						now)));
+
+		} else if earliest_intermediate.map (
+			|earliest_intermediate|
+			certificate_info.not_after < earliest_intermediate.not_after
+		).unwrap_or (true) {
+
+			earliest_intermediate =
+				Some (certificate_info);
+
+		}
+
+	}
+
+	// an intermediate which expires after the leaf is covered by the leaf's
+	// own expiry check
+
+	if let Some (earliest_intermediate) = earliest_intermediate {
+
+		let leaf_expiry =
+			match certificate_chain.first () {
+				Some (& Ok (ref leaf)) => Some (leaf.not_after),
+				_ => None,
+			};
+
+		if leaf_expiry.map (
+			|leaf_expiry| earliest_intermediate.not_after < leaf_expiry
+		).unwrap_or (true) {
+
+			status_messages.push (
+				check_certificate_remaining_time (
+					cert_expiry_warning,
+					cert_expiry_critical,
+					& format! (
+						"intermediate certificate {}",
+						earliest_intermediate.name ()),
+					earliest_intermediate.not_after.signed_duration_since (
+						now)));
+
+		}
+
+	}
+
+	(status_messages, details)
+
+}
+
+pub fn check_certificate_names (
+	expect_cert_names: & [String],
+	expect_cert_issuer: & Option <String>,
+	certificate_info: & CertificateInfo,
+) -> Vec <CheckStatusMessage> {
+
+	let mut status_messages: Vec <CheckStatusMessage> =
+		Vec::new ();
+
+	for expect_cert_name in expect_cert_names.iter () {
+
+		status_messages.push (
+			if certificate_info.covers_name (
+				expect_cert_name) {
+
+				CheckStatusMessage::new (
+					CheckStatus::Ok,
+					format! (
+						"certificate covers {}",
+						expect_cert_name))
+
+			} else {
+
+				CheckStatusMessage::new (
+					CheckStatus::Critical,
+					format! (
+						"certificate does not cover {} (critical)",
+						expect_cert_name))
+
+			});
+
+	}
+
+	if let Some (ref expect_cert_issuer) = * expect_cert_issuer {
+
+		status_messages.push (
+			if & certificate_info.issuer == expect_cert_issuer
+				|| certificate_info.issuer_common_name.as_ref ()
+					== Some (expect_cert_issuer) {
+
+				CheckStatusMessage::new (
+					CheckStatus::Ok,
+					format! (
+						"certificate issued by {}",
+						expect_cert_issuer))
+
+			} else {
+
+				CheckStatusMessage::new (
+					CheckStatus::Critical,
+					format! (
+						"certificate issued by {} instead of {} (critical)",
+						certificate_info.issuer,
+						expect_cert_issuer))
+
+			});
+
+	}
+
+	status_messages
+
+}
+
+pub fn check_certificate_remaining_time (
+	cert_expiry_warning: time::Duration,
+	cert_expiry_critical: time::Duration,
+	label: & str,
+	remaining_time: ChronoDuration,
+) -> CheckStatusMessage {
+
+	let critical_time =
+		ChronoDuration::seconds (
+			cert_expiry_critical.as_secs () as i64);
+
+	let warning_time =
+		ChronoDuration::seconds (
+			cert_expiry_warning.as_secs () as i64);
+
+	if remaining_time < critical_time {
+
+		CheckStatusMessage::new (
+			CheckStatus::Critical,
+			format! (
+				"{} expires in {} (critical)",
+				label,
+				display_remaining_time (
+					remaining_time)))
+
+	} else if remaining_time < warning_time {
+
+		CheckStatusMessage::new (
+			CheckStatus::Warning,
+			format! (
+				"{} expires in {} (warning)",
+				label,
+				display_remaining_time (
+					remaining_time)))
+
+	} else {
+
+		CheckStatusMessage::new (
+			CheckStatus::Ok,
+			format! (
+				"{} expires in {}",
+				label,
+				display_remaining_time (
+					remaining_time)))
+
+	}
+
+}
+
+pub fn display_remaining_time (
+	remaining_time: ChronoDuration,
+) -> String {
+
+	if remaining_time < ChronoDuration::days (2) {
+
+		format! (
+			"{} hours",
+			remaining_time.num_hours ())
+
+	} else if remaining_time < ChronoDuration::weeks (2) {
+
+		format! (
+			"{} days",
+			remaining_time.num_days ())
+
+	} else {
+
+		format! (
+			"{} weeks",
+			remaining_time.num_weeks ())
+
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::time::Duration;
+
+	use chrono::Duration as ChronoDuration;
+	use chrono::NaiveDate;
+
+	use logic::*;
+	use lowlevel::http::*;
+
+	use super::check_certificate_chain;
+	use super::check_certificate_names;
+	use super::check_certificate_remaining_time;
+
+	fn status_messages (
+		status_messages: & [CheckStatusMessage],
+	) -> Vec <(CheckStatus, String)> {
+
+		status_messages.iter ().map (
+			|status_message| (
+				* status_message.status (),
+				status_message.message ().to_string (),
+			)
+		).collect ()
+
+	}
+
+	#[ test ]
+	fn certificate_names_and_issuer () {
+
+		let certificate_info =
+			inspect_certificate (
+				include_bytes! (
+					"../../../fixtures/certificates/rsa-sha256.der"),
+			).unwrap ();
+
+		assert_eq! (
+			status_messages (
+				& check_certificate_names (
+					& [
+						"www.example.com".to_string (),
+						"mail.example.com".to_string (),
+					],
+					& Some ("www.example.com".to_string ()),
+					& certificate_info)),
+			vec! [
+				(CheckStatus::Ok,
+					"certificate covers www.example.com".to_string ()),
+				(CheckStatus::Critical,
+					"certificate does not cover mail.example.com (critical)"
+						.to_string ()),
+				(CheckStatus::Ok,
+					"certificate issued by www.example.com".to_string ()),
+			]);
+
+		assert_eq! (
+			status_messages (
+				& check_certificate_names (
+					& [],
+					& Some ("C=GB, O=Example Ltd, CN=www.example.com".to_string ()),
+					& certificate_info)),
+			vec! [
+				(CheckStatus::Ok,
+					"certificate issued by C=GB, O=Example Ltd, \
+					CN=www.example.com".to_string ()),
+			]);
+
+		assert_eq! (
+			status_messages (
+				& check_certificate_names (
+					& [],
+					& Some ("Example Issuing CA".to_string ()),
+					& certificate_info)),
+			vec! [
+				(CheckStatus::Critical,
+					"certificate issued by C=GB, O=Example Ltd, \
+					CN=www.example.com instead of Example Issuing CA (critical)"
+						.to_string ()),
+			]);
+
+		assert! (
+			check_certificate_names (
+				& [],
+				& None,
+				& certificate_info,
+			).is_empty ());
+
+	}
+
+	#[ test ]
+	fn certificate_expiry_thresholds () {
+
+		for & (remaining_time, ref expected_status, expected_message) in [
+			(ChronoDuration::days (30), CheckStatus::Ok,
+				"certificate expires in 4 weeks"),
+			(ChronoDuration::days (7), CheckStatus::Ok,
+				"certificate expires in 7 days"),
+			(ChronoDuration::days (6), CheckStatus::Warning,
+				"certificate expires in 6 days (warning)"),
+			(ChronoDuration::days (5), CheckStatus::Warning,
+				"certificate expires in 5 days (warning)"),
+			(ChronoDuration::hours (36), CheckStatus::Critical,
+				"certificate expires in 36 hours (critical)"),
+			(ChronoDuration::hours (-2), CheckStatus::Critical,
+				"certificate expires in -2 hours (critical)"),
+		].iter () {
+
+			let status_message =
+				check_certificate_remaining_time (
+					Duration::from_secs (7 * 24 * 60 * 60),
+					Duration::from_secs (5 * 24 * 60 * 60),
+					"certificate",
+					remaining_time);
+
+			assert_eq! (
+				status_message.status (),
+				expected_status);
+
+			assert_eq! (
+				status_message.message (),
+				expected_message);
+
+		}
+
+	}
+
+	#[ test ]
+	fn certificate_chains () {
+
+		let certificate_chain =
+			vec! [
+				inspect_certificate (
+					include_bytes! (
+						"../../../fixtures/certificates/rsa-sha256.der")),
+				Err ("truncated der value".to_string ()),
+			];
+
+		let (chain_messages, details) =
+			check_certificate_chain (
+				& certificate_chain,
+				Duration::from_secs (7 * 24 * 60 * 60),
+				Duration::from_secs (5 * 24 * 60 * 60),
+				& [ "example.com".to_string () ],
+				& None,
+				NaiveDate::from_ymd (2026, 12, 26).and_hms (0, 0, 0));
+
+		assert_eq! (
+			status_messages (& chain_messages),
+			vec! [
+				(CheckStatus::Ok,
+					"certificate covers example.com".to_string ()),
+				(CheckStatus::Warning,
+					"certificate expires in 6 days (warning)".to_string ()),
+			]);
+
+		assert_eq! (
+			details,
+			vec! [
+				"certificate 0: subject C=GB, O=Example Ltd, \
+				CN=www.example.com, issuer C=GB, O=Example Ltd, \
+				CN=www.example.com, serial 12:34:AB:CD, \
+				sha256WithRSAEncryption, 2048 bit RSA key, valid until \
+				2027-01-01 00:00:00",
+				"certificate 1: unable to inspect: truncated der value",
+			]);
+
+		let (chain_messages, details) =
+			check_certificate_chain (
+				& [],
+				Duration::from_secs (7 * 24 * 60 * 60),
+				Duration::from_secs (5 * 24 * 60 * 60),
+				& [],
+				& None,
+				NaiveDate::from_ymd (2026, 12, 26).and_hms (0, 0, 0));
+
+		assert_eq! (
+			status_messages (& chain_messages),
+			vec! [
+				(CheckStatus::Critical,
+					"no certificate presented (critical)".to_string ()),
+			]);
+
+		assert! (
+			details.is_empty ());
+
+	}
+
+	#[ test ]
+	fn truncated_leaf_certificate () {
+
+		let certificate_bytes: & [u8] =
+			include_bytes! (
+				"../../../fixtures/certificates/rsa-sha256.der");
+
+		let leaf_error =
+			inspect_certificate (
+				& certificate_bytes [0 .. 100],
+			).err ().unwrap ();
+
+		let certificate_chain =
+			vec! [
+				Err (leaf_error.clone ()),
+				inspect_certificate (
+					certificate_bytes),
+			];
+
+		let (chain_messages, details) =
+			check_certificate_chain (
+				& certificate_chain,
+				Duration::from_secs (7 * 24 * 60 * 60),
+				Duration::from_secs (5 * 24 * 60 * 60),
+				& [ "example.com".to_string () ],
+				& None,
+				NaiveDate::from_ymd (2026, 12, 26).and_hms (0, 0, 0));
+
+		assert_eq! (
+			status_messages (& chain_messages),
+			vec! [
+				(CheckStatus::Unknown,
+					format! (
+						"unable to inspect certificate: {}",
+						leaf_error)),
+			]);
+
+		assert_eq! (
+			details [0],
+			format! (
+				"certificate 0: unable to inspect: {}",
+				leaf_error));
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
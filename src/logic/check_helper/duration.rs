@@ -1,6 +1,7 @@
 use std::time;
 
 use logic::*;
+use logic::check_helper::*;
 
 #[ allow (unused) ]
 struct DurationFormat <'a> {
@@ -225,6 +226,33 @@ fn display_duration_generic (
 
 }
 
+pub fn duration_as_seconds (
+	duration: & time::Duration,
+) -> f64 {
+
+	duration.as_secs () as f64
+	+ duration.subsec_nanos () as f64 / 1_000_000_000.0
+
+}
+
+pub fn duration_from_seconds (
+	seconds: f64,
+) -> time::Duration {
+
+	if seconds <= 0.0 {
+
+		time::Duration::new (0, 0)
+
+	} else {
+
+		time::Duration::new (
+			seconds.trunc () as u64,
+			(seconds.fract () * 1_000_000_000.0) as u32)
+
+	}
+
+}
+
 pub fn check_duration_less_than (
 	check_result_builder: & mut CheckResultBuilder,
 	warning_limit: & Option <time::Duration>,
@@ -233,25 +261,56 @@ pub fn check_duration_less_than (
 	value: & time::Duration,
 ) {
 
-	if critical_limit.is_some ()
-		&& * value > critical_limit.unwrap () {
+	check_duration_in_range (
+		check_result_builder,
+		& warning_limit.map (
+			|warning_limit|
+			CheckRange::alert_above (
+				duration_as_seconds (
+					& warning_limit))),
+		& critical_limit.map (
+			|critical_limit|
+			CheckRange::alert_above (
+				duration_as_seconds (
+					& critical_limit))),
+		message,
+		value);
+
+}
+
+// ranges are expressed in seconds
+
+pub fn check_duration_in_range (
+	check_result_builder: & mut CheckResultBuilder,
+	warning_range: & Option <CheckRange>,
+	critical_range: & Option <CheckRange>,
+	message: & str,
+	value: & time::Duration,
+) {
+
+	let value_seconds =
+		duration_as_seconds (
+			value);
+
+	if let Some (critical_range) =
+		range_alerting (critical_range, value_seconds) {
 
 		check_result_builder.critical (
 			format! (
 				"{} (critical is {})",
 				message,
-				display_duration_short (
-					& critical_limit.unwrap ())));
+				critical_range.describe (
+					display_seconds_short)));
 
-	} else if warning_limit.is_some ()
-		&& * value > warning_limit.unwrap () {
+	} else if let Some (warning_range) =
+		range_alerting (warning_range, value_seconds) {
 
 		check_result_builder.warning (
 			format! (
 				"{} (warning is {})",
 				message,
-				display_duration_short (
-					& warning_limit.unwrap ())));
+				warning_range.describe (
+					display_seconds_short)));
 
 	} else {
 
@@ -264,4 +323,14 @@ pub fn check_duration_less_than (
 
 }
 
+fn display_seconds_short (
+	seconds: f64,
+) -> String {
+
+	display_duration_short (
+		& duration_from_seconds (
+			seconds))
+
+}
+
 // ex: noet ts=4 filetype=rust
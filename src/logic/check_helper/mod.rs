@@ -1,9 +1,13 @@
+mod certificate;
 mod datasize;
 mod duration;
+mod range;
 mod ratio;
 
+pub use self::certificate::*;
 pub use self::datasize::*;
 pub use self::duration::*;
+pub use self::range::*;
 pub use self::ratio::*;
 
 // ex: noet ts=4 filetype=rust
@@ -0,0 +1,490 @@
+use std::error;
+use std::fmt;
+
+use logic::simple_error::*;
+
+// what a plain number without any range syntax means, this is the standard
+// nagios behaviour for AlertAbove, while AlertBelow keeps the meaning of our
+// older "minimum value" style options
+
+#[ derive (Clone, Copy, Debug, PartialEq) ]
+pub enum CheckRangeBareValue {
+	AlertAbove,
+	AlertBelow,
+}
+
+#[ derive (Clone, Copy, Debug, PartialEq, Serialize) ]
+pub struct CheckRange {
+	start: Option <f64>,
+	end: Option <f64>,
+	inside: bool,
+
+	// a bare number means "above" when alerting above, while an explicit
+	// "0:10" also alerts below zero
+
+	#[ serde (skip_serializing) ]
+	bare: bool,
+}
+
+impl CheckRange {
+
+	// constructors
+
+	pub fn alert_above (
+		limit: f64,
+	) -> CheckRange {
+
+		CheckRange {
+			start: None,
+			end: Some (limit),
+			inside: false,
+			bare: false,
+		}
+
+	}
+
+	pub fn alert_below (
+		limit: f64,
+	) -> CheckRange {
+
+		CheckRange {
+			start: Some (limit),
+			end: None,
+			inside: false,
+			bare: false,
+		}
+
+	}
+
+	pub fn parse_number (
+		range_string: & str,
+		bare_value: CheckRangeBareValue,
+	) -> Result <CheckRange, Box <error::Error>> {
+
+		Self::parse (
+			range_string,
+			bare_value,
+			|value_string|
+
+			Ok (
+				value_string.parse::<f64> (
+				).map_err (
+					|_|
+					format! (
+						"invalid number in range: {}",
+						value_string),
+				) ?
+			)
+
+		)
+
+	}
+
+	pub fn parse <
+		ParseValue: Fn (& str) -> Result <f64, Box <error::Error>>,
+	> (
+		range_string: & str,
+		bare_value: CheckRangeBareValue,
+		parse_value: ParseValue,
+	) -> Result <CheckRange, Box <error::Error>> {
+
+		let (inside, range_string) =
+			if range_string.starts_with ("@") {
+				(true, & range_string [1 ..])
+			} else {
+				(false, range_string)
+			};
+
+		let bare =
+			! range_string.contains (':');
+
+		let (start, end) =
+			if let Some (colon_position) =
+				range_string.find (':') {
+
+			let start_string =
+				& range_string [0 .. colon_position];
+
+			let end_string =
+				& range_string [colon_position + 1 ..];
+
+			(
+
+				if start_string == "~" {
+					None
+				} else if start_string.is_empty () {
+					Some (0.0)
+				} else {
+					Some (parse_value (start_string) ?)
+				},
+
+				if end_string.is_empty () {
+					None
+				} else {
+					Some (parse_value (end_string) ?)
+				},
+
+			)
+
+		} else if range_string.is_empty () {
+
+			return Err (Box::new (
+				SimpleError::from (
+					"empty range")));
+
+		} else {
+
+			let value =
+				parse_value (range_string) ?;
+
+			if inside || bare_value == CheckRangeBareValue::AlertAbove {
+				(Some (0.0), Some (value))
+			} else {
+				(Some (value), None)
+			}
+
+		};
+
+		if let (Some (start), Some (end)) = (start, end) {
+
+			if start > end {
+
+				return Err (Box::new (
+					SimpleError::from (
+						format! (
+							"range start is greater than end: {}",
+							range_string))));
+
+			}
+
+		}
+
+		Ok (CheckRange {
+			start: start,
+			end: end,
+			inside: inside,
+			bare: bare,
+		})
+
+	}
+
+	// evaluation
+
+	pub fn alerts (
+		& self,
+		value: f64,
+	) -> bool {
+
+		let in_range =
+			self.start.map (|start| value >= start).unwrap_or (true)
+			&& self.end.map (|end| value <= end).unwrap_or (true);
+
+		in_range == self.inside
+
+	}
+
+	// transformation
+
+	pub fn scale (
+		& self,
+		factor: f64,
+	) -> CheckRange {
+
+		CheckRange {
+			start: self.start.map (|start| start * factor),
+			end: self.end.map (|end| end * factor),
+			inside: self.inside,
+			bare: self.bare,
+		}
+
+	}
+
+	// accessors
+
+	pub fn start (
+		& self,
+	) -> Option <f64> {
+		self.start
+	}
+
+	pub fn end (
+		& self,
+	) -> Option <f64> {
+		self.end
+	}
+
+	pub fn inside (
+		& self,
+	) -> bool {
+		self.inside
+	}
+
+	// human readable description, eg "below 10%"
+
+	pub fn describe <
+		DisplayValue: Fn (f64) -> String,
+	> (
+		& self,
+		display_value: DisplayValue,
+	) -> String {
+
+		match (self.inside, self.start, self.end) {
+
+			(false, None, None) =>
+				"never".to_string (),
+
+			(false, Some (start), None) =>
+				format! (
+					"below {}",
+					display_value (start)),
+
+			(false, None, Some (end)) =>
+				format! (
+					"above {}",
+					display_value (end)),
+
+			(false, Some (start), Some (end)) if self.bare && start == 0.0 =>
+				format! (
+					"above {}",
+					display_value (end)),
+
+			(false, Some (start), Some (end)) =>
+				format! (
+					"outside {} to {}",
+					display_value (start),
+					display_value (end)),
+
+			(true, None, None) =>
+				"always".to_string (),
+
+			(true, Some (start), None) =>
+				format! (
+					"at least {}",
+					display_value (start)),
+
+			(true, None, Some (end)) =>
+				format! (
+					"at most {}",
+					display_value (end)),
+
+			(true, Some (start), Some (end)) =>
+				format! (
+					"between {} and {}",
+					display_value (start),
+					display_value (end)),
+
+		}
+
+	}
+
+}
+
+pub fn range_alerting (
+	range: & Option <CheckRange>,
+	value: f64,
+) -> Option <CheckRange> {
+
+	match * range {
+
+		Some (range) if range.alerts (value) =>
+			Some (range),
+
+		_ =>
+			None,
+
+	}
+
+}
+
+// nagios range syntax, as used in performance data
+
+impl fmt::Display for CheckRange {
+
+	fn fmt (
+		& self,
+		formatter: & mut fmt::Formatter,
+	) -> Result <(), fmt::Error> {
+
+		if self.inside {
+			formatter.write_str ("@") ?;
+		}
+
+		match (self.start, self.end) {
+
+			(Some (start), Some (end)) if start == 0.0 =>
+				write! (formatter, "{}", end) ?,
+
+			(Some (start), Some (end)) =>
+				write! (formatter, "{}:{}", start, end) ?,
+
+			(Some (start), None) =>
+				write! (formatter, "{}:", start) ?,
+
+			(None, Some (end)) =>
+				write! (formatter, "~:{}", end) ?,
+
+			(None, None) =>
+				formatter.write_str ("~:") ?,
+
+		}
+
+		Ok (())
+
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use super::*;
+
+	use super::CheckRangeBareValue::*;
+
+	fn parse (
+		range_string: & str,
+		bare_value: CheckRangeBareValue,
+	) -> (Option <f64>, Option <f64>, bool) {
+
+		let range =
+			CheckRange::parse_number (
+				range_string,
+				bare_value,
+			).unwrap ();
+
+		(range.start (), range.end (), range.inside ())
+
+	}
+
+	#[ test ]
+	fn parse_valid_ranges () {
+
+		for & (range_string, bare_value, expected) in [
+			("10", AlertAbove, (Some (0.0), Some (10.0), false)),
+			("10", AlertBelow, (Some (10.0), None, false)),
+			("10:", AlertAbove, (Some (10.0), None, false)),
+			("10:", AlertBelow, (Some (10.0), None, false)),
+			("~:10", AlertAbove, (None, Some (10.0), false)),
+			("~:10", AlertBelow, (None, Some (10.0), false)),
+			("10:20", AlertAbove, (Some (10.0), Some (20.0), false)),
+			("10:20", AlertBelow, (Some (10.0), Some (20.0), false)),
+			("@10:20", AlertAbove, (Some (10.0), Some (20.0), true)),
+			("@10", AlertBelow, (Some (0.0), Some (10.0), true)),
+			("-5:-1", AlertAbove, (Some (-5.0), Some (-1.0), false)),
+			("~:-0.5", AlertAbove, (None, Some (-0.5), false)),
+			("0.25:1.5", AlertBelow, (Some (0.25), Some (1.5), false)),
+			("0.8", AlertAbove, (Some (0.0), Some (0.8), false)),
+		].iter () {
+
+			assert_eq! (
+				(range_string, parse (range_string, bare_value)),
+				(range_string, expected));
+
+		}
+
+	}
+
+	#[ test ]
+	fn parse_invalid_ranges () {
+
+		for & range_string in [
+			"",
+			"@",
+			"abc",
+			"10:abc",
+			"abc:10",
+			"1:2:3",
+			"20:10",
+			"@20:10",
+			"-1:-5",
+		].iter () {
+
+			for & bare_value in [ AlertAbove, AlertBelow ].iter () {
+
+				assert! (
+					CheckRange::parse_number (
+						range_string,
+						bare_value,
+					).is_err (),
+					"{:?} should be rejected",
+					range_string);
+
+			}
+
+		}
+
+	}
+
+	#[ test ]
+	fn alerts () {
+
+		for & (range_string, bare_value, value, expected) in [
+			("10", AlertAbove, 10.0, false),
+			("10", AlertAbove, 10.5, true),
+			("10", AlertAbove, -1.0, true),
+			("0:10", AlertAbove, -1.0, true),
+			("0:10", AlertAbove, 5.0, false),
+			("10", AlertBelow, 10.0, false),
+			("10", AlertBelow, 9.5, true),
+			("10:", AlertAbove, 9.0, true),
+			("~:10", AlertAbove, -1000.0, false),
+			("~:10", AlertAbove, 11.0, true),
+			("10:20", AlertAbove, 15.0, false),
+			("10:20", AlertAbove, 25.0, true),
+			("@10:20", AlertAbove, 15.0, true),
+			("@10:20", AlertAbove, 20.0, true),
+			("@10:20", AlertAbove, 25.0, false),
+			("-5:-1", AlertAbove, -3.0, false),
+			("-5:-1", AlertAbove, 0.0, true),
+		].iter () {
+
+			assert_eq! (
+				(
+					range_string,
+					value,
+					CheckRange::parse_number (
+						range_string,
+						bare_value,
+					).unwrap ().alerts (value),
+				),
+				(range_string, value, expected));
+
+		}
+
+	}
+
+	#[ test ]
+	fn describe_and_display () {
+
+		for & (range_string, bare_value, description, display) in [
+			("10", AlertAbove, "above 10", "10"),
+			("10", AlertBelow, "below 10", "10:"),
+			("~:10", AlertAbove, "above 10", "~:10"),
+			("10:20", AlertAbove, "outside 10 to 20", "10:20"),
+			("0:10", AlertAbove, "outside 0 to 10", "10"),
+			(":10", AlertBelow, "outside 0 to 10", "10"),
+			("@10:20", AlertAbove, "between 10 and 20", "@10:20"),
+			("@10:", AlertAbove, "at least 10", "@10:"),
+			("@~:10", AlertAbove, "at most 10", "@~:10"),
+		].iter () {
+
+			let range =
+				CheckRange::parse_number (
+					range_string,
+					bare_value,
+				).unwrap ();
+
+			assert_eq! (
+				(
+					range.describe (|value| value.to_string ()),
+					range.to_string (),
+				),
+				(description.to_string (), display.to_string ()));
+
+		}
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
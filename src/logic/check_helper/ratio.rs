@@ -1,6 +1,7 @@
 use std::error;
 
 use logic::*;
+use logic::check_helper::*;
 
 pub fn check_ratio_greater_than (
 	check_result_builder: & mut CheckResultBuilder,
@@ -10,37 +11,13 @@ pub fn check_ratio_greater_than (
 	value: f64,
 ) -> Result <(), Box <error::Error>> {
 
-	if critical_limit.is_some ()
-		&& value < critical_limit.unwrap () {
-
-		check_result_builder.critical (
-			format! (
-				"{} or {}% (critical is {}%)",
-				message,
-				(value * 100.0) as u64,
-				(critical_limit.unwrap () * 100.0) as u64));
-
-	} else if warning_limit.is_some ()
-		&& value < warning_limit.unwrap () {
-
-		check_result_builder.warning (
-			format! (
-				"{} or {}% (warning is {}%)",
-				message,
-				(value * 100.0) as u64,
-				(warning_limit.unwrap () * 100.0) as u64));
-
-	} else {
-
-		check_result_builder.ok (
-			format! (
-				"{} or {}%",
-				message,
-				(value * 100.0) as u64));
-
-	}
-
-	Ok (())
+	check_ratio_in_range (
+		check_result_builder,
+		& warning_limit.map (CheckRange::alert_below),
+		& critical_limit.map (CheckRange::alert_below),
+		message,
+		value,
+	)
 
 }
 
@@ -52,25 +29,43 @@ pub fn check_ratio_lesser_than (
 	value: f64,
 ) -> Result <(), Box <error::Error>> {
 
-	if critical_limit.is_some ()
-		&& value > critical_limit.unwrap () {
+	check_ratio_in_range (
+		check_result_builder,
+		& warning_limit.map (CheckRange::alert_above),
+		& critical_limit.map (CheckRange::alert_above),
+		message,
+		value,
+	)
+
+}
+
+pub fn check_ratio_in_range (
+	check_result_builder: & mut CheckResultBuilder,
+	warning_range: & Option <CheckRange>,
+	critical_range: & Option <CheckRange>,
+	message: & str,
+	value: f64,
+) -> Result <(), Box <error::Error>> {
+
+	if let Some (critical_range) =
+		range_alerting (critical_range, value) {
 
 		check_result_builder.critical (
 			format! (
-				"{} or {}% (critical is {}%)",
+				"{} or {}% (critical is {})",
 				message,
 				(value * 100.0) as u64,
-				(critical_limit.unwrap () * 100.0) as u64));
+				critical_range.describe (display_ratio)));
 
-	} else if warning_limit.is_some ()
-		&& value > warning_limit.unwrap () {
+	} else if let Some (warning_range) =
+		range_alerting (warning_range, value) {
 
 		check_result_builder.warning (
 			format! (
-				"{} or {}% (warning is {}%)",
+				"{} or {}% (warning is {})",
 				message,
 				(value * 100.0) as u64,
-				(warning_limit.unwrap () * 100.0) as u64));
+				warning_range.describe (display_ratio)));
 
 	} else {
 
@@ -86,4 +81,14 @@ pub fn check_ratio_lesser_than (
 
 }
 
+fn display_ratio (
+	value: f64,
+) -> String {
+
+	format! (
+		"{}%",
+		(value * 100.0) as u64)
+
+}
+
 // ex: noet ts=4 filetype=rust
@@ -24,6 +24,7 @@ macro_rules! check {
 		perform = |
 			$ perform_self : ident,
 			$ perform_plugin_provider : ident,
+			$ perform_environment : ident,
 			$ perform_check_result_builder: ident
 		| {
 			$ ( $ perform_definition : tt ) *
@@ -71,6 +72,85 @@ macro_rules! check {
 					"help",
 					"print this help menu");
 
+				$ options_spec.optopt (
+					"",
+					"output-format",
+					"output format: nagios (default) or json",
+					"FORMAT");
+
+				$ options_spec.optopt (
+					"",
+					"check-timeout",
+					"give up with an unknown status after this long",
+					"DURATION");
+
+				$ options_spec.optopt (
+					"",
+					"submit-icinga",
+					"submit the result to this icinga 2 api instead of exiting \
+					with its status",
+					"URL");
+
+				$ options_spec.optopt (
+					"",
+					"icinga-username",
+					"username for icinga 2 api",
+					"USERNAME");
+
+				$ options_spec.optopt (
+					"",
+					"icinga-password",
+					"password for icinga 2 api",
+					"PASSWORD");
+
+				$ options_spec.optopt (
+					"",
+					"icinga-host",
+					"icinga host to submit the result for",
+					"HOST");
+
+				$ options_spec.optopt (
+					"",
+					"icinga-service",
+					"icinga service to submit the result for, if not a host check",
+					"SERVICE");
+
+				$ options_spec.optopt (
+					"",
+					"icinga-timeout",
+					"timeout for submission to icinga 2 api, default 30s",
+					"DURATION");
+
+				$ options_spec.optopt (
+					"",
+					"icinga-ca-file",
+					"ca certificates to verify the icinga 2 api with, usually \
+					/var/lib/icinga2/certs/ca.crt",
+					"FILE");
+
+				$ options_spec.optflag (
+					"",
+					"icinga-insecure",
+					"don't verify the icinga 2 api certificate");
+
+				$ options_spec.optmulti (
+					"",
+					"config",
+					"read options from this check's section of a JSON file",
+					"FILE");
+
+				$ options_spec.optmulti (
+					"",
+					"option-from-env",
+					"read an option's value from an environment variable",
+					"NAME=VARIABLE");
+
+				$ options_spec.optopt (
+					"",
+					"state-directory",
+					"where to keep state between runs, for checks which need it",
+					"DIR");
+
 				$ ( $ options_spec_definition ) *
 
 				$ options_spec
@@ -97,6 +177,7 @@ macro_rules! check {
 			fn perform_check (
 				& $ perform_self,
 				$ perform_plugin_provider : & PluginProvider,
+				$ perform_environment : & Environment,
 			) -> Result <CheckResult, Box <error::Error>> {
 
 				let mut $ perform_check_result_builder =
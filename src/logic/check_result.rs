@@ -1,11 +1,21 @@
+use logic::performance_data::*;
 use logic::plugin_provider::*;
 
-#[ derive (Clone, Copy, Debug) ]
+#[ derive (Clone, Copy, Debug, PartialEq, Serialize) ]
 pub enum CheckStatus {
+
+	#[ serde (rename = "ok") ]
 	Ok,
+
+	#[ serde (rename = "warning") ]
 	Warning,
+
+	#[ serde (rename = "critical") ]
 	Critical,
+
+	#[ serde (rename = "unknown") ]
 	Unknown,
+
 }
 
 impl CheckStatus {
@@ -99,13 +109,66 @@ impl From <CheckStatus> for i32 {
 
 }
 
+#[ derive (Clone, Debug, Serialize) ]
+pub struct CheckStatusMessage {
+
+	#[ serde (rename = "status") ]
+	status: CheckStatus,
+
+	#[ serde (rename = "message") ]
+	message: String,
+
+}
+
+impl CheckStatusMessage {
+
+	pub fn new <IntoString: Into <String>> (
+		status: CheckStatus,
+		message: IntoString,
+	) -> CheckStatusMessage {
+
+		CheckStatusMessage {
+			status: status,
+			message: message.into (),
+		}
+
+	}
+
+	pub fn status (
+		& self,
+	) -> & CheckStatus {
+		& self.status
+	}
+
+	pub fn message (
+		& self,
+	) -> & str {
+		self.message.as_str ()
+	}
+
+}
+
+#[ derive (Serialize) ]
 pub struct CheckResult {
+
+	#[ serde (rename = "status") ]
 	status: CheckStatus,
+
+	#[ serde (rename = "prefix") ]
 	prefix: String,
+
+	#[ serde (rename = "status-message") ]
 	status_message: String,
-	status_messages: Vec <String>,
-	performance_data: Vec <String>,
+
+	#[ serde (rename = "status-messages") ]
+	status_messages: Vec <CheckStatusMessage>,
+
+	#[ serde (rename = "performance-data") ]
+	performance_data: Vec <PerformanceData>,
+
+	#[ serde (rename = "extra-information") ]
 	extra_information: Vec <String>,
+
 }
 
 impl CheckResult {
@@ -115,11 +178,21 @@ impl CheckResult {
 	pub fn new (
 		status: CheckStatus,
 		prefix: String,
-		status_messages: Vec <String>,
-		performance_data: Vec <String>,
+		status_messages: Vec <CheckStatusMessage>,
+		performance_data: Vec <PerformanceData>,
 		extra_information: Vec <String>,
 	) -> CheckResult {
 
+		let status_message =
+			if status_messages.is_empty () {
+				status.default_message ().to_string ()
+			} else {
+				status_messages.iter ().map (
+					|status_message|
+					status_message.message ()
+				).collect::<Vec <& str>> ().join (", ")
+			};
+
 		CheckResult {
 
 			status:
@@ -129,11 +202,7 @@ impl CheckResult {
 				prefix,
 
 			status_message:
-				if status_messages.is_empty () {
-					status.default_message ().to_string ()
-				} else {
-					status_messages.join (", ")
-				},
+				status_message,
 
 			status_messages:
 				status_messages,
@@ -170,13 +239,13 @@ impl CheckResult {
 
 	pub fn status_messages (
 		& self,
-	) -> & Vec <String> {
+	) -> & Vec <CheckStatusMessage> {
 		& self.status_messages
 	}
 
 	pub fn performance_data (
 		& self,
-	) -> & Vec <String> {
+	) -> & Vec <PerformanceData> {
 		& self.performance_data
 	}
 
@@ -190,8 +259,8 @@ impl CheckResult {
 
 pub struct CheckResultBuilder {
 	status: CheckStatus,
-	status_messages: Vec <String>,
-	performance_data: Vec <String>,
+	status_messages: Vec <CheckStatusMessage>,
+	performance_data: Vec <PerformanceData>,
 	extra_information: Vec <String>,
 }
 
@@ -219,7 +288,10 @@ impl CheckResultBuilder {
 	) {
 
 		self.status_messages.push (
-			message.into ());
+			CheckStatusMessage {
+				status: CheckStatus::Ok,
+				message: message.into (),
+			});
 
 	}
 
@@ -229,7 +301,10 @@ impl CheckResultBuilder {
 	) {
 
 		self.status_messages.push (
-			message.into ());
+			CheckStatusMessage {
+				status: CheckStatus::Unknown,
+				message: message.into (),
+			});
 
 		self.status.update (
 			CheckStatus::Unknown);
@@ -242,7 +317,10 @@ impl CheckResultBuilder {
 	) {
 
 		self.status_messages.push (
-			message.into ());
+			CheckStatusMessage {
+				status: CheckStatus::Warning,
+				message: message.into (),
+			});
 
 		self.status.update (
 			CheckStatus::Warning);
@@ -255,13 +333,29 @@ impl CheckResultBuilder {
 	) {
 
 		self.status_messages.push (
-			message.into ());
+			CheckStatusMessage {
+				status: CheckStatus::Critical,
+				message: message.into (),
+			});
 
 		self.status.update (
 			CheckStatus::Critical);
 
 	}
 
+	pub fn status_message (
+		& mut self,
+		status_message: CheckStatusMessage,
+	) {
+
+		self.status.update (
+			status_message.status);
+
+		self.status_messages.push (
+			status_message);
+
+	}
+
 	pub fn extra_information <IntoString: Into <String>> (
 		& mut self,
 		information_temp: IntoString,
@@ -279,6 +373,16 @@ impl CheckResultBuilder {
 
 	}
 
+	pub fn performance_data (
+		& mut self,
+		performance_data: PerformanceData,
+	) {
+
+		self.performance_data.push (
+			performance_data);
+
+	}
+
 	pub fn update_status (
 		& mut self,
 		new_status: CheckStatus,
@@ -325,7 +429,10 @@ impl CheckResultBuilder {
 
 				} else {
 
-					self.status_messages.join (", ")
+					self.status_messages.iter ().map (
+						|status_message|
+						status_message.message.as_str ()
+					).collect::<Vec <& str>> ().join (", ")
 
 				},
 
@@ -344,4 +451,42 @@ impl CheckResultBuilder {
 
 }
 
+#[ cfg (test) ]
+mod tests {
+
+	use super::*;
+
+	#[ test ]
+	fn new_keeps_message_severity () {
+
+		let check_result =
+			CheckResult::new (
+				CheckStatus::Critical,
+				"HTTP".to_string (),
+				vec! [
+					CheckStatusMessage::new (
+						CheckStatus::Ok,
+						"status 200"),
+					CheckStatusMessage::new (
+						CheckStatus::Critical,
+						"body mismatch (critical)"),
+				],
+				vec! [],
+				vec! [],
+			);
+
+		assert_eq! (
+			check_result.status_message (),
+			"status 200, body mismatch (critical)");
+
+		assert_eq! (
+			check_result.status_messages ().iter ().map (
+				|status_message| * status_message.status ()
+			).collect::<Vec <CheckStatus>> (),
+			vec! [ CheckStatus::Ok, CheckStatus::Critical ]);
+
+	}
+
+}
+
 // ex: noet ts=4 filetype=rust
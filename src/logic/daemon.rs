@@ -0,0 +1,1204 @@
+use getopts;
+use serde_json;
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+
+use logic::arg_helper;
+use logic::check_helper;
+use logic::check_result::*;
+use logic::environment::*;
+use logic::multi_call::*;
+use logic::plugin_provider::*;
+use logic::runner::*;
+use logic::simple_error::*;
+use logic::state::*;
+
+pub type PluginProvidersFn =
+	fn () -> Vec <Box <PluginProvider>>;
+
+// ---------- configuration
+
+#[ derive (Clone, Debug, Deserialize) ]
+pub struct DaemonConfig {
+
+	#[ serde (rename = "listen", default = "default_listen") ]
+	pub listen: String,
+
+	#[ serde (rename = "checks") ]
+	pub checks: Vec <DaemonCheckConfig>,
+
+}
+
+#[ derive (Clone, Debug, Deserialize) ]
+pub struct DaemonCheckConfig {
+
+	#[ serde (rename = "name") ]
+	pub name: String,
+
+	#[ serde (rename = "provider") ]
+	pub provider: String,
+
+	#[ serde (rename = "arguments", default) ]
+	pub arguments: Vec <String>,
+
+	#[ serde (rename = "interval") ]
+	pub interval: String,
+
+}
+
+fn default_listen (
+) -> String {
+	"127.0.0.1:9271".to_string ()
+}
+
+impl DaemonConfig {
+
+	pub fn load (
+		config_path: & str,
+	) -> Result <DaemonConfig, Box <error::Error>> {
+
+		let mut config_string =
+			String::new ();
+
+		File::open (
+			config_path,
+		) ?.read_to_string (
+			& mut config_string,
+		) ?;
+
+		Ok (
+			serde_json::from_str (
+				& config_string,
+			) ?
+		)
+
+	}
+
+}
+
+// ---------- state
+
+pub struct DaemonCheckState {
+	provider_name: String,
+	check_result: Option <CheckResult>,
+	last_run: Option <time::SystemTime>,
+	last_duration: Option <time::Duration>,
+}
+
+pub type DaemonState =
+	Arc <Mutex <BTreeMap <String, DaemonCheckState>>>;
+
+// ---------- scheduler
+
+pub fn run_daemon_from_command_line (
+	plugin_providers_fn: PluginProvidersFn,
+	arguments: Vec <String>,
+) -> Result <(), Box <error::Error>> {
+
+	let mut options_spec =
+		getopts::Options::new ();
+
+	options_spec.reqopt (
+		"",
+		"config",
+		"daemon configuration file, in JSON format",
+		"FILE");
+
+	options_spec.optopt (
+		"",
+		"listen",
+		"address to serve metrics on, overrides the configuration",
+		"ADDRESS");
+
+	let options_matches =
+		options_spec.parse (
+			arguments,
+		) ?;
+
+	let mut daemon_config =
+		DaemonConfig::load (
+			& arg_helper::parse_string_required (
+				& options_matches,
+				"config",
+			) ?,
+		) ?;
+
+	if let Some (listen) =
+		arg_helper::parse_string (
+			& options_matches,
+			"listen",
+		) ? {
+
+		daemon_config.listen = listen;
+
+	}
+
+	// bind first, so a bad address fails before any checks run
+
+	let listener =
+		TcpListener::bind (
+			daemon_config.listen.as_str (),
+		).map_err (
+			|error|
+			format! (
+				"unable to listen on {}: {}",
+				daemon_config.listen,
+				error),
+		) ?;
+
+	let daemon_state =
+		start_daemon (
+			plugin_providers_fn,
+			& daemon_config,
+		) ?;
+
+	serve_metrics (
+		listener,
+		daemon_state);
+
+	Ok (())
+
+}
+
+pub fn start_daemon (
+	plugin_providers_fn: PluginProvidersFn,
+	daemon_config: & DaemonConfig,
+) -> Result <DaemonState, Box <error::Error>> {
+
+	let daemon_state: DaemonState =
+		Arc::new (Mutex::new (BTreeMap::new ()));
+
+	// verify everything before starting any threads
+
+	let plugin_providers =
+		plugin_providers_fn ();
+
+	let mut check_intervals: Vec <time::Duration> =
+		Vec::new ();
+
+	for check_config in daemon_config.checks.iter () {
+
+		let plugin_provider =
+			find_plugin_provider (
+				& plugin_providers,
+				& check_config.provider,
+			).ok_or_else (
+				||
+
+				format! (
+					"unknown provider for check {}: {}",
+					check_config.name,
+					check_config.provider)
+
+			) ?;
+
+		let check_interval =
+			arg_helper::parse_duration_string (
+				& check_config.interval,
+				"interval",
+			) ?;
+
+		// anything shorter would just spin
+
+		if check_interval < time::Duration::from_secs (1) {
+
+			return Err (Box::new (
+				SimpleError::from (
+					format! (
+						"interval for check {} must be at least one second: {}",
+						check_config.name,
+						check_config.interval))));
+
+		}
+
+		check_intervals.push (
+			check_interval);
+
+		let mut daemon_state_locked =
+			daemon_state.lock ().unwrap ();
+
+		if daemon_state_locked.contains_key (
+			& check_config.name) {
+
+			return Err (Box::new (
+				SimpleError::from (
+					format! (
+						"duplicated check name: {}",
+						check_config.name))));
+
+		}
+
+		daemon_state_locked.insert (
+			check_config.name.to_string (),
+			DaemonCheckState {
+				provider_name: plugin_provider.name ().to_string (),
+				check_result: None,
+				last_run: None,
+				last_duration: None,
+			});
+
+	}
+
+	// start a thread for each check
+
+	for (check_config, check_interval) in daemon_config.checks.iter ().zip (
+		check_intervals.into_iter ()) {
+
+		let check_config =
+			check_config.clone ();
+
+		let daemon_state =
+			daemon_state.clone ();
+
+		thread::spawn (
+			move ||
+
+			run_check_loop (
+				plugin_providers_fn,
+				check_config,
+				check_interval,
+				daemon_state)
+
+		);
+
+	}
+
+	Ok (daemon_state)
+
+}
+
+fn run_check_loop (
+	plugin_providers_fn: PluginProvidersFn,
+	check_config: DaemonCheckConfig,
+	check_interval: time::Duration,
+	daemon_state: DaemonState,
+) {
+
+	// plugin providers are not shareable between threads
+
+	let plugin_providers =
+		plugin_providers_fn ();
+
+	let plugin_provider =
+		find_plugin_provider (
+			& plugin_providers,
+			& check_config.provider,
+		).unwrap ();
+
+	// a check which hangs must not freeze its gauges, so each run is limited
+	// to --check-timeout, or the interval if that isn't given
+
+	let check_timeout =
+		check_timeout_from_arguments (
+			plugin_provider,
+			& check_config.arguments,
+		).unwrap_or (
+			check_interval);
+
+	// a run which timed out is still going, and we don't start another
+	// alongside it
+
+	let mut previous_run: Option <mpsc::Receiver <CheckResult>> =
+		None;
+
+	loop {
+
+		let start_time =
+			time::Instant::now ();
+
+		let check_result =
+			if previous_run_finished (& mut previous_run) {
+
+				run_check_with_timeout (
+					plugin_providers_fn,
+					plugin_provider.prefix (),
+					& check_config,
+					check_timeout,
+					& mut previous_run)
+
+			} else {
+
+				CheckResult::new (
+					CheckStatus::Unknown,
+					plugin_provider.prefix ().to_string (),
+					vec! [
+						CheckStatusMessage::new (
+							CheckStatus::Unknown,
+							"previous run still in progress".to_string ()),
+					],
+					vec! [],
+					vec! [],
+				)
+
+			};
+
+		let duration =
+			start_time.elapsed ();
+
+		{
+
+			let mut daemon_state_locked =
+				daemon_state.lock ().unwrap ();
+
+			let check_state =
+				daemon_state_locked.get_mut (
+					& check_config.name,
+				).unwrap ();
+
+			check_state.check_result = Some (check_result);
+			check_state.last_run = Some (time::SystemTime::now ());
+			check_state.last_duration = Some (duration);
+
+		}
+
+		if duration < check_interval {
+
+			thread::sleep (
+				check_interval - duration);
+
+		}
+
+	}
+
+}
+
+fn check_timeout_from_arguments (
+	plugin_provider: & PluginProvider,
+	arguments: & [String],
+) -> Option <time::Duration> {
+
+	let options_spec =
+		plugin_provider.build_options_spec ();
+
+	expand_arguments (
+		plugin_provider,
+		arguments,
+	).ok ().and_then (
+		|arguments|
+		options_spec.parse (
+			arguments,
+		).ok ()
+	).and_then (
+		|options_matches|
+		arg_helper::parse_duration (
+			& options_matches,
+			"check-timeout",
+		).unwrap_or (None)
+	)
+
+}
+
+fn previous_run_finished (
+	previous_run: & mut Option <mpsc::Receiver <CheckResult>>,
+) -> bool {
+
+	let finished =
+		match * previous_run {
+
+		Some (ref receiver) =>
+			match receiver.try_recv () {
+				Err (mpsc::TryRecvError::Empty) => false,
+				_ => true,
+			},
+
+		None =>
+			true,
+
+	};
+
+	if finished {
+		* previous_run = None;
+	}
+
+	finished
+
+}
+
+// the check runs on its own thread, which is abandoned if it times out, and
+// its receiver kept so we can tell when it has finished
+
+fn run_check_with_timeout (
+	plugin_providers_fn: PluginProvidersFn,
+	prefix: & str,
+	check_config: & DaemonCheckConfig,
+	check_timeout: time::Duration,
+	previous_run: & mut Option <mpsc::Receiver <CheckResult>>,
+) -> CheckResult {
+
+	let (sender, receiver) =
+		mpsc::channel ();
+
+	let provider_name =
+		check_config.provider.to_string ();
+
+	let arguments =
+		check_config.arguments.clone ();
+
+	thread::spawn (
+		move || {
+
+		let plugin_providers =
+			plugin_providers_fn ();
+
+		let plugin_provider =
+			find_plugin_provider (
+				& plugin_providers,
+				& provider_name,
+			).unwrap ();
+
+		// the receiver is gone if the daemon has moved on
+
+		sender.send (
+			run_check_once (
+				plugin_provider,
+				& arguments),
+		).unwrap_or (());
+
+	});
+
+	let received =
+		receiver.recv_timeout (
+			check_timeout);
+
+	match received {
+
+		Ok (check_result) =>
+			check_result,
+
+		Err (_) => {
+
+			* previous_run = Some (receiver);
+
+			CheckResult::new (
+				CheckStatus::Unknown,
+				prefix.to_string (),
+				vec! [
+					CheckStatusMessage::new (
+						CheckStatus::Unknown,
+						format! (
+							"check timed out after {}",
+							check_helper::display_duration_long (
+								& check_timeout))),
+				],
+				vec! [],
+				vec! [],
+			)
+
+		},
+
+	}
+
+}
+
+fn run_check_once (
+	plugin_provider: & PluginProvider,
+	arguments: & [String],
+) -> CheckResult {
+
+	let options_spec =
+		plugin_provider.build_options_spec ();
+
+	let arguments =
+		match expand_arguments (
+			plugin_provider,
+			arguments,
+		) {
+
+		Ok (arguments) =>
+			arguments,
+
+		Err (error) =>
+			return CheckResult::new (
+				CheckStatus::Unknown,
+				plugin_provider.prefix ().to_string (),
+				vec! [
+					CheckStatusMessage::new (
+						CheckStatus::Unknown,
+						"unable to load options"),
+					CheckStatusMessage::new (
+						CheckStatus::Unknown,
+						error.description ()),
+				],
+				vec! [],
+				vec! [],
+			),
+
+	};
+
+	match options_spec.parse (
+		arguments,
+	) {
+
+		Ok (options_matches) => {
+
+			let state_directory =
+				match parse_state_directory (
+					& options_matches,
+				) {
+
+				Ok (state_directory) =>
+					state_directory,
+
+				Err (error) =>
+					return CheckResult::new (
+						CheckStatus::Unknown,
+						plugin_provider.prefix ().to_string (),
+						vec! [
+							CheckStatusMessage::new (
+								CheckStatus::Unknown,
+								format! (
+									"invalid arguments: {}",
+									error.description ())),
+						],
+						vec! [],
+						vec! [],
+					),
+
+			};
+
+			let mut environment =
+				SystemEnvironment::new ();
+
+			environment.set_state_directory (
+				state_directory);
+
+			run_from_options_matches (
+				plugin_provider,
+				& options_spec,
+				& options_matches,
+				& environment)
+
+		},
+
+		Err (error) =>
+			CheckResult::new (
+				CheckStatus::Unknown,
+				plugin_provider.prefix ().to_string (),
+				vec! [
+					CheckStatusMessage::new (
+						CheckStatus::Unknown,
+						format! (
+							"invalid arguments: {}",
+							error)),
+				],
+				vec! [],
+				vec! [],
+			),
+
+	}
+
+}
+
+// ---------- metrics server
+
+pub fn serve_metrics (
+	listener: TcpListener,
+	daemon_state: DaemonState,
+) {
+
+	for stream_result in listener.incoming () {
+
+		if let Ok (stream) = stream_result {
+
+			// errors here only affect this client
+
+			handle_metrics_request (
+				stream,
+				& daemon_state,
+			).unwrap_or (());
+
+		}
+
+	}
+
+}
+
+pub fn spawn_metrics_server (
+	listener: TcpListener,
+	daemon_state: DaemonState,
+) -> Result <SocketAddr, Box <error::Error>> {
+
+	let local_address =
+		listener.local_addr () ?;
+
+	thread::spawn (
+		move ||
+
+		serve_metrics (
+			listener,
+			daemon_state)
+
+	);
+
+	Ok (local_address)
+
+}
+
+fn handle_metrics_request (
+	stream: TcpStream,
+	daemon_state: & DaemonState,
+) -> Result <(), Box <error::Error>> {
+
+	stream.set_read_timeout (
+		Some (time::Duration::from_secs (10)),
+	) ?;
+
+	let mut reader =
+		BufReader::new (
+			stream.try_clone () ?);
+
+	let mut request_line =
+		String::new ();
+
+	reader.read_line (
+		& mut request_line,
+	) ?;
+
+	// consume the headers
+
+	loop {
+
+		let mut header_line =
+			String::new ();
+
+		if reader.read_line (& mut header_line) ? == 0
+			|| header_line.trim ().is_empty () {
+
+			break;
+
+		}
+
+	}
+
+	let request_parts: Vec <& str> =
+		request_line.split_whitespace ().collect ();
+
+	let (status, content_type, body) =
+		match (request_parts.get (0), request_parts.get (1)) {
+
+		(Some (& "GET"), Some (& "/metrics")) => (
+			"200 OK",
+			"text/plain; version=0.0.4",
+			render_metrics (
+				& daemon_state.lock ().unwrap ()),
+		),
+
+		(Some (& "GET"), Some (_)) => (
+			"404 Not Found",
+			"text/plain",
+			"not found\n".to_string (),
+		),
+
+		_ => (
+			"405 Method Not Allowed",
+			"text/plain",
+			"method not allowed\n".to_string (),
+		),
+
+	};
+
+	let mut stream =
+		stream;
+
+	write! (
+		stream,
+		"HTTP/1.0 {}\r\n\
+		Content-Type: {}\r\n\
+		Content-Length: {}\r\n\
+		Connection: close\r\n\
+		\r\n\
+		{}",
+		status,
+		content_type,
+		body.len (),
+		body,
+	) ?;
+
+	stream.flush () ?;
+
+	Ok (())
+
+}
+
+// ---------- prometheus text format
+
+fn render_metrics (
+	daemon_state: & BTreeMap <String, DaemonCheckState>,
+) -> String {
+
+	let mut output =
+		String::new ();
+
+	let mut status_lines: Vec <String> = Vec::new ();
+	let mut timestamp_lines: Vec <String> = Vec::new ();
+	let mut duration_lines: Vec <String> = Vec::new ();
+	let mut performance_data_lines: Vec <String> = Vec::new ();
+
+	for (check_name, check_state) in daemon_state.iter () {
+
+		let check_labels =
+			format! (
+				"check=\"{}\",provider=\"{}\"",
+				escape_label_value (check_name),
+				escape_label_value (& check_state.provider_name));
+
+		let check_result =
+			match check_state.check_result {
+				Some (ref check_result) => check_result,
+				None => continue,
+			};
+
+		status_lines.push (
+			format! (
+				"wbs_check_status{{{}}} {}",
+				check_labels,
+				* check_result.status () as i32));
+
+		if let Some (last_run) =
+			check_state.last_run {
+
+			if let Ok (since_epoch) =
+				last_run.duration_since (
+					time::UNIX_EPOCH) {
+
+				timestamp_lines.push (
+					format! (
+						"wbs_check_last_run_timestamp_seconds{{{}}} {}",
+						check_labels,
+						since_epoch.as_secs ()));
+
+			}
+
+		}
+
+		if let Some (last_duration) =
+			check_state.last_duration {
+
+			duration_lines.push (
+				format! (
+					"wbs_check_duration_seconds{{{}}} {}",
+					check_labels,
+					check_helper::duration_as_seconds (
+						& last_duration)));
+
+		}
+
+		for performance_data in check_result.performance_data () {
+
+			performance_data_lines.push (
+				format! (
+					"wbs_check_performance_data{{{},label=\"{}\",unit=\"{}\"}} {}",
+					check_labels,
+					escape_label_value (performance_data.label ()),
+					escape_label_value (performance_data.unit ().as_str ()),
+					format_metric_value (performance_data.value ())));
+
+		}
+
+	}
+
+	for (name, help, lines) in vec! [
+
+		(
+			"wbs_check_status",
+			"Check status, 0 ok, 1 warning, 2 critical, 3 unknown",
+			status_lines,
+		),
+
+		(
+			"wbs_check_last_run_timestamp_seconds",
+			"Time the check last completed",
+			timestamp_lines,
+		),
+
+		(
+			"wbs_check_duration_seconds",
+			"Time taken by the last run of the check",
+			duration_lines,
+		),
+
+		(
+			"wbs_check_performance_data",
+			"Performance data reported by the last run of the check",
+			performance_data_lines,
+		),
+
+	] {
+
+		output.push_str (
+			& format! (
+				"# HELP {} {}\n# TYPE {} gauge\n",
+				name,
+				help,
+				name));
+
+		for line in lines {
+			output.push_str (& line);
+			output.push ('\n');
+		}
+
+	}
+
+	output
+
+}
+
+fn escape_label_value (
+	value: & str,
+) -> String {
+
+	value
+		.replace ("\\", "\\\\")
+		.replace ("\"", "\\\"")
+		.replace ("\n", "\\n")
+
+}
+
+fn format_metric_value (
+	value: f64,
+) -> String {
+
+	if value.is_nan () {
+		"NaN".to_string ()
+	} else if value.is_infinite () && value > 0.0 {
+		"+Inf".to_string ()
+	} else if value.is_infinite () {
+		"-Inf".to_string ()
+	} else {
+		format! ("{}", value)
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::env;
+	use std::fs;
+	use std::io::Read;
+	use std::io::Write;
+	use std::net::TcpListener;
+	use std::net::TcpStream;
+	use std::thread;
+	use std::time;
+
+	use checks;
+	use logic::*;
+	use logic::daemon::*;
+
+	fn plugin_providers (
+	) -> Vec <Box <PluginProvider>> {
+
+		vec! [
+			checks::generic::new (),
+		]
+
+	}
+
+	fn check_config (
+		name: & str,
+		arguments: & [& str],
+	) -> DaemonCheckConfig {
+
+		DaemonCheckConfig {
+			name: name.to_string (),
+			provider: "generic".to_string (),
+			arguments: arguments.iter ().map (
+				|argument| argument.to_string ()
+			).collect (),
+			interval: "1h".to_string (),
+		}
+
+	}
+
+	// polls the metrics endpoint until the check has reported
+
+	fn fetch_metrics (
+		metrics_address: & str,
+		wanted: & str,
+	) -> String {
+
+		let start_time =
+			time::Instant::now ();
+
+		loop {
+
+			let mut stream =
+				TcpStream::connect (
+					metrics_address,
+				).unwrap ();
+
+			stream.write_all (
+				b"GET /metrics HTTP/1.0\r\n\r\n",
+			).unwrap ();
+
+			let mut response =
+				String::new ();
+
+			stream.read_to_string (
+				& mut response,
+			).unwrap ();
+
+			if response.contains (wanted)
+				|| start_time.elapsed () > time::Duration::from_secs (20) {
+
+				return response;
+
+			}
+
+			thread::sleep (
+				time::Duration::from_millis (50));
+
+		}
+
+	}
+
+	#[ test ]
+	fn serves_check_results_as_metrics () {
+
+		let result_path =
+			env::temp_dir ().join (
+				format! (
+					"wbs-monitoring-daemon-test-{}.json",
+					time::SystemTime::now ().duration_since (
+						time::UNIX_EPOCH,
+					).unwrap ().subsec_nanos ()));
+
+		fs::File::create (
+			& result_path,
+		).unwrap ().write_all (
+			b"{\"status\": \"warning\", \"status-message\": \"queue growing\", \
+			\"performance-data\": [{\"label\": \"queue\", \"value\": 42}]}",
+		).unwrap ();
+
+		let daemon_state =
+			start_daemon (
+				plugin_providers,
+				& DaemonConfig {
+					listen: "127.0.0.1:0".to_string (),
+					checks: vec! [
+						check_config (
+							"queue",
+							& [ "--file", result_path.to_str ().unwrap () ]),
+					],
+				},
+			).unwrap ();
+
+		let metrics_address =
+			spawn_metrics_server (
+				TcpListener::bind ("127.0.0.1:0").unwrap (),
+				daemon_state,
+			).unwrap ().to_string ();
+
+		let response =
+			fetch_metrics (
+				& metrics_address,
+				"wbs_check_status{");
+
+		fs::remove_file (
+			& result_path,
+		).unwrap ();
+
+		assert! (
+			response.starts_with ("HTTP/1.0 200 OK\r\n"),
+			"{}",
+			response);
+
+		for expected_line in vec! [
+			"wbs_check_status{check=\"queue\",provider=\"check-generic\"} 1\n",
+			"wbs_check_last_run_timestamp_seconds{check=\"queue\",provider=\"check-generic\"} ",
+			"wbs_check_duration_seconds{check=\"queue\",provider=\"check-generic\"} ",
+			"wbs_check_performance_data{check=\"queue\",provider=\"check-generic\",\
+				label=\"queue\",unit=\"\"} 42\n",
+		] {
+
+			assert! (
+				response.contains (expected_line),
+				"{:?} not in {}",
+				expected_line,
+				response);
+
+		}
+
+	}
+
+	#[ test ]
+	fn check_timeout_reports_unknown () {
+
+		// accepts connections but never answers
+
+		let silent_listener =
+			TcpListener::bind (
+				"127.0.0.1:0",
+			).unwrap ();
+
+		let target =
+			format! (
+				"http://{}/",
+				silent_listener.local_addr ().unwrap ());
+
+		let daemon_state =
+			start_daemon (
+				plugin_providers,
+				& DaemonConfig {
+					listen: "127.0.0.1:0".to_string (),
+					checks: vec! [
+						check_config (
+							"silent",
+							& [ "--target", & target, "--check-timeout", "1s" ]),
+					],
+				},
+			).unwrap ();
+
+		let start_time =
+			time::Instant::now ();
+
+		loop {
+
+			{
+
+				let daemon_state_locked =
+					daemon_state.lock ().unwrap ();
+
+				let check_state =
+					daemon_state_locked.get ("silent").unwrap ();
+
+				if let Some (ref check_result) = check_state.check_result {
+
+					assert_eq! (
+						(* check_result.status (), check_result.status_message ()),
+						(CheckStatus::Unknown, "check timed out after 1.00 second"));
+
+					break;
+
+				}
+
+			}
+
+			assert! (
+				start_time.elapsed () < time::Duration::from_secs (20));
+
+			thread::sleep (
+				time::Duration::from_millis (50));
+
+		}
+
+	}
+
+	#[ test ]
+	fn short_intervals_rejected () {
+
+		for interval in vec! [ "0s", "500ms" ] {
+
+			let mut quick_config =
+				check_config (
+					"quick",
+					& [ "--file", "/dev/null" ]);
+
+			quick_config.interval = interval.to_string ();
+
+			assert_eq! (
+				start_daemon (
+					plugin_providers,
+					& DaemonConfig {
+						listen: "127.0.0.1:0".to_string (),
+						checks: vec! [ quick_config ],
+					},
+				).err ().unwrap ().to_string (),
+				format! (
+					"interval for check quick must be at least one second: {}",
+					interval));
+
+		}
+
+	}
+
+	#[ test ]
+	fn hung_run_not_repeated () {
+
+		// accepts connections but never answers, so the first run hangs for
+		// the default request timeout
+
+		let silent_listener =
+			TcpListener::bind (
+				"127.0.0.1:0",
+			).unwrap ();
+
+		let target =
+			format! (
+				"http://{}/",
+				silent_listener.local_addr ().unwrap ());
+
+		let mut silent_config =
+			check_config (
+				"silent",
+				& [ "--target", & target, "--check-timeout", "1s" ]);
+
+		silent_config.interval = "1s".to_string ();
+
+		let daemon_state =
+			start_daemon (
+				plugin_providers,
+				& DaemonConfig {
+					listen: "127.0.0.1:0".to_string (),
+					checks: vec! [ silent_config ],
+				},
+			).unwrap ();
+
+		let start_time =
+			time::Instant::now ();
+
+		loop {
+
+			{
+
+				let daemon_state_locked =
+					daemon_state.lock ().unwrap ();
+
+				let check_state =
+					daemon_state_locked.get ("silent").unwrap ();
+
+				if let Some (ref check_result) = check_state.check_result {
+
+					if check_result.status_message ()
+						== "previous run still in progress" {
+
+						assert_eq! (
+							* check_result.status (),
+							CheckStatus::Unknown);
+
+						break;
+
+					}
+
+				}
+
+			}
+
+			assert! (
+				start_time.elapsed () < time::Duration::from_secs (20));
+
+			thread::sleep (
+				time::Duration::from_millis (50));
+
+		}
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
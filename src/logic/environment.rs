@@ -0,0 +1,378 @@
+extern crate glob;
+extern crate libc;
+
+use std::error;
+use std::ffi;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time;
+
+use logic::state::*;
+use lowlevel::apt;
+
+pub use lowlevel::apt::UpgradeSummary as AptUpgradeSummary;
+
+// ---------- environment
+
+// everything a check reads from the system it runs on goes through here, so
+// that checks can also be run against recorded fixtures
+
+pub trait Environment {
+
+	fn now (
+		& self,
+	) -> time::SystemTime;
+
+	fn read_file (
+		& self,
+		path: & Path,
+	) -> io::Result <Vec <u8>>;
+
+	fn file_metadata (
+		& self,
+		path: & Path,
+	) -> io::Result <FileMetadata>;
+
+	fn read_directory (
+		& self,
+		path: & Path,
+	) -> io::Result <Vec <String>>;
+
+	fn glob (
+		& self,
+		pattern: & str,
+	) -> Result <Vec <PathBuf>, Box <error::Error>>;
+
+	fn filesystem_space (
+		& self,
+		path: & Path,
+	) -> io::Result <FilesystemSpace>;
+
+	fn run_command (
+		& self,
+		program: & str,
+		arguments: & [String],
+	) -> io::Result <CommandOutput>;
+
+	fn apt_upgrade_summary (
+		& self,
+		root_path: & Path,
+	) -> Result <AptUpgradeSummary, Box <error::Error>>;
+
+	// what the check is currently doing, reported if it times out
+
+	fn enter_phase (
+		& self,
+		phase: & str,
+	);
+
+	// values kept between runs, under --state-directory
+
+	fn open_state (
+		& self,
+		plugin_name: & str,
+		instance_key: & str,
+	) -> Result <PluginState, Box <error::Error>>;
+
+}
+
+#[ derive (Clone, Copy, Debug) ]
+pub struct FileMetadata {
+	pub is_directory: bool,
+	pub size: u64,
+	pub modified: time::SystemTime,
+}
+
+#[ derive (Clone, Copy, Debug) ]
+pub struct FilesystemSpace {
+	pub total_bytes: u64,
+	pub available_bytes: u64,
+}
+
+#[ derive (Clone, Debug) ]
+pub struct CommandOutput {
+	pub exit_code: Option <i32>,
+	pub stdout: Vec <u8>,
+	pub stderr: Vec <u8>,
+}
+
+// ---------- check phase
+
+#[ derive (Clone) ]
+pub struct CheckPhase {
+	phase: Arc <Mutex <String>>,
+}
+
+impl CheckPhase {
+
+	pub fn new (
+		phase: & str,
+	) -> CheckPhase {
+
+		CheckPhase {
+			phase: Arc::new (Mutex::new (phase.to_string ())),
+		}
+
+	}
+
+	pub fn set (
+		& self,
+		phase: & str,
+	) {
+
+		* self.phase.lock ().unwrap () =
+			phase.to_string ();
+
+	}
+
+	pub fn get (
+		& self,
+	) -> String {
+
+		self.phase.lock ().unwrap ().clone ()
+
+	}
+
+}
+
+// ---------- system environment
+
+pub struct SystemEnvironment {
+	check_phase: CheckPhase,
+	state_directory: PathBuf,
+}
+
+impl SystemEnvironment {
+
+	pub fn new (
+	) -> SystemEnvironment {
+
+		SystemEnvironment {
+			check_phase: CheckPhase::new ("performing check"),
+			state_directory: PathBuf::from (DEFAULT_STATE_DIRECTORY),
+		}
+
+	}
+
+	pub fn set_state_directory (
+		& mut self,
+		state_directory: PathBuf,
+	) {
+
+		self.state_directory =
+			state_directory;
+
+	}
+
+	pub fn check_phase (
+		& self,
+	) -> CheckPhase {
+
+		self.check_phase.clone ()
+
+	}
+
+}
+
+impl Environment for SystemEnvironment {
+
+	fn now (
+		& self,
+	) -> time::SystemTime {
+
+		time::SystemTime::now ()
+
+	}
+
+	fn read_file (
+		& self,
+		path: & Path,
+	) -> io::Result <Vec <u8>> {
+
+		let mut contents =
+			Vec::new ();
+
+		fs::File::open (
+			path,
+		) ?.read_to_end (
+			& mut contents,
+		) ?;
+
+		Ok (contents)
+
+	}
+
+	fn file_metadata (
+		& self,
+		path: & Path,
+	) -> io::Result <FileMetadata> {
+
+		let metadata =
+			fs::metadata (
+				path,
+			) ?;
+
+		Ok (FileMetadata {
+			is_directory: metadata.is_dir (),
+			size: metadata.len (),
+			modified: metadata.modified () ?,
+		})
+
+	}
+
+	fn read_directory (
+		& self,
+		path: & Path,
+	) -> io::Result <Vec <String>> {
+
+		let mut file_names: Vec <String> =
+			Vec::new ();
+
+		for entry_result in fs::read_dir (
+			path,
+		) ? {
+
+			file_names.push (
+				entry_result ?
+					.file_name ()
+					.to_string_lossy ()
+					.into_owned ());
+
+		}
+
+		Ok (file_names)
+
+	}
+
+	fn glob (
+		& self,
+		pattern: & str,
+	) -> Result <Vec <PathBuf>, Box <error::Error>> {
+
+		Ok (
+			glob::glob (
+				pattern,
+			) ?.filter_map (
+				|path_result| path_result.ok ()
+			).collect ()
+		)
+
+	}
+
+	fn filesystem_space (
+		& self,
+		path: & Path,
+	) -> io::Result <FilesystemSpace> {
+
+		let path_c =
+			ffi::CString::new (
+				path.as_os_str ().as_bytes (),
+			).map_err (
+				|error|
+				io::Error::new (
+					io::ErrorKind::InvalidInput,
+					error),
+			) ?;
+
+		let mut filesystem_stats: libc::statfs =
+			unsafe {
+				mem::zeroed ()
+			};
+
+		let statfs_result =
+			unsafe {
+				libc::statfs (
+					path_c.as_ptr (),
+					& mut filesystem_stats)
+			};
+
+		if statfs_result != 0 {
+			return Err (io::Error::last_os_error ());
+		}
+
+		let block_size =
+			filesystem_stats.f_bsize as u64;
+
+		Ok (FilesystemSpace {
+			total_bytes: filesystem_stats.f_blocks as u64 * block_size,
+			available_bytes: filesystem_stats.f_bavail as u64 * block_size,
+		})
+
+	}
+
+	fn run_command (
+		& self,
+		program: & str,
+		arguments: & [String],
+	) -> io::Result <CommandOutput> {
+
+		let output =
+			process::Command::new (
+				program,
+			).args (
+				arguments,
+			).output () ?;
+
+		Ok (CommandOutput {
+			exit_code: output.status.code (),
+			stdout: output.stdout,
+			stderr: output.stderr,
+		})
+
+	}
+
+	fn apt_upgrade_summary (
+		& self,
+		root_path: & Path,
+	) -> Result <AptUpgradeSummary, Box <error::Error>> {
+
+		apt::configuration_set_string (
+			"Dir",
+			root_path.to_string_lossy (),
+		) ?;
+
+		apt::configuration_set_string (
+			"Dir::State::Status",
+			root_path.join (
+				"var/lib/dpkg/status",
+			).to_string_lossy (),
+		) ?;
+
+		apt::upgrade_summary_get ()
+
+	}
+
+	fn enter_phase (
+		& self,
+		phase: & str,
+	) {
+
+		self.check_phase.set (
+			phase);
+
+	}
+
+	fn open_state (
+		& self,
+		plugin_name: & str,
+		instance_key: & str,
+	) -> Result <PluginState, Box <error::Error>> {
+
+		PluginState::open (
+			& self.state_directory,
+			plugin_name,
+			instance_key)
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,556 @@
+use getopts;
+use hyper::Uri;
+use hyper::header::Authorization;
+use hyper::header::Basic;
+use serde_json;
+
+use std::error;
+use std::time;
+
+use logic::arg_helper;
+use logic::check_result::*;
+use logic::performance_data::*;
+use logic::simple_error::*;
+use lowlevel::http::*;
+
+const PROCESS_CHECK_RESULT_PATH: & str =
+	"/v1/actions/process-check-result";
+
+// ---------- passive submission
+
+// sends a check result to the icinga 2 api, for checks run from cron rather
+// than scheduled by icinga itself
+
+#[ derive (Clone) ]
+pub struct IcingaSubmission {
+	url: Uri,
+	username: Option <String>,
+	password: Option <String>,
+	host_name: String,
+	service_name: Option <String>,
+	timeout: time::Duration,
+	tls_options: HttpTlsOptions,
+}
+
+#[ derive (Serialize) ]
+struct IcingaCheckResult <'a> {
+
+	#[ serde (rename = "type") ]
+	object_type: & 'a str,
+
+	#[ serde (rename = "filter") ]
+	filter: & 'a str,
+
+	#[ serde (rename = "filter_vars") ]
+	filter_vars: IcingaFilterVars <'a>,
+
+	#[ serde (rename = "exit_status") ]
+	exit_status: i32,
+
+	#[ serde (rename = "plugin_output") ]
+	plugin_output: String,
+
+	#[ serde (rename = "performance_data") ]
+	performance_data: Vec <String>,
+
+}
+
+#[ derive (Serialize) ]
+struct IcingaFilterVars <'a> {
+
+	#[ serde (rename = "host_name") ]
+	host_name: & 'a str,
+
+	#[ serde (rename = "service_name", skip_serializing_if = "Option::is_none") ]
+	service_name: Option <& 'a str>,
+
+}
+
+#[ derive (Deserialize) ]
+struct IcingaResponse {
+
+	#[ serde (rename = "results", default) ]
+	results: Vec <IcingaResponseResult>,
+
+}
+
+#[ derive (Deserialize) ]
+struct IcingaResponseResult {
+
+	#[ serde (rename = "code") ]
+	code: f64,
+
+	#[ serde (rename = "status", default) ]
+	status: String,
+
+}
+
+impl IcingaSubmission {
+
+	pub fn from_options_matches (
+		options_matches: & getopts::Matches,
+	) -> Result <Option <IcingaSubmission>, Box <error::Error>> {
+
+		let url_string =
+			match options_matches.opt_str (
+				"submit-icinga",
+			) {
+				Some (url_string) => url_string,
+				None => return Ok (None),
+			};
+
+		let url: Uri =
+			url_string.parse ().map_err (
+				|_|
+				format! (
+					"invalid url for --submit-icinga: {}",
+					url_string),
+			) ?;
+
+		match (url.scheme (), url.host ()) {
+
+			(Some ("http"), Some (_)) | (Some ("https"), Some (_)) =>
+				(),
+
+			_ =>
+				return Err (Box::new (
+					SimpleError::from (
+						format! (
+							"invalid url for --submit-icinga: {}",
+							url_string)))),
+
+		}
+
+		Ok (Some (IcingaSubmission {
+
+			url: url,
+
+			username:
+				options_matches.opt_str (
+					"icinga-username"),
+
+			password:
+				options_matches.opt_str (
+					"icinga-password"),
+
+			host_name:
+				arg_helper::parse_string_required (
+					options_matches,
+					"icinga-host",
+				) ?,
+
+			service_name:
+				options_matches.opt_str (
+					"icinga-service"),
+
+			timeout:
+				arg_helper::parse_duration_or_default (
+					options_matches,
+					"icinga-timeout",
+					& time::Duration::new (30, 0),
+				) ?,
+
+			// the icinga 2 api uses its own ca, so the default roots are no
+			// use against a stock install
+
+			tls_options: HttpTlsOptions {
+				ca_file: options_matches.opt_str ("icinga-ca-file"),
+				client_cert: None,
+				client_key: None,
+				insecure: options_matches.opt_present ("icinga-insecure"),
+			},
+
+		}))
+
+	}
+
+	pub fn submit (
+		& self,
+		check_result: & CheckResult,
+	) -> Result <(), Box <error::Error>> {
+
+		let secure =
+			self.url.scheme () == Some ("https");
+
+		// checked in from_options_matches
+
+		let host =
+			self.url.host ().unwrap_or ("").to_string ();
+
+		// build request
+
+		let icinga_check_result =
+			IcingaCheckResult {
+
+			object_type:
+				if self.service_name.is_some () {
+					"Service"
+				} else {
+					"Host"
+				},
+
+			filter:
+				if self.service_name.is_some () {
+					"host.name==host_name && service.name==service_name"
+				} else {
+					"host.name==host_name"
+				},
+
+			filter_vars: IcingaFilterVars {
+				host_name: & self.host_name,
+				service_name: self.service_name.as_ref ().map (
+					|service_name| service_name.as_str ()),
+			},
+
+			exit_status:
+				* check_result.status () as i32,
+
+			plugin_output:
+				plugin_output (
+					check_result),
+
+			performance_data:
+				check_result.performance_data ().iter ().map (
+					|performance_data| performance_data.to_string ()
+				).collect (),
+
+		};
+
+		let request_body =
+			serde_json::to_vec (
+				& icinga_check_result,
+			) ?;
+
+		// no content-length here, HttpConnection sets it from the body
+
+		let mut request_headers: Vec <(String, String)> =
+			vec! [
+				("Accept".to_string (), "application/json".to_string ()),
+				("Content-Type".to_string (), "application/json".to_string ()),
+			];
+
+		if let Some (ref username) = self.username {
+
+			request_headers.push ((
+				"Authorization".to_string (),
+				Authorization (
+					Basic {
+						username: username.to_string (),
+						password: self.password.clone (),
+					},
+				).to_string (),
+			));
+
+		}
+
+		// perform request
+
+		let mut http_connection =
+			HttpConnection::connect (
+				host.to_string (),
+				self.url.port ().map (|port| port as u64),
+				secure,
+				host.to_string (),
+				& self.tls_options,
+			) ?;
+
+		let http_response =
+			http_connection.perform (
+				HttpRequest {
+					method: HttpMethod::Post,
+					path: format! (
+						"{}{}",
+						self.url.path ().trim_right_matches ('/'),
+						PROCESS_CHECK_RESULT_PATH),
+					headers: request_headers,
+					body: request_body,
+				},
+				self.timeout,
+			) ?;
+
+		// check response
+
+		if http_response.status_code () != 200 {
+
+			return Err (Box::new (
+				SimpleError::from (
+					format! (
+						"icinga returned {} {}",
+						http_response.status_code (),
+						http_response.status_message ()))));
+
+		}
+
+		let icinga_response: IcingaResponse =
+			serde_json::from_slice (
+				http_response.body (),
+			) ?;
+
+		if icinga_response.results.is_empty () {
+
+			return Err (Box::new (
+				SimpleError::from (
+					"icinga did not find the object to update")));
+
+		}
+
+		for result in icinga_response.results {
+
+			if result.code != 200.0 {
+
+				return Err (Box::new (
+					SimpleError::from (
+						format! (
+							"icinga rejected the result: {}",
+							result.status))));
+
+			}
+
+		}
+
+		Ok (())
+
+	}
+
+}
+
+fn plugin_output (
+	check_result: & CheckResult,
+) -> String {
+
+	let mut lines =
+		vec! [
+			format! (
+				"{} {}: {}",
+				check_result.prefix (),
+				check_result.status ().prefix (),
+				check_result.status_message ()),
+		];
+
+	lines.extend (
+		check_result.extra_information ().iter ().cloned ());
+
+	lines.join ("\n")
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use getopts;
+
+	use std::error::Error;
+
+	use serde_json;
+
+	use logic::*;
+	use logic::icinga::*;
+	use logic::testing::*;
+
+	const JSON_HEADERS: & 'static [(& 'static str, & 'static str)] =
+		& [ ("Content-Type", "application/json") ];
+
+	fn options_matches (
+		arguments: & [& str],
+	) -> getopts::Matches {
+
+		let mut options_spec =
+			getopts::Options::new ();
+
+		for option_name in vec! [
+			"submit-icinga",
+			"icinga-username",
+			"icinga-password",
+			"icinga-host",
+			"icinga-service",
+			"icinga-timeout",
+			"icinga-ca-file",
+		] {
+
+			options_spec.optopt (
+				"",
+				option_name,
+				"",
+				"");
+
+		}
+
+		options_spec.optflag (
+			"",
+			"icinga-insecure",
+			"");
+
+		options_spec.parse (
+			arguments,
+		).unwrap ()
+
+	}
+
+	fn check_result (
+	) -> CheckResult {
+
+		CheckResult::new (
+			CheckStatus::Warning,
+			"APT".to_string (),
+			vec! [
+				CheckStatusMessage::new (
+					CheckStatus::Warning,
+					"3 packages need upgrading (warning)"),
+			],
+			vec! [
+				PerformanceData::new (
+					"upgrade",
+					3.0,
+					PerformanceDataUnit::None,
+				).minimum (
+					0.0,
+				),
+			],
+			vec! [],
+		)
+
+	}
+
+	#[ test ]
+	fn submits_service_result () {
+
+		let (port, receiver) =
+			start_stand_in_http_server (
+				"200 OK",
+				JSON_HEADERS,
+				"{\"results\":[{\"code\":200.0,\"status\":\"Successfully processed check result\"}]}");
+
+		let url =
+			format! (
+				"http://127.0.0.1:{}",
+				port);
+
+		let icinga_submission =
+			IcingaSubmission::from_options_matches (
+				& options_matches (& [
+					"--submit-icinga", & url,
+					"--icinga-username", "root",
+					"--icinga-password", "icinga",
+					"--icinga-host", "web.example.com",
+					"--icinga-service", "apt",
+				]),
+			).unwrap ().unwrap ();
+
+		icinga_submission.submit (
+			& check_result (),
+		).unwrap ();
+
+		let request =
+			receiver.recv ().unwrap ();
+
+		assert_eq! (
+			request.request_line,
+			"POST /v1/actions/process-check-result HTTP/1.1");
+
+		assert_eq! (
+			request.header ("authorization"),
+			Some ("Basic cm9vdDppY2luZ2E="));
+
+		assert_eq! (
+			request.header ("content-length"),
+			Some (request.body.len ().to_string ().as_str ()));
+
+		let body: serde_json::Value =
+			serde_json::from_str (
+				& request.body,
+			).unwrap ();
+
+		assert_eq! (
+			body,
+			json! ({
+				"type": "Service",
+				"filter": "host.name==host_name && service.name==service_name",
+				"filter_vars": {
+					"host_name": "web.example.com",
+					"service_name": "apt",
+				},
+				"exit_status": 1,
+				"plugin_output": "APT WARNING: 3 packages need upgrading (warning)",
+				"performance_data": [ "upgrade=3;;;0" ],
+			}));
+
+	}
+
+	#[ test ]
+	fn reports_missing_object () {
+
+		let (port, _receiver) =
+			start_stand_in_http_server (
+				"404 No objects found",
+				JSON_HEADERS,
+				"{}");
+
+		let url =
+			format! (
+				"http://127.0.0.1:{}",
+				port);
+
+		let icinga_submission =
+			IcingaSubmission::from_options_matches (
+				& options_matches (& [
+					"--submit-icinga", & url,
+					"--icinga-host", "web.example.com",
+				]),
+			).unwrap ().unwrap ();
+
+		let error =
+			icinga_submission.submit (
+				& check_result (),
+			).unwrap_err ();
+
+		assert_eq! (
+			error.description (),
+			"icinga returned 404 No objects found");
+
+	}
+
+	#[ test ]
+	fn rejects_url_without_host () {
+
+		let error =
+			match IcingaSubmission::from_options_matches (
+				& options_matches (& [
+					"--submit-icinga", "https:///v1",
+					"--icinga-host", "web.example.com",
+				]),
+			) {
+				Ok (_) => panic! ("url without host accepted"),
+				Err (error) => error,
+			};
+
+		assert_eq! (
+			error.description (),
+			"invalid url for --submit-icinga: https:///v1");
+
+	}
+
+	#[ test ]
+	fn builds_tls_options () {
+
+		let icinga_submission =
+			IcingaSubmission::from_options_matches (
+				& options_matches (& [
+					"--submit-icinga", "https://icinga.example.com:5665",
+					"--icinga-host", "web.example.com",
+					"--icinga-ca-file", "/var/lib/icinga2/certs/ca.crt",
+					"--icinga-insecure",
+				]),
+			).unwrap ().unwrap ();
+
+		assert_eq! (
+			icinga_submission.tls_options.ca_file,
+			Some ("/var/lib/icinga2/certs/ca.crt".to_string ()));
+
+		assert! (
+			icinga_submission.tls_options.insecure);
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -4,13 +4,26 @@ pub mod check_macros;
 pub mod arg_helper;
 pub mod check_helper;
 pub mod check_result;
+pub mod daemon;
+pub mod environment;
+pub mod icinga;
+pub mod multi_call;
+pub mod performance_data;
 pub mod simple_error;
+pub mod state;
 pub mod plugin_provider;
 pub mod runner;
 
+#[ cfg (test) ]
+pub mod testing;
+
 pub use self::check_macros::*;
 pub use self::check_result::*;
 pub use self::check_result::*;
+pub use self::daemon::*;
+pub use self::environment::*;
+pub use self::multi_call::*;
+pub use self::performance_data::*;
 pub use self::simple_error::*;
 pub use self::plugin_provider::*;
 pub use self::runner::*;
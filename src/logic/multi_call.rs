@@ -0,0 +1,221 @@
+use std::env;
+use std::path::Path;
+use std::process;
+
+use logic::daemon::*;
+use logic::plugin_provider::*;
+use logic::runner::*;
+
+const MULTI_CALL_NAME: & str = "wbs-monitoring";
+
+pub fn find_plugin_provider <'a> (
+	plugin_providers: & 'a [Box <PluginProvider>],
+	name: & str,
+) -> Option <& 'a PluginProvider> {
+
+	plugin_providers.iter ().find (
+		|plugin_provider|
+
+		plugin_provider.name () == name
+		|| plugin_provider.name () == format! ("check-{}", name)
+
+	).map (
+		|plugin_provider| & ** plugin_provider
+	)
+
+}
+
+fn show_multi_call_usage (
+	plugin_providers: & [Box <PluginProvider>],
+) {
+
+	println! (
+		"Usage: {} CHECK [options]",
+		MULTI_CALL_NAME);
+
+	println! (
+		"       {} list",
+		MULTI_CALL_NAME);
+
+	println! (
+		"       {} daemon --config FILE [--listen ADDRESS]",
+		MULTI_CALL_NAME);
+
+	println! (
+		"");
+
+	println! (
+		"Alternatively, create a symlink to this binary named after the \
+		check.");
+
+	println! (
+		"");
+
+	show_plugin_providers (
+		plugin_providers);
+
+}
+
+fn show_plugin_providers (
+	plugin_providers: & [Box <PluginProvider>],
+) {
+
+	println! (
+		"Available checks:");
+
+	println! (
+		"");
+
+	for plugin_provider in plugin_providers {
+
+		println! (
+			"  {:24} {}",
+			plugin_provider.name (),
+			plugin_provider.prefix ());
+
+	}
+
+	println! (
+		"");
+
+}
+
+pub fn run_multi_call (
+	plugin_providers_fn: PluginProvidersFn,
+) {
+
+	let plugin_providers =
+		plugin_providers_fn ();
+
+	let plugin_providers =
+		& plugin_providers [..];
+
+	let environment_arguments: Vec <String> =
+		env::args ().into_iter ().collect ();
+
+	// dispatch on the name we were invoked as
+
+	let invoked_name =
+		environment_arguments.first ().and_then (
+			|argument|
+			Path::new (argument).file_name ()
+		).and_then (
+			|file_name|
+			file_name.to_str ()
+		).unwrap_or (
+			MULTI_CALL_NAME,
+		).to_string ();
+
+	if invoked_name != MULTI_CALL_NAME {
+
+		if let Some (plugin_provider) =
+			find_plugin_provider (
+				plugin_providers,
+				& invoked_name) {
+
+			run_from_arguments (
+				plugin_provider,
+				environment_arguments);
+
+			return;
+
+		}
+
+	}
+
+	// otherwise dispatch on a subcommand
+
+	let subcommand =
+		match environment_arguments.get (1) {
+
+		Some (subcommand) =>
+			subcommand.to_string (),
+
+		None => {
+
+			show_multi_call_usage (
+				plugin_providers);
+
+			process::exit (1);
+
+		},
+
+	};
+
+	if subcommand == "list" {
+
+		show_plugin_providers (
+			plugin_providers);
+
+		process::exit (0);
+
+	}
+
+	if subcommand == "daemon" {
+
+		if let Err (error) =
+			run_daemon_from_command_line (
+				plugin_providers_fn,
+				environment_arguments.into_iter ().skip (1).collect ()) {
+
+			println! (
+				"");
+
+			println! (
+				"Daemon failed: {}",
+				error.description ());
+
+			println! (
+				"");
+
+			process::exit (1);
+
+		}
+
+		process::exit (0);
+
+	}
+
+	if subcommand == "--help" || subcommand == "help" {
+
+		show_multi_call_usage (
+			plugin_providers);
+
+		process::exit (0);
+
+	}
+
+	match find_plugin_provider (
+		plugin_providers,
+		& subcommand,
+	) {
+
+		Some (plugin_provider) =>
+			run_from_arguments (
+				plugin_provider,
+				environment_arguments.into_iter ().skip (1).collect ()),
+
+		None => {
+
+			println! (
+				"");
+
+			println! (
+				"Unknown check: {}",
+				subcommand);
+
+			println! (
+				"");
+
+			show_plugin_providers (
+				plugin_providers);
+
+			process::exit (1);
+
+		},
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
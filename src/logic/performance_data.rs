@@ -0,0 +1,457 @@
+use serde;
+
+use std::fmt;
+
+use logic::check_helper::*;
+
+#[ derive (Clone, Copy, Debug, PartialEq) ]
+pub enum PerformanceDataUnit {
+	None,
+	Seconds,
+	Milliseconds,
+	Microseconds,
+	Percent,
+	Bytes,
+	Kilobytes,
+	Megabytes,
+	Terabytes,
+	Counter,
+}
+
+impl PerformanceDataUnit {
+
+	pub fn as_str (
+		& self,
+	) -> & str {
+
+		match * self {
+			PerformanceDataUnit::None => "",
+			PerformanceDataUnit::Seconds => "s",
+			PerformanceDataUnit::Milliseconds => "ms",
+			PerformanceDataUnit::Microseconds => "us",
+			PerformanceDataUnit::Percent => "%",
+			PerformanceDataUnit::Bytes => "B",
+			PerformanceDataUnit::Kilobytes => "KB",
+			PerformanceDataUnit::Megabytes => "MB",
+			PerformanceDataUnit::Terabytes => "TB",
+			PerformanceDataUnit::Counter => "c",
+		}
+
+	}
+
+}
+
+impl serde::Serialize for PerformanceDataUnit {
+
+	fn serialize <Serializer: serde::Serializer> (
+		& self,
+		serializer: Serializer,
+	) -> Result <Serializer::Ok, Serializer::Error> {
+
+		serializer.serialize_str (
+			self.as_str ())
+
+	}
+
+}
+
+#[ derive (Clone, Debug, Serialize) ]
+pub struct PerformanceData {
+	label: String,
+	value: f64,
+	unit: PerformanceDataUnit,
+	warning: Option <CheckRange>,
+	critical: Option <CheckRange>,
+	minimum: Option <f64>,
+	maximum: Option <f64>,
+}
+
+impl PerformanceData {
+
+	// constructor
+
+	pub fn new <IntoString: Into <String>> (
+		label: IntoString,
+		value: f64,
+		unit: PerformanceDataUnit,
+	) -> PerformanceData {
+
+		PerformanceData {
+			label: label.into (),
+			value: value,
+			unit: unit,
+			warning: None,
+			critical: None,
+			minimum: None,
+			maximum: None,
+		}
+
+	}
+
+	// thresholds
+
+	pub fn warning_above <IntoOption: Into <Option <f64>>> (
+		mut self,
+		limit: IntoOption,
+	) -> PerformanceData {
+
+		self.warning =
+			limit.into ().map (
+				CheckRange::alert_above);
+
+		self
+
+	}
+
+	pub fn warning_below <IntoOption: Into <Option <f64>>> (
+		mut self,
+		limit: IntoOption,
+	) -> PerformanceData {
+
+		self.warning =
+			limit.into ().map (
+				CheckRange::alert_below);
+
+		self
+
+	}
+
+	pub fn critical_above <IntoOption: Into <Option <f64>>> (
+		mut self,
+		limit: IntoOption,
+	) -> PerformanceData {
+
+		self.critical =
+			limit.into ().map (
+				CheckRange::alert_above);
+
+		self
+
+	}
+
+	pub fn critical_below <IntoOption: Into <Option <f64>>> (
+		mut self,
+		limit: IntoOption,
+	) -> PerformanceData {
+
+		self.critical =
+			limit.into ().map (
+				CheckRange::alert_below);
+
+		self
+
+	}
+
+	pub fn warning_range (
+		mut self,
+		range: Option <CheckRange>,
+	) -> PerformanceData {
+
+		self.warning =
+			range;
+
+		self
+
+	}
+
+	pub fn critical_range (
+		mut self,
+		range: Option <CheckRange>,
+	) -> PerformanceData {
+
+		self.critical =
+			range;
+
+		self
+
+	}
+
+	// boundaries
+
+	pub fn minimum <IntoOption: Into <Option <f64>>> (
+		mut self,
+		minimum: IntoOption,
+	) -> PerformanceData {
+
+		self.minimum =
+			minimum.into ();
+
+		self
+
+	}
+
+	pub fn maximum <IntoOption: Into <Option <f64>>> (
+		mut self,
+		maximum: IntoOption,
+	) -> PerformanceData {
+
+		self.maximum =
+			maximum.into ();
+
+		self
+
+	}
+
+	// accessors
+
+	pub fn label (
+		& self,
+	) -> & str {
+		self.label.as_str ()
+	}
+
+	pub fn value (
+		& self,
+	) -> f64 {
+		self.value
+	}
+
+	pub fn unit (
+		& self,
+	) -> PerformanceDataUnit {
+		self.unit
+	}
+
+	pub fn warning (
+		& self,
+	) -> Option <& CheckRange> {
+		self.warning.as_ref ()
+	}
+
+	pub fn critical (
+		& self,
+	) -> Option <& CheckRange> {
+		self.critical.as_ref ()
+	}
+
+	pub fn minimum_value (
+		& self,
+	) -> Option <f64> {
+		self.minimum
+	}
+
+	pub fn maximum_value (
+		& self,
+	) -> Option <f64> {
+		self.maximum
+	}
+
+}
+
+impl fmt::Display for PerformanceData {
+
+	fn fmt (
+		& self,
+		formatter: & mut fmt::Formatter,
+	) -> Result <(), fmt::Error> {
+
+		let rendered =
+			format! (
+				"{}={}{};{};{};{};{}",
+				quote_label (
+					& self.label),
+				format_number (
+					self.value),
+				self.unit.as_str (),
+				self.warning.map (
+					|warning| warning.to_string (),
+				).unwrap_or_default (),
+				self.critical.map (
+					|critical| critical.to_string (),
+				).unwrap_or_default (),
+				self.minimum.map (
+					format_number,
+				).unwrap_or_default (),
+				self.maximum.map (
+					format_number,
+				).unwrap_or_default ());
+
+		// trailing empty fields may be omitted
+
+		formatter.write_str (
+			rendered.trim_right_matches (';'),
+		) ?;
+
+		Ok (())
+
+	}
+
+}
+
+pub fn render_performance_data (
+	performance_data: & [PerformanceData],
+	multi_line: bool,
+) -> (Option <String>, Vec <String>) {
+
+	if performance_data.is_empty () {
+
+		(None, vec! [])
+
+	} else if ! multi_line {
+
+		(
+			Some (
+				performance_data.iter ().map (
+					|performance_data|
+					performance_data.to_string ()
+				).collect::<Vec <String>> ().join (" ")),
+			vec! [],
+		)
+
+	} else {
+
+		(
+			Some (
+				performance_data [0].to_string ()),
+			performance_data [1 ..].iter ().map (
+				|performance_data|
+				performance_data.to_string ()
+			).collect (),
+		)
+
+	}
+
+}
+
+fn quote_label (
+	label: & str,
+) -> String {
+
+	// equals and pipe characters are not allowed at all
+
+	let label =
+		label.replace (
+			|character: char| character == '=' || character == '|',
+			"_");
+
+	if label.chars ().any (
+		|character|
+		character.is_whitespace () || character == '\'',
+	) {
+
+		format! (
+			"'{}'",
+			label.replace ("'", "''"))
+
+	} else {
+
+		label
+
+	}
+
+}
+
+fn format_number (
+	value: f64,
+) -> String {
+
+	if value.is_finite () {
+
+		format! (
+			"{}",
+			value)
+
+	} else {
+
+		"U".to_string ()
+
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::f64;
+
+	use super::*;
+
+	#[ test ]
+	fn labels_are_quoted () {
+
+		assert_eq! (
+			vec! [
+				quote_label ("load"),
+				quote_label ("disk space"),
+				quote_label ("bob's disk"),
+				quote_label ("a=b|c"),
+			],
+			vec! [
+				"load",
+				"'disk space'",
+				"'bob''s disk'",
+				"a_b_c",
+			]);
+
+	}
+
+	#[ test ]
+	fn non_finite_values_are_unknown () {
+
+		assert_eq! (
+			PerformanceData::new (
+				"rate",
+				f64::NAN,
+				PerformanceDataUnit::None,
+			).maximum (
+				f64::INFINITY,
+			).to_string (),
+			"rate=U;;;;U");
+
+	}
+
+	#[ test ]
+	fn thresholds_and_boundaries () {
+
+		assert_eq! (
+			PerformanceData::new (
+				"free space",
+				1.5,
+				PerformanceDataUnit::Bytes,
+			).warning_above (
+				10.0,
+			).critical_below (
+				2.0,
+			).minimum (
+				0.0,
+			).to_string (),
+			"'free space'=1.5B;~:10;2:;0");
+
+	}
+
+	#[ test ]
+	fn rendering () {
+
+		let performance_data =
+			vec! [
+				PerformanceData::new ("one", 1.0, PerformanceDataUnit::None),
+				PerformanceData::new ("two", 2.0, PerformanceDataUnit::Seconds),
+				PerformanceData::new ("three", 3.0, PerformanceDataUnit::Counter),
+			];
+
+		assert_eq! (
+			render_performance_data (
+				& [],
+				true),
+			(None, vec! []));
+
+		assert_eq! (
+			render_performance_data (
+				& performance_data,
+				false),
+			(Some ("one=1 two=2s three=3c".to_string ()), vec! []));
+
+		assert_eq! (
+			render_performance_data (
+				& performance_data,
+				true),
+			(
+				Some ("one=1".to_string ()),
+				vec! [ "two=2s".to_string (), "three=3c".to_string () ],
+			));
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
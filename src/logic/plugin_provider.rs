@@ -3,6 +3,7 @@ use getopts;
 use std::error;
 
 use logic::check_result::*;
+use logic::environment::*;
 
 pub trait PluginProvider {
 
@@ -31,6 +32,7 @@ pub trait PluginInstance {
 	fn perform_check (
 		& self,
 		plugin_provider: & PluginProvider,
+		environment: & Environment,
 	) -> Result <CheckResult, Box <error::Error>>;
 
 }
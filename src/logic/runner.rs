@@ -1,10 +1,52 @@
 use getopts;
+use serde_json;
 
+use std::cmp;
 use std::env;
+use std::error;
+use std::fs::File;
+use std::io::Read;
 use std::process;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time;
 
+use logic::arg_helper;
+use logic::check_helper;
 use logic::check_result::*;
+use logic::environment::*;
+use logic::icinga::*;
+use logic::performance_data::*;
 use logic::plugin_provider::*;
+use logic::simple_error::*;
+use logic::state::*;
+
+#[ derive (Clone, Copy, Debug, PartialEq) ]
+pub enum OutputFormat {
+	Nagios,
+	Json,
+}
+
+impl arg_helper::EnumArg for OutputFormat {
+
+	fn from_string (
+		string_value: & str,
+	) -> Option <OutputFormat> {
+
+		match string_value {
+
+			"nagios" => Some (OutputFormat::Nagios),
+			"json" => Some (OutputFormat::Json),
+
+			_ => None,
+
+		}
+
+	}
+
+}
 
 fn show_help (
 	plugin_provider: & PluginProvider,
@@ -23,57 +65,84 @@ fn show_help (
 
 }
 
-fn show_usage_error (
-	plugin_provider: & PluginProvider,
-	_options_spec: & getopts::Options,
+fn usage_error_reason (
 	error: & getopts::Fail,
-) {
-
-	println! (
-		"");
+) -> String {
 
 	match error {
 
 		& getopts::Fail::OptionMissing (
 			ref argument_name,
-		) => {
-
-			println! (
+		) =>
+			format! (
 				"Missing required argument: --{}",
-				argument_name.as_str ());
-
-		},
+				argument_name),
 
 		& _ =>
-
-			println! (
-				"{:?}",
-				error),
+			error.to_string (),
 
 	}
 
-	println! (
-		"");
+}
 
-	println! (
-		"For detailed usage information, run:");
+// option errors are reported as a result like any other, so that scripts
+// asking for json still get json
 
-	println! (
-		"  {} --help",
-		plugin_provider.name ());
+fn options_error_result (
+	plugin_provider: & PluginProvider,
+	message: & str,
+	reason: & str,
+) -> CheckResult {
 
-	println! (
-		"");
+	CheckResult::new (
+		CheckStatus::Unknown,
+		plugin_provider.prefix ().to_string (),
+		vec! [
+			CheckStatusMessage::new (
+				CheckStatus::Unknown,
+				message),
+			CheckStatusMessage::new (
+				CheckStatus::Unknown,
+				reason),
+		],
+		vec! [],
+		vec! [
+			format! (
+				"For detailed usage information, run: {} --help",
+				plugin_provider.name ()),
+		],
+	)
+
+}
+
+fn exit_with_options_error (
+	plugin_provider: & PluginProvider,
+	output_format: OutputFormat,
+	message: & str,
+	reason: & str,
+) -> ! {
+
+	display_result (
+		output_format,
+		& options_error_result (
+			plugin_provider,
+			message,
+			reason));
+
+	process::exit (
+		CheckStatus::Unknown as i32);
 
 }
 
 pub fn run_generically (
 	plugin_provider: & PluginProvider,
 	plugin_instance: & Box <PluginInstance>,
+	environment: & Environment,
 ) -> CheckResult {
 
 	match plugin_instance.perform_check (
 		plugin_provider,
+		environment,
 	) {
 
 		Ok (check_result) =>
@@ -84,9 +153,12 @@ pub fn run_generically (
 				CheckStatus::Unknown,
 				plugin_provider.prefix ().to_string (),
 				vec! [
-					"check did not run correctly due to program \
-						error".to_string (),
-					error.description ().to_string (),
+					CheckStatusMessage::new (
+						CheckStatus::Unknown,
+						"check did not run correctly due to program error"),
+					CheckStatusMessage::new (
+						CheckStatus::Unknown,
+						error.description ()),
 				],
 				vec! [],
 				vec! [],
@@ -100,6 +172,7 @@ pub fn run_from_options_matches (
 	plugin_provider: & PluginProvider,
 	options_spec: & getopts::Options,
 	options_matches: & getopts::Matches,
+	environment: & Environment,
 ) -> CheckResult {
 
 	let plugin_instance =
@@ -113,16 +186,21 @@ pub fn run_from_options_matches (
 		Ok (plugin_instance) =>
 			run_generically (
 				plugin_provider,
-				& plugin_instance),
+				& plugin_instance,
+				environment),
 
 		Err (error) =>
 			CheckResult::new (
 				CheckStatus::Unknown,
 				plugin_provider.prefix ().to_string (),
 				vec! [
-					"unable to process command line arguments due to program \
-						error".to_string (),
-					error.description ().to_string (),
+					CheckStatusMessage::new (
+						CheckStatus::Unknown,
+						"unable to process command line arguments due to \
+						program error"),
+					CheckStatusMessage::new (
+						CheckStatus::Unknown,
+						error.description ()),
 				],
 				vec! [],
 				vec! [],
@@ -136,12 +214,20 @@ pub fn run_from_command_line (
 	plugin_provider: & PluginProvider,
 ) {
 
+	run_from_arguments (
+		plugin_provider,
+		env::args ().into_iter ().collect ());
+
+}
+
+pub fn run_from_arguments (
+	plugin_provider: & PluginProvider,
+	environment_arguments: Vec <String>,
+) {
+
 	let options_spec =
 		plugin_provider.build_options_spec ();
 
-	let environment_arguments: Vec <String> =
-		env::args ().into_iter ().collect ();
-
 	// handle --help specially
 
 	if environment_arguments.contains (
@@ -156,6 +242,44 @@ pub fn run_from_command_line (
 
 	}
 
+	// merge in options from configuration files and indirections
+
+	let mut environment_arguments =
+		environment_arguments;
+
+	let program_arguments =
+		environment_arguments.split_off (
+			cmp::min (1, environment_arguments.len ()));
+
+	let expanded_arguments =
+		match expand_arguments (
+			plugin_provider,
+			& program_arguments,
+		) {
+
+		Ok (expanded_arguments) =>
+			expanded_arguments,
+
+		Err (error) =>
+			exit_with_options_error (
+				plugin_provider,
+				command_line_output_format (
+					& program_arguments),
+				"unable to load options",
+				error.description ()),
+
+	};
+
+	// until the options are parsed, the output format comes from a simple
+	// scan of the arguments, which includes any from config files
+
+	let scanned_output_format =
+		command_line_output_format (
+			& expanded_arguments);
+
+	environment_arguments.extend (
+		expanded_arguments);
+
 	// parse options
 
 	let options_matches =
@@ -166,42 +290,165 @@ pub fn run_from_command_line (
 		Ok (options_matches) =>
 			options_matches,
 
-		Err (error) => {
+		Err (error) =>
+			exit_with_options_error (
+				plugin_provider,
+				scanned_output_format,
+				"invalid command line arguments",
+				& usage_error_reason (
+					& error)),
+
+	};
+
+	let output_format =
+		match arg_helper::parse_enum (
+			& options_matches,
+			"output-format",
+		) {
+
+		Ok (output_format) =>
+			output_format.unwrap_or (
+				OutputFormat::Nagios),
 
-			show_usage_error (
+		Err (error) =>
+			exit_with_options_error (
 				plugin_provider,
-				& options_spec,
-				& error);
+				scanned_output_format,
+				"invalid command line arguments",
+				error.description ()),
 
-			process::exit (1);
+	};
 
-		},
+	let check_timeout =
+		match arg_helper::parse_duration (
+			& options_matches,
+			"check-timeout",
+		) {
+
+		Ok (check_timeout) =>
+			check_timeout,
+
+		Err (error) =>
+			exit_with_options_error (
+				plugin_provider,
+				output_format,
+				"invalid command line arguments",
+				error.description ()),
+
+	};
+
+	let icinga_submission =
+		match IcingaSubmission::from_options_matches (
+			& options_matches,
+		) {
+
+		Ok (icinga_submission) =>
+			icinga_submission,
+
+		Err (error) =>
+			exit_with_options_error (
+				plugin_provider,
+				output_format,
+				"invalid command line arguments",
+				error.description ()),
+
+	};
+
+	// delegate, under a watchdog if there is a timeout
+
+	let state_directory =
+		match parse_state_directory (
+			& options_matches,
+		) {
+
+		Ok (state_directory) =>
+			state_directory,
+
+		Err (error) =>
+			exit_with_options_error (
+				plugin_provider,
+				output_format,
+				"invalid command line arguments",
+				error.description ()),
 
 	};
 
-	// delegate
+	let mut environment =
+		SystemEnvironment::new ();
+
+	environment.set_state_directory (
+		state_directory);
+
+	let reported =
+		Arc::new (AtomicBool::new (false));
+
+	if let Some (check_timeout) = check_timeout {
+
+		start_watchdog (
+			plugin_provider.prefix (),
+			output_format,
+			icinga_submission.clone (),
+			check_timeout,
+			environment.check_phase (),
+			reported.clone ());
+
+	}
 
 	let check_result =
 		run_from_options_matches (
 			plugin_provider,
 			& options_spec,
 			& options_matches,
+			& environment,
 		);
 
+	// if the watchdog got there first, it is reporting and will exit for us
+
+	if ! claim_report (& reported) {
+
+		loop {
+			thread::park ();
+		}
+
+	}
+
+	report_result (
+		output_format,
+		icinga_submission.as_ref (),
+		& check_result);
+
+}
+
+fn report_result (
+	output_format: OutputFormat,
+	icinga_submission: Option <& IcingaSubmission>,
+	check_result: & CheckResult,
+) -> ! {
+
 	// display result
 
-	println! (
-		"{} {}: {}",
-		plugin_provider.prefix (),
-		check_result.status ().prefix (),
-		check_result.status_message (),
-	);
+	display_result (
+		output_format,
+		check_result);
 
-	for extra_line in check_result.extra_information () {
+	// passive submission reports the status to icinga, so we only fail if
+	// that doesn't work
 
-		println! (
-			"{}",
-			extra_line);
+	if let Some (icinga_submission) = icinga_submission {
+
+		if let Err (error) =
+			icinga_submission.submit (
+				check_result) {
+
+			println! (
+				"Submission to icinga failed: {}",
+				error.description ());
+
+			process::exit (1);
+
+		}
+
+		process::exit (0);
 
 	}
 
@@ -210,4 +457,1006 @@ pub fn run_from_command_line (
 
 }
 
+// ---------- watchdog
+
+// the check can't be interrupted safely, so if it is still running when the
+// timeout passes, we report on its behalf and exit the whole process. only
+// one of the watchdog and the main thread may report, which ever claims it
+// first, so that a check finishing near the deadline doesn't print twice or
+// get cut off half way through an icinga submission. the timeout is submitted
+// like any other result, within its own --icinga-timeout.
+
+fn start_watchdog (
+	prefix: & str,
+	output_format: OutputFormat,
+	icinga_submission: Option <IcingaSubmission>,
+	check_timeout: time::Duration,
+	check_phase: CheckPhase,
+	reported: Arc <AtomicBool>,
+) {
+
+	let prefix =
+		prefix.to_string ();
+
+	thread::spawn (
+		move || {
+
+		thread::sleep (
+			check_timeout);
+
+		if ! claim_report (& reported) {
+			return;
+		}
+
+		report_result (
+			output_format,
+			icinga_submission.as_ref (),
+			& timeout_result (
+				prefix,
+				check_timeout,
+				& check_phase));
+
+	});
+
+}
+
+fn claim_report (
+	reported: & AtomicBool,
+) -> bool {
+
+	! reported.swap (
+		true,
+		Ordering::SeqCst)
+
+}
+
+fn timeout_result (
+	prefix: String,
+	check_timeout: time::Duration,
+	check_phase: & CheckPhase,
+) -> CheckResult {
+
+	CheckResult::new (
+		CheckStatus::Unknown,
+		prefix,
+		vec! [
+			CheckStatusMessage::new (
+				CheckStatus::Unknown,
+				format! (
+					"check timed out after {} while {}",
+					check_helper::display_duration_long (
+						& check_timeout),
+					check_phase.get ())),
+		],
+		vec! [],
+		vec! [],
+	)
+
+}
+
+// ---------- option sources
+
+// options on the command line take precedence over indirections, which take
+// precedence over the check's section in any --config files. values on the
+// command line of the form @PATH are read from that file, and @@ gives a
+// literal @.
+
+pub fn expand_arguments (
+	plugin_provider: & PluginProvider,
+	arguments: & [String],
+) -> Result <Vec <String>, Box <error::Error>> {
+
+	let command_line_names: Vec <String> =
+		option_names (
+			arguments);
+
+	let command_line_arguments: Vec <String> =
+		read_value_files (
+			arguments,
+		) ?;
+
+	let mut indirect_arguments: Vec <String> =
+		Vec::new ();
+
+	for option_from_env in option_values (
+		arguments,
+		"option-from-env",
+	) {
+
+		let (option_name, variable_name) =
+			split_indirection (
+				& option_from_env,
+				"option-from-env",
+			) ?;
+
+		let value =
+			env::var (
+				& variable_name,
+			).map_err (
+				|_|
+				format! (
+					"environment variable not set for --{}: {}",
+					option_name,
+					variable_name),
+			) ?;
+
+		indirect_arguments.push (
+			format! (
+				"--{}={}",
+				option_name,
+				value));
+
+	}
+
+	let indirect_names: Vec <String> =
+		option_names (
+			& indirect_arguments);
+
+	let mut config_arguments: Vec <String> =
+		Vec::new ();
+
+	for config_path in option_values (
+		arguments,
+		"config",
+	) {
+
+		for (option_name, option_value) in load_config_options (
+			plugin_provider,
+			& config_path,
+		) ? {
+
+			if command_line_names.contains (& option_name)
+				|| indirect_names.contains (& option_name) {
+
+				continue;
+
+			}
+
+			config_arguments.push (
+				match option_value {
+					Some (option_value) =>
+						format! ("--{}={}", option_name, option_value),
+					None =>
+						format! ("--{}", option_name),
+				});
+
+		}
+
+	}
+
+	let mut expanded_arguments =
+		config_arguments;
+
+	expanded_arguments.extend (
+		indirect_arguments.into_iter ().filter (
+			|argument|
+			! command_line_names.contains (
+				& option_name (argument).unwrap ())));
+
+	// a short flag on the command line can still clash with a config or
+	// indirect option, which only getopts can tell us, so we drop those
+	// until it stops complaining
+
+	let options_spec =
+		plugin_provider.build_options_spec ();
+
+	loop {
+
+		let mut candidate_arguments =
+			expanded_arguments.clone ();
+
+		candidate_arguments.extend (
+			command_line_arguments.iter ().cloned ());
+
+		let duplicated_name =
+			match options_spec.parse (
+				& candidate_arguments,
+			) {
+
+			Err (getopts::Fail::OptionDuplicated (name)) =>
+				name,
+
+			_ =>
+				return Ok (candidate_arguments),
+
+		};
+
+		let num_before =
+			expanded_arguments.len ();
+
+		expanded_arguments.retain (
+			|argument|
+			option_name (argument).as_ref () != Some (& duplicated_name));
+
+		if expanded_arguments.len () == num_before {
+			return Ok (candidate_arguments);
+		}
+
+	}
+
+}
+
+fn load_config_options (
+	plugin_provider: & PluginProvider,
+	config_path: & str,
+) -> Result <Vec <(String, Option <String>)>, Box <error::Error>> {
+
+	let mut config_string =
+		String::new ();
+
+	File::open (
+		config_path,
+	).and_then (
+		|mut file|
+		file.read_to_string (
+			& mut config_string)
+	).map_err (
+		|error|
+		format! (
+			"unable to read {}: {}",
+			config_path,
+			error),
+	) ?;
+
+	let config: serde_json::Value =
+		serde_json::from_str (
+			& config_string,
+		).map_err (
+			|error|
+			format! (
+				"unable to parse {}: {}",
+				config_path,
+				error),
+		) ?;
+
+	let short_name =
+		plugin_provider.name ().trim_left_matches ("check-");
+
+	let section =
+		match config.get (
+			plugin_provider.name (),
+		).or_else (
+			|| config.get (short_name)
+		) {
+
+		Some (& serde_json::Value::Object (ref section)) =>
+			section,
+
+		Some (_) =>
+			return Err (Box::new (
+				SimpleError::from (
+					format! (
+						"section {} in {} must be an object",
+						plugin_provider.name (),
+						config_path)))),
+
+		None =>
+			return Ok (vec! []),
+
+	};
+
+	let mut config_options: Vec <(String, Option <String>)> =
+		Vec::new ();
+
+	for (option_name, option_value) in section.iter () {
+
+		let values: Vec <& serde_json::Value> =
+			match * option_value {
+				serde_json::Value::Array (ref values) => values.iter ().collect (),
+				ref value => vec! [ value ],
+			};
+
+		for value in values {
+
+			match * value {
+
+				serde_json::Value::Null |
+				serde_json::Value::Bool (false) =>
+					(),
+
+				serde_json::Value::Bool (true) =>
+					config_options.push (
+						(option_name.to_string (), None)),
+
+				serde_json::Value::String (ref value) =>
+					config_options.push (
+						(option_name.to_string (), Some (value.to_string ()))),
+
+				serde_json::Value::Number (ref value) =>
+					config_options.push (
+						(option_name.to_string (), Some (value.to_string ()))),
+
+				_ =>
+					return Err (Box::new (
+						SimpleError::from (
+							format! (
+								"invalid value for {} in {}",
+								option_name,
+								config_path)))),
+
+			}
+
+		}
+
+	}
+
+	Ok (config_options)
+
+}
+
+fn read_value_files (
+	arguments: & [String],
+) -> Result <Vec <String>, Box <error::Error>> {
+
+	let mut resolved_arguments: Vec <String> =
+		Vec::new ();
+
+	let mut iterator =
+		arguments.iter ();
+
+	while let Some (argument) =
+		iterator.next () {
+
+		if argument == "--" {
+
+			resolved_arguments.push (
+				argument.to_string ());
+
+			resolved_arguments.extend (
+				iterator.cloned ());
+
+			break;
+
+		}
+
+		if argument.starts_with ("--") {
+
+			if let Some (equals_position) =
+				argument.find ('=') {
+
+				resolved_arguments.push (
+					format! (
+						"{}={}",
+						& argument [0 .. equals_position],
+						read_value_file (
+							& argument [equals_position + 1 ..],
+						) ?));
+
+				continue;
+
+			}
+
+		} else if argument.starts_with ("@") {
+
+			resolved_arguments.push (
+				read_value_file (
+					argument,
+				) ?);
+
+			continue;
+
+		}
+
+		resolved_arguments.push (
+			argument.to_string ());
+
+	}
+
+	Ok (resolved_arguments)
+
+}
+
+fn read_value_file (
+	value: & str,
+) -> Result <String, Box <error::Error>> {
+
+	if ! value.starts_with ("@") {
+		return Ok (value.to_string ());
+	}
+
+	if value.starts_with ("@@") {
+		return Ok (value [1 ..].to_string ());
+	}
+
+	let file_name =
+		& value [1 ..];
+
+	let mut file_value =
+		String::new ();
+
+	File::open (
+		file_name,
+	).and_then (
+		|mut file|
+		file.read_to_string (
+			& mut file_value)
+	).map_err (
+		|error|
+		format! (
+			"unable to read option value from {}: {}",
+			file_name,
+			error),
+	) ?;
+
+	Ok (
+		file_value.trim_right_matches (
+			|character: char| character == '\n' || character == '\r',
+		).to_string ())
+
+}
+
+fn split_indirection (
+	indirection: & str,
+	option_name: & str,
+) -> Result <(String, String), Box <error::Error>> {
+
+	let mut parts =
+		indirection.splitn (2, '=');
+
+	match (parts.next (), parts.next ()) {
+
+		(Some (name), Some (source)) if ! name.is_empty () && ! source.is_empty () =>
+			Ok ((
+				name.trim_left_matches ("--").to_string (),
+				source.to_string (),
+			)),
+
+		_ =>
+			Err (Box::new (
+				SimpleError::from (
+					format! (
+						"--{} must be in NAME=SOURCE format",
+						option_name)))),
+
+	}
+
+}
+
+fn option_name (
+	argument: & str,
+) -> Option <String> {
+
+	if argument.starts_with ("--") && argument.len () > 2 {
+
+		Some (
+			argument [2 ..].splitn (2, '=').next ().unwrap ().to_string ())
+
+	} else {
+
+		None
+
+	}
+
+}
+
+fn option_names (
+	arguments: & [String],
+) -> Vec <String> {
+
+	arguments.iter ().filter_map (
+		|argument|
+		option_name (argument)
+	).collect ()
+
+}
+
+// the options haven't been parsed yet when loading them fails, so we look for
+// the output format on the command line ourselves
+
+fn command_line_output_format (
+	arguments: & [String],
+) -> OutputFormat {
+
+	option_values (
+		arguments,
+		"output-format",
+	).last ().and_then (
+		|output_format|
+		<OutputFormat as arg_helper::EnumArg>::from_string (
+			output_format)
+	).unwrap_or (
+		OutputFormat::Nagios)
+
+}
+
+fn option_values (
+	arguments: & [String],
+	wanted_name: & str,
+) -> Vec <String> {
+
+	let mut values: Vec <String> =
+		Vec::new ();
+
+	let mut iterator =
+		arguments.iter ();
+
+	while let Some (argument) =
+		iterator.next () {
+
+		if argument == "--" {
+			break;
+		}
+
+		if option_name (argument).as_ref ().map (String::as_str) != Some (wanted_name) {
+			continue;
+		}
+
+		if let Some (equals_position) =
+			argument.find ('=') {
+
+			values.push (
+				argument [equals_position + 1 ..].to_string ());
+
+		} else if let Some (value) =
+			iterator.next () {
+
+			values.push (
+				value.to_string ());
+
+		}
+
+	}
+
+	values
+
+}
+
+// ---------- output
+
+fn display_result (
+	output_format: OutputFormat,
+	check_result: & CheckResult,
+) {
+
+	match output_format {
+
+		OutputFormat::Nagios =>
+			display_result_nagios (
+				check_result),
+
+		OutputFormat::Json =>
+			display_result_json (
+				check_result),
+
+	}
+
+}
+
+fn display_result_nagios (
+	check_result: & CheckResult,
+) {
+
+	let (first_performance_data, other_performance_data) =
+		render_performance_data (
+			check_result.performance_data (),
+			! check_result.extra_information ().is_empty ());
+
+	if let Some (first_performance_data) =
+		first_performance_data {
+
+		println! (
+			"{} {}: {} | {}",
+			check_result.prefix (),
+			check_result.status ().prefix (),
+			check_result.status_message (),
+			first_performance_data,
+		);
+
+	} else {
+
+		println! (
+			"{} {}: {}",
+			check_result.prefix (),
+			check_result.status ().prefix (),
+			check_result.status_message (),
+		);
+
+	}
+
+	let num_extra_lines =
+		check_result.extra_information ().len ();
+
+	for (index, extra_line) in check_result.extra_information ().iter ().enumerate () {
+
+		// the remaining performance data follows the last line
+
+		if index + 1 == num_extra_lines
+			&& ! other_performance_data.is_empty () {
+
+			println! (
+				"{} | {}",
+				extra_line,
+				other_performance_data.join ("\n"));
+
+		} else {
+
+			println! (
+				"{}",
+				extra_line);
+
+		}
+
+	}
+
+}
+
+fn display_result_json (
+	check_result: & CheckResult,
+) {
+
+	println! (
+		"{}",
+		serde_json::to_string_pretty (
+			check_result,
+		).unwrap ());
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use getopts;
+
+	use std::env;
+	use std::error;
+	use std::fs;
+	use std::io::Write;
+	use std::path::PathBuf;
+	use std::sync::Arc;
+	use std::sync::atomic::AtomicBool;
+	use std::thread;
+	use std::time;
+
+	use logic::*;
+
+	check! {
+
+		new = new,
+		name = "check-example",
+		prefix = "EXAMPLE",
+
+		provider = CheckExampleProvider,
+
+		instance = CheckExampleInstance {
+			hostname: String,
+		},
+
+		options_spec = |options_spec| {
+
+			options_spec.optopt (
+				"H",
+				"hostname",
+				"host to check",
+				"HOST");
+
+			options_spec.optopt (
+				"",
+				"port",
+				"port to check",
+				"PORT");
+
+			options_spec.optopt (
+				"",
+				"path",
+				"path to check",
+				"PATH");
+
+		},
+
+		options_parse = |options_matches| {
+
+			CheckExampleInstance {
+				hostname: options_matches.opt_str ("hostname").unwrap_or_default (),
+			}
+
+		},
+
+		perform = |self, plugin_provider, _environment, check_result_builder| {
+
+			check_result_builder.ok (
+				self.hostname.to_string ());
+
+		},
+
+	}
+
+	fn write_temp_file (
+		name: & str,
+		contents: & str,
+	) -> PathBuf {
+
+		let path =
+			env::temp_dir ().join (
+				format! (
+					"wbs-monitoring-runner-test-{}-{}",
+					name,
+					time::SystemTime::now ().duration_since (
+						time::UNIX_EPOCH,
+					).unwrap ().subsec_nanos ()));
+
+		fs::File::create (
+			& path,
+		).unwrap ().write_all (
+			contents.as_bytes (),
+		).unwrap ();
+
+		path
+
+	}
+
+	fn expand (
+		arguments: & [& str],
+	) -> Vec <String> {
+
+		expand_arguments (
+			& * new (),
+			& arguments.iter ().map (
+				|argument| argument.to_string ()
+			).collect::<Vec <String>> (),
+		).unwrap ()
+
+	}
+
+	#[ test ]
+	fn option_precedence () {
+
+		let config_path =
+			write_temp_file (
+				"config.json",
+				"{\"example\": {\"hostname\": \"config.example.com\", \
+				\"port\": 80, \"path\": \"/config\"}}");
+
+		let port_path =
+			write_temp_file (
+				"port",
+				"8080\n");
+
+		env::set_var (
+			"WBS_MONITORING_RUNNER_TEST_PATH",
+			"/env");
+
+		let config_argument =
+			format! ("--config={}", config_path.to_str ().unwrap ());
+
+		let port_argument =
+			format! ("@{}", port_path.to_str ().unwrap ());
+
+		// config, then indirections, then the command line, each only where a
+		// later source doesn't give the same option
+
+		assert_eq! (
+			expand (& [
+				& config_argument,
+				"--option-from-env", "path=WBS_MONITORING_RUNNER_TEST_PATH",
+				"--hostname", "cli.example.com",
+				"--port", & port_argument,
+			]),
+			vec! [
+				"--path=/env".to_string (),
+				config_argument.to_string (),
+				"--option-from-env".to_string (),
+				"path=WBS_MONITORING_RUNNER_TEST_PATH".to_string (),
+				"--hostname".to_string (),
+				"cli.example.com".to_string (),
+				"--port".to_string (),
+				"8080".to_string (),
+			]);
+
+		// the command line also wins over indirections
+
+		assert_eq! (
+			expand (& [
+				& config_argument,
+				"--option-from-env", "path=WBS_MONITORING_RUNNER_TEST_PATH",
+				"--path=/cli",
+			]),
+			vec! [
+				"--hostname=config.example.com".to_string (),
+				"--port=80".to_string (),
+				config_argument.to_string (),
+				"--option-from-env".to_string (),
+				"path=WBS_MONITORING_RUNNER_TEST_PATH".to_string (),
+				"--path=/cli".to_string (),
+			]);
+
+		fs::remove_file (& config_path).unwrap ();
+		fs::remove_file (& port_path).unwrap ();
+
+	}
+
+	#[ test ]
+	fn option_values_from_files () {
+
+		let hostname_path =
+			write_temp_file (
+				"hostname",
+				"file.example.com\r\n");
+
+		assert_eq! (
+			expand (& [
+				& format! ("--hostname=@{}", hostname_path.to_str ().unwrap ()),
+				"--path", "@@home",
+			]),
+			vec! [
+				"--hostname=file.example.com".to_string (),
+				"--path".to_string (),
+				"@home".to_string (),
+			]);
+
+		fs::remove_file (& hostname_path).unwrap ();
+
+		assert_eq! (
+			expand_arguments (
+				& * new (),
+				& vec! [
+					"--hostname".to_string (),
+					"@/nonexistent/hostname".to_string (),
+				],
+			).err ().unwrap ().to_string (),
+			"unable to read option value from /nonexistent/hostname: \
+			No such file or directory (os error 2)");
+
+	}
+
+	#[ test ]
+	fn short_flag_overrides_config () {
+
+		let config_path =
+			write_temp_file (
+				"config.json",
+				"{\"check-example\": {\"hostname\": \"config.example.com\"}}");
+
+		let config_argument =
+			format! ("--config={}", config_path.to_str ().unwrap ());
+
+		let expanded_arguments =
+			expand (& [
+				& config_argument,
+				"-H", "cli.example.com",
+			]);
+
+		fs::remove_file (& config_path).unwrap ();
+
+		assert_eq! (
+			expanded_arguments,
+			vec! [
+				config_argument.to_string (),
+				"-H".to_string (),
+				"cli.example.com".to_string (),
+			]);
+
+		let check_result =
+			testing::run_without_fixture (
+				& * new (),
+				& expanded_arguments.iter ().map (
+					String::as_str,
+				).collect::<Vec <& str>> ());
+
+		assert_eq! (
+			check_result.status_message (),
+			"cli.example.com");
+
+	}
+
+	#[ test ]
+	fn only_one_report () {
+
+		let reported =
+			Arc::new (AtomicBool::new (false));
+
+		let claims: Vec <bool> =
+			(0 .. 8).map (
+				|_| {
+
+				let reported =
+					reported.clone ();
+
+				thread::spawn (
+					move || super::claim_report (& reported))
+
+			}).collect::<Vec <_>> ().into_iter ().map (
+				|claim_thread| claim_thread.join ().unwrap ()
+			).collect ();
+
+		assert_eq! (
+			claims.iter ().filter (|& & claimed| claimed).count (),
+			1);
+
+	}
+
+	#[ test ]
+	fn timeout_names_the_phase () {
+
+		let environment =
+			SystemEnvironment::new ();
+
+		environment.enter_phase (
+			"requesting http://example.com/");
+
+		let check_result =
+			super::timeout_result (
+				"HTTP".to_string (),
+				time::Duration::from_secs (10),
+				& environment.check_phase ());
+
+		assert_eq! (
+			(* check_result.status (), check_result.status_message ()),
+			(
+				CheckStatus::Unknown,
+				"check timed out after 10.0 seconds while requesting \
+				http://example.com/",
+			));
+
+	}
+
+	#[ test ]
+	fn options_errors () {
+
+		let options_spec =
+			new ().build_options_spec ();
+
+		let error =
+			options_spec.parse (
+				vec! [ "--hostname" ],
+			).err ().unwrap ();
+
+		let check_result =
+			super::options_error_result (
+				& * new (),
+				"invalid command line arguments",
+				& super::usage_error_reason (
+					& error));
+
+		assert_eq! (
+			(
+				* check_result.status (),
+				check_result.status_messages ().iter ().map (
+					|status_message| status_message.message ()
+				).collect::<Vec <& str>> (),
+			),
+			(
+				CheckStatus::Unknown,
+				vec! [
+					"invalid command line arguments",
+					"Argument to option 'hostname' missing.",
+				],
+			));
+
+		assert_eq! (
+			check_result.extra_information (),
+			& [
+				"For detailed usage information, run: check-example --help"
+					.to_string (),
+			]);
+
+	}
+
+	#[ test ]
+	fn output_format_from_command_line () {
+
+		assert_eq! (
+			vec! [
+				super::command_line_output_format (& []),
+				super::command_line_output_format (& [
+					"--output-format=json".to_string (),
+				]),
+				super::command_line_output_format (& [
+					"--output-format".to_string (),
+					"json".to_string (),
+				]),
+				super::command_line_output_format (& [
+					"--output-format=xml".to_string (),
+				]),
+			],
+			vec! [
+				OutputFormat::Nagios,
+				OutputFormat::Json,
+				OutputFormat::Json,
+				OutputFormat::Nagios,
+			]);
+
+	}
+
+}
+
 // ex: noet ts=4 filetype=rust
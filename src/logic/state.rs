@@ -0,0 +1,660 @@
+extern crate libc;
+
+use getopts;
+use serde_json;
+
+use std::error;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use std::time;
+
+use logic::simple_error::*;
+
+pub const DEFAULT_STATE_DIRECTORY: & str =
+	"/var/lib/wbs-monitoring";
+
+const LOCK_TIMEOUT_MILLIS: u64 = 10_000;
+const LOCK_RETRY_MILLIS: u64 = 100;
+
+// a relative path would depend on wherever the check happens to be started
+// from
+
+pub fn parse_state_directory (
+	options_matches: & getopts::Matches,
+) -> Result <PathBuf, Box <error::Error>> {
+
+	let state_directory =
+		PathBuf::from (
+			options_matches.opt_str (
+				"state-directory",
+			).unwrap_or_else (
+				|| DEFAULT_STATE_DIRECTORY.to_string (),
+			));
+
+	if ! state_directory.is_absolute () {
+
+		return Err (Box::new (
+			SimpleError::from (
+				format! (
+					"Invalid value for state-directory, expected an absolute \
+					path: {}",
+					state_directory.to_string_lossy ()))));
+
+	}
+
+	Ok (state_directory)
+
+}
+
+// ---------- plugin state
+
+// Values kept between runs of a plugin instance, stored as a JSON object in
+// a file under the state directory. The state is locked for as long as this
+// is held, and changes are only written by save ().
+
+pub struct PluginState {
+	path: PathBuf,
+	values: serde_json::Map <String, serde_json::Value>,
+	_lock_file: File,
+}
+
+#[ derive (Clone, Copy, Debug, PartialEq) ]
+pub enum CounterRate {
+	FirstSample,
+	Reset (f64),
+	Rate (f64),
+	NoTimeElapsed,
+}
+
+impl PluginState {
+
+	pub fn open (
+		state_directory: & Path,
+		plugin_name: & str,
+		instance_key: & str,
+	) -> Result <PluginState, Box <error::Error>> {
+
+		let plugin_directory =
+			state_directory.join (
+				encode_file_name (plugin_name));
+
+		fs::create_dir_all (
+			& plugin_directory,
+		).map_err (
+			|error|
+			format! (
+				"unable to create state directory {}: {}",
+				plugin_directory.to_string_lossy (),
+				error),
+		) ?;
+
+		let base_name =
+			encode_file_name (
+				instance_key);
+
+		let lock_file =
+			lock_file (
+				& plugin_directory.join (
+					format! ("{}.lock", base_name)),
+			) ?;
+
+		let path =
+			plugin_directory.join (
+				format! ("{}.json", base_name));
+
+		let values =
+			match File::open (& path) {
+
+			Ok (mut file) => {
+
+				let mut contents =
+					String::new ();
+
+				file.read_to_string (
+					& mut contents,
+				) ?;
+
+				match serde_json::from_str::<serde_json::Value> (
+					& contents,
+				) ? {
+
+					serde_json::Value::Object (values) =>
+						values,
+
+					_ =>
+						return Err (Box::new (
+							SimpleError::from (
+								format! (
+									"invalid state file: {}",
+									path.to_string_lossy ())))),
+
+				}
+
+			},
+
+			Err (ref error) if error.kind () == io::ErrorKind::NotFound =>
+				serde_json::Map::new (),
+
+			Err (error) =>
+				return Err (Box::new (error)),
+
+		};
+
+		Ok (PluginState {
+			path: path,
+			values: values,
+			_lock_file: lock_file,
+		})
+
+	}
+
+	// generic values
+
+	pub fn get (
+		& self,
+		key: & str,
+	) -> Option <& serde_json::Value> {
+		self.values.get (key)
+	}
+
+	pub fn set (
+		& mut self,
+		key: & str,
+		value: serde_json::Value,
+	) {
+
+		self.values.insert (
+			key.to_string (),
+			value);
+
+	}
+
+	pub fn remove (
+		& mut self,
+		key: & str,
+	) {
+
+		self.values.remove (
+			key);
+
+	}
+
+	pub fn get_f64 (
+		& self,
+		key: & str,
+	) -> Option <f64> {
+
+		self.values.get (
+			key,
+		).and_then (
+			|value| value.as_f64 ()
+		)
+
+	}
+
+	pub fn set_f64 (
+		& mut self,
+		key: & str,
+		value: f64,
+	) {
+
+		self.set (
+			key,
+			json! (value));
+
+	}
+
+	// rates between invocations
+
+	pub fn counter_rate (
+		& mut self,
+		key: & str,
+		value: f64,
+		now: time::SystemTime,
+	) -> CounterRate {
+
+		let now_seconds =
+			match now.duration_since (time::UNIX_EPOCH) {
+				Ok (duration) =>
+					duration.as_secs () as f64
+					+ duration.subsec_nanos () as f64 / 1_000_000_000.0,
+				Err (_) =>
+					0.0,
+			};
+
+		let previous =
+			self.values.get (key).and_then (
+				|previous|
+				match (
+					previous.get ("value").and_then (|value| value.as_f64 ()),
+					previous.get ("timestamp").and_then (|value| value.as_f64 ()),
+				) {
+					(Some (value), Some (timestamp)) => Some ((value, timestamp)),
+					_ => None,
+				}
+			);
+
+		self.set (
+			key,
+			json! ({
+				"value": value,
+				"timestamp": now_seconds,
+			}));
+
+		let (previous_value, previous_timestamp) =
+			match previous {
+				Some (previous) => previous,
+				None => return CounterRate::FirstSample,
+			};
+
+		let elapsed =
+			now_seconds - previous_timestamp;
+
+		if elapsed <= 0.0 {
+
+			CounterRate::NoTimeElapsed
+
+		} else if value < previous_value {
+
+			// the counter restarted from zero since the last sample
+
+			CounterRate::Reset (
+				value / elapsed)
+
+		} else {
+
+			CounterRate::Rate (
+				(value - previous_value) / elapsed)
+
+		}
+
+	}
+
+	// persistence
+
+	pub fn save (
+		& self,
+	) -> Result <(), Box <error::Error>> {
+
+		let temp_path =
+			self.path.with_extension (
+				format! (
+					"json.{}.tmp",
+					unsafe { libc::getpid () }));
+
+		{
+
+			let mut temp_file =
+				File::create (
+					& temp_path,
+				) ?;
+
+			temp_file.write_all (
+				serde_json::to_string (
+					& self.values,
+				) ?.as_bytes (),
+			) ?;
+
+			temp_file.sync_all () ?;
+
+		}
+
+		fs::rename (
+			& temp_path,
+			& self.path,
+		) ?;
+
+		Ok (())
+
+	}
+
+}
+
+impl CounterRate {
+
+	pub fn per_second (
+		& self,
+	) -> Option <f64> {
+
+		match * self {
+			CounterRate::Rate (rate) => Some (rate),
+			CounterRate::Reset (rate) => Some (rate),
+			CounterRate::FirstSample => None,
+			CounterRate::NoTimeElapsed => None,
+		}
+
+	}
+
+}
+
+fn lock_file (
+	lock_path: & Path,
+) -> Result <File, Box <error::Error>> {
+
+	let lock_file =
+		OpenOptions::new ()
+			.read (true)
+			.write (true)
+			.create (true)
+			.open (lock_path) ?;
+
+	let start_time =
+		time::Instant::now ();
+
+	loop {
+
+		let lock_result =
+			unsafe {
+				libc::flock (
+					lock_file.as_raw_fd (),
+					libc::LOCK_EX | libc::LOCK_NB)
+			};
+
+		if lock_result == 0 {
+			return Ok (lock_file);
+		}
+
+		if start_time.elapsed ()
+			> time::Duration::from_millis (LOCK_TIMEOUT_MILLIS) {
+
+			return Err (Box::new (
+				SimpleError::from (
+					format! (
+						"state is locked by another run: {}",
+						lock_path.to_string_lossy ()))));
+
+		}
+
+		thread::sleep (
+			time::Duration::from_millis (LOCK_RETRY_MILLIS));
+
+	}
+
+}
+
+fn encode_file_name (
+	name: & str,
+) -> String {
+
+	let mut encoded =
+		String::new ();
+
+	for byte in name.bytes () {
+
+		match byte {
+
+			b'a' ... b'z' | b'A' ... b'Z' | b'0' ... b'9' | b'-' | b'_' =>
+				encoded.push (byte as char),
+
+			_ =>
+				encoded.push_str (
+					& format! ("%{:02x}", byte)),
+
+		}
+
+	}
+
+	encoded
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::env;
+	use std::fs;
+	use std::path::PathBuf;
+	use std::sync::mpsc;
+	use std::thread;
+	use std::time;
+
+	use super::*;
+
+	fn temp_state_directory (
+		name: & str,
+	) -> PathBuf {
+
+		env::temp_dir ().join (
+			format! (
+				"wbs-monitoring-state-test-{}-{}",
+				name,
+				time::SystemTime::now ().duration_since (
+					time::UNIX_EPOCH,
+				).unwrap ().subsec_nanos ()))
+
+	}
+
+	fn at (
+		seconds: u64,
+	) -> time::SystemTime {
+
+		time::UNIX_EPOCH + time::Duration::from_secs (seconds)
+
+	}
+
+	#[ test ]
+	fn counter_rates () {
+
+		let state_directory =
+			temp_state_directory ("rates");
+
+		let mut plugin_state =
+			PluginState::open (
+				& state_directory,
+				"check-test",
+				"instance",
+			).unwrap ();
+
+		assert_eq! (
+			vec! [
+				plugin_state.counter_rate ("requests", 1000.0, at (1000)),
+				plugin_state.counter_rate ("requests", 4000.0, at (1060)),
+				plugin_state.counter_rate ("requests", 4000.0, at (1060)),
+				plugin_state.counter_rate ("requests", 300.0, at (1120)),
+				plugin_state.counter_rate ("other", 5.0, at (1120)),
+			],
+			vec! [
+				CounterRate::FirstSample,
+				CounterRate::Rate (50.0),
+				CounterRate::NoTimeElapsed,
+				CounterRate::Reset (5.0),
+				CounterRate::FirstSample,
+			]);
+
+		assert_eq! (
+			CounterRate::Reset (5.0).per_second (),
+			Some (5.0));
+
+		assert_eq! (
+			CounterRate::FirstSample.per_second (),
+			None);
+
+		fs::remove_dir_all (
+			& state_directory,
+		).unwrap ();
+
+	}
+
+	#[ test ]
+	fn state_directories () {
+
+		let mut options_spec =
+			getopts::Options::new ();
+
+		options_spec.optopt (
+			"",
+			"state-directory",
+			"",
+			"");
+
+		let parse = |arguments: & [& str]| {
+
+			parse_state_directory (
+				& options_spec.parse (
+					arguments,
+				).unwrap ())
+
+		};
+
+		assert_eq! (
+			parse (& []).unwrap (),
+			PathBuf::from (DEFAULT_STATE_DIRECTORY));
+
+		assert_eq! (
+			parse (& [ "--state-directory", "/tmp/state" ]).unwrap (),
+			PathBuf::from ("/tmp/state"));
+
+		assert_eq! (
+			parse (& [ "--state-directory", "state" ]).unwrap_err ().description (),
+			"Invalid value for state-directory, expected an absolute path: state");
+
+	}
+
+	#[ test ]
+	fn save_and_reopen () {
+
+		let state_directory =
+			temp_state_directory ("save");
+
+		{
+
+			let mut plugin_state =
+				PluginState::open (
+					& state_directory,
+					"check-test",
+					"http://example.com/",
+				).unwrap ();
+
+			assert_eq! (
+				plugin_state.get_f64 ("value"),
+				None);
+
+			plugin_state.set_f64 ("value", 1.5);
+			plugin_state.counter_rate ("requests", 10.0, at (1000));
+
+			plugin_state.save ().unwrap ();
+
+			// later changes are only kept if saved
+
+			plugin_state.set_f64 ("value", 2.5);
+
+		}
+
+		let mut plugin_state =
+			PluginState::open (
+				& state_directory,
+				"check-test",
+				"http://example.com/",
+			).unwrap ();
+
+		assert_eq! (
+			plugin_state.get_f64 ("value"),
+			Some (1.5));
+
+		assert_eq! (
+			plugin_state.counter_rate ("requests", 20.0, at (1010)),
+			CounterRate::Rate (1.0));
+
+		// the save goes through a temporary file, which is renamed over the
+		// state, so nothing else is left behind
+
+		let mut file_names: Vec <String> =
+			fs::read_dir (
+				state_directory.join ("check-test"),
+			).unwrap ().map (
+				|entry| entry.unwrap ().file_name ().to_string_lossy ().into_owned ()
+			).collect ();
+
+		file_names.sort ();
+
+		assert_eq! (
+			file_names,
+			vec! [
+				"http%3a%2f%2fexample%2ecom%2f.json",
+				"http%3a%2f%2fexample%2ecom%2f.lock",
+			]);
+
+		fs::remove_dir_all (
+			& state_directory,
+		).unwrap ();
+
+	}
+
+	#[ test ]
+	fn concurrent_runs_wait_for_the_lock () {
+
+		let state_directory =
+			temp_state_directory ("lock");
+
+		let mut first_state =
+			PluginState::open (
+				& state_directory,
+				"check-test",
+				"instance",
+			).unwrap ();
+
+		let (sender, receiver) =
+			mpsc::channel ();
+
+		let thread_state_directory =
+			state_directory.clone ();
+
+		let second_run =
+			thread::spawn (
+				move || {
+
+				let second_state =
+					PluginState::open (
+						& thread_state_directory,
+						"check-test",
+						"instance",
+					).unwrap ();
+
+				sender.send (
+					time::Instant::now (),
+				).unwrap ();
+
+				second_state.get_f64 ("value")
+
+			});
+
+		thread::sleep (
+			time::Duration::from_millis (500));
+
+		assert! (
+			receiver.try_recv ().is_err (),
+			"second run got the lock while the first held it");
+
+		first_state.set_f64 ("value", 3.0);
+		first_state.save ().unwrap ();
+
+		let released =
+			time::Instant::now ();
+
+		drop (
+			first_state);
+
+		assert! (
+			receiver.recv ().unwrap () >= released);
+
+		assert_eq! (
+			second_run.join ().unwrap (),
+			Some (3.0));
+
+		fs::remove_dir_all (
+			& state_directory,
+		).unwrap ();
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,736 @@
+extern crate glob;
+
+use chrono::DateTime;
+use serde_json;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::error;
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+
+use logic::check_result::*;
+use logic::environment::*;
+use logic::plugin_provider::*;
+use logic::runner::*;
+use logic::state::*;
+
+// ---------- fixture environment
+
+// a fake environment loaded from a JSON file under fixtures/, so that checks
+// can be run against recorded system state
+
+#[ derive (Deserialize) ]
+struct FixtureData {
+
+	#[ serde (rename = "now") ]
+	now: Option <String>,
+
+	#[ serde (rename = "files", default) ]
+	files: BTreeMap <String, FixtureFileData>,
+
+	#[ serde (rename = "directories", default) ]
+	directories: Vec <String>,
+
+	#[ serde (rename = "filesystems", default) ]
+	filesystems: BTreeMap <String, FixtureFilesystemData>,
+
+	#[ serde (rename = "commands", default) ]
+	commands: Vec <FixtureCommand>,
+
+	#[ serde (rename = "apt-upgrade-summary") ]
+	apt_upgrade_summary: Option <AptUpgradeSummary>,
+
+}
+
+#[ derive (Deserialize) ]
+struct FixtureFileData {
+
+	#[ serde (rename = "modified") ]
+	modified: Option <String>,
+
+	#[ serde (rename = "contents", default) ]
+	contents: String,
+
+}
+
+#[ derive (Deserialize) ]
+struct FixtureFilesystemData {
+
+	#[ serde (rename = "total-bytes") ]
+	total_bytes: u64,
+
+	#[ serde (rename = "available-bytes") ]
+	available_bytes: u64,
+
+}
+
+#[ derive (Clone, Deserialize) ]
+struct FixtureCommand {
+
+	#[ serde (rename = "program") ]
+	program: String,
+
+	#[ serde (rename = "arguments", default) ]
+	arguments: Vec <String>,
+
+	#[ serde (rename = "exit-code") ]
+	exit_code: Option <i32>,
+
+	#[ serde (rename = "stdout", default) ]
+	stdout: String,
+
+	#[ serde (rename = "stderr", default) ]
+	stderr: String,
+
+}
+
+struct FixtureFile {
+	modified: time::SystemTime,
+	contents: Vec <u8>,
+}
+
+pub struct FixtureEnvironment {
+	now: time::SystemTime,
+	files: BTreeMap <PathBuf, FixtureFile>,
+	directories: BTreeSet <PathBuf>,
+	filesystems: BTreeMap <PathBuf, FilesystemSpace>,
+	commands: Vec <FixtureCommand>,
+	apt_upgrade_summary: Option <AptUpgradeSummary>,
+	state_directory: Option <PathBuf>,
+}
+
+impl FixtureEnvironment {
+
+	pub fn load (
+		fixture_name: & str,
+	) -> Result <FixtureEnvironment, Box <error::Error>> {
+
+		let fixture_path =
+			Path::new (env! ("CARGO_MANIFEST_DIR"))
+				.join ("fixtures")
+				.join (format! ("{}.json", fixture_name));
+
+		let mut fixture_string =
+			String::new ();
+
+		File::open (
+			& fixture_path,
+		) ?.read_to_string (
+			& mut fixture_string,
+		) ?;
+
+		let fixture_data: FixtureData =
+			serde_json::from_str (
+				& fixture_string,
+			) ?;
+
+		let now =
+			match fixture_data.now {
+				Some (ref now) => parse_timestamp (now) ?,
+				None => time::UNIX_EPOCH,
+			};
+
+		let mut files =
+			BTreeMap::new ();
+
+		let mut directories =
+			BTreeSet::new ();
+
+		for directory in fixture_data.directories {
+
+			directories.insert (
+				PathBuf::from (directory));
+
+		}
+
+		for (file_path, file_data) in fixture_data.files {
+
+			files.insert (
+				PathBuf::from (file_path),
+				FixtureFile {
+					modified: match file_data.modified {
+						Some (ref modified) => parse_timestamp (modified) ?,
+						None => now,
+					},
+					contents: file_data.contents.into_bytes (),
+				});
+
+		}
+
+		// parent directories exist implicitly
+
+		let mut parents: Vec <PathBuf> =
+			Vec::new ();
+
+		for path in files.keys ().chain (directories.iter ()) {
+
+			let mut parent =
+				path.parent ();
+
+			while let Some (parent_path) = parent {
+
+				parents.push (
+					parent_path.to_path_buf ());
+
+				parent =
+					parent_path.parent ();
+
+			}
+
+		}
+
+		directories.extend (
+			parents);
+
+		let mut filesystems =
+			BTreeMap::new ();
+
+		for (filesystem_path, filesystem_data)
+			in fixture_data.filesystems {
+
+			filesystems.insert (
+				PathBuf::from (filesystem_path),
+				FilesystemSpace {
+					total_bytes: filesystem_data.total_bytes,
+					available_bytes: filesystem_data.available_bytes,
+				});
+
+		}
+
+		Ok (FixtureEnvironment {
+			now: now,
+			files: files,
+			directories: directories,
+			filesystems: filesystems,
+			commands: fixture_data.commands,
+			apt_upgrade_summary: fixture_data.apt_upgrade_summary,
+			state_directory: None,
+		})
+
+	}
+
+}
+
+impl Environment for FixtureEnvironment {
+
+	fn now (
+		& self,
+	) -> time::SystemTime {
+
+		self.now
+
+	}
+
+	fn read_file (
+		& self,
+		path: & Path,
+	) -> io::Result <Vec <u8>> {
+
+		self.files.get (
+			path,
+		).map (
+			|file| file.contents.clone ()
+		).ok_or_else (
+			|| not_found (path)
+		)
+
+	}
+
+	fn file_metadata (
+		& self,
+		path: & Path,
+	) -> io::Result <FileMetadata> {
+
+		if let Some (file) = self.files.get (path) {
+
+			Ok (FileMetadata {
+				is_directory: false,
+				size: file.contents.len () as u64,
+				modified: file.modified,
+			})
+
+		} else if self.directories.contains (path) {
+
+			Ok (FileMetadata {
+				is_directory: true,
+				size: 0,
+				modified: self.now,
+			})
+
+		} else {
+
+			Err (not_found (path))
+
+		}
+
+	}
+
+	fn read_directory (
+		& self,
+		path: & Path,
+	) -> io::Result <Vec <String>> {
+
+		if ! self.directories.contains (path) {
+			return Err (not_found (path));
+		}
+
+		Ok (
+			self.files.keys ().chain (
+				self.directories.iter (),
+			).filter (
+				|child_path|
+				child_path.parent () == Some (path)
+			).filter_map (
+				|child_path|
+				child_path.file_name ()
+			).map (
+				|file_name|
+				file_name.to_string_lossy ().into_owned ()
+			).collect ()
+		)
+
+	}
+
+	fn glob (
+		& self,
+		pattern: & str,
+	) -> Result <Vec <PathBuf>, Box <error::Error>> {
+
+		let pattern =
+			glob::Pattern::new (
+				pattern,
+			) ?;
+
+		let match_options =
+			glob::MatchOptions {
+				case_sensitive: true,
+				require_literal_separator: true,
+				require_literal_leading_dot: false,
+			};
+
+		Ok (
+			self.files.keys ().chain (
+				self.directories.iter (),
+			).filter (
+				|path|
+				pattern.matches_path_with (
+					path,
+					& match_options)
+			).cloned ().collect ()
+		)
+
+	}
+
+	fn filesystem_space (
+		& self,
+		path: & Path,
+	) -> io::Result <FilesystemSpace> {
+
+		self.filesystems.get (
+			path,
+		).cloned ().ok_or_else (
+			|| not_found (path)
+		)
+
+	}
+
+	fn run_command (
+		& self,
+		program: & str,
+		arguments: & [String],
+	) -> io::Result <CommandOutput> {
+
+		self.commands.iter ().find (
+			|command|
+			command.program == program
+			&& command.arguments.as_slice () == arguments
+		).map (
+			|command|
+			CommandOutput {
+				exit_code: command.exit_code,
+				stdout: command.stdout.clone ().into_bytes (),
+				stderr: command.stderr.clone ().into_bytes (),
+			}
+		).ok_or_else (
+			|| not_found (Path::new (program))
+		)
+
+	}
+
+	fn apt_upgrade_summary (
+		& self,
+		_root_path: & Path,
+	) -> Result <AptUpgradeSummary, Box <error::Error>> {
+
+		self.apt_upgrade_summary.ok_or_else (
+			|| "no apt upgrade summary in fixture".into ()
+		)
+
+	}
+
+	fn enter_phase (
+		& self,
+		_phase: & str,
+	) {
+	}
+
+	// state only exists when the test gives a --state-directory, since it
+	// has to outlive the fixture
+
+	fn open_state (
+		& self,
+		plugin_name: & str,
+		instance_key: & str,
+	) -> Result <PluginState, Box <error::Error>> {
+
+		match self.state_directory {
+
+			Some (ref state_directory) =>
+				PluginState::open (
+					state_directory,
+					plugin_name,
+					instance_key),
+
+			None =>
+				Err ("no state directory given to fixture test".into ()),
+
+		}
+
+	}
+
+}
+
+fn parse_timestamp (
+	timestamp_string: & str,
+) -> Result <time::SystemTime, Box <error::Error>> {
+
+	let timestamp =
+		DateTime::parse_from_rfc3339 (
+			timestamp_string,
+		) ?;
+
+	Ok (
+		time::UNIX_EPOCH
+		+ time::Duration::from_secs (
+			timestamp.timestamp () as u64))
+
+}
+
+fn not_found (
+	path: & Path,
+) -> io::Error {
+
+	io::Error::new (
+		io::ErrorKind::NotFound,
+		format! (
+			"not in fixture: {}",
+			path.to_string_lossy ()))
+
+}
+
+// ---------- running checks
+
+pub fn run_with_fixture (
+	plugin_provider: & PluginProvider,
+	fixture_name: & str,
+	arguments: & [& str],
+) -> CheckResult {
+
+	let mut environment =
+		FixtureEnvironment::load (
+			fixture_name,
+		).unwrap_or_else (
+			|error|
+			panic! (
+				"unable to load fixture {}: {}",
+				fixture_name,
+				error));
+
+	let options_spec =
+		plugin_provider.build_options_spec ();
+
+	let options_matches =
+		options_spec.parse (
+			arguments,
+		).unwrap_or_else (
+			|error|
+			panic! (
+				"invalid arguments for {}: {}",
+				plugin_provider.name (),
+				error));
+
+	if options_matches.opt_present ("state-directory") {
+
+		environment.state_directory =
+			Some (
+				parse_state_directory (
+					& options_matches,
+				).unwrap ());
+
+	}
+
+	run_from_options_matches (
+		plugin_provider,
+		& options_spec,
+		& options_matches,
+		& environment)
+
+}
+
+// for checks which talk to a server started by the test rather than reading
+// from the environment
+
+pub fn run_without_fixture (
+	plugin_provider: & PluginProvider,
+	arguments: & [& str],
+) -> CheckResult {
+
+	let options_spec =
+		plugin_provider.build_options_spec ();
+
+	let options_matches =
+		options_spec.parse (
+			arguments,
+		).unwrap_or_else (
+			|error|
+			panic! (
+				"invalid arguments for {}: {}",
+				plugin_provider.name (),
+				error));
+
+	let mut environment =
+		SystemEnvironment::new ();
+
+	environment.set_state_directory (
+		parse_state_directory (
+			& options_matches,
+		).unwrap ());
+
+	run_from_options_matches (
+		plugin_provider,
+		& options_spec,
+		& options_matches,
+		& environment)
+
+}
+
+pub fn assert_status_messages (
+	check_result: & CheckResult,
+	expected_status: CheckStatus,
+	expected_messages: & [& str],
+) {
+
+	let messages: Vec <& str> =
+		check_result.status_messages ().iter ().map (
+			|status_message| status_message.message ()
+		).collect ();
+
+	assert_eq! (
+		(* check_result.status (), messages.as_slice ()),
+		(expected_status, expected_messages));
+
+}
+
+pub fn assert_performance_data (
+	check_result: & CheckResult,
+	expected_performance_data: & [& str],
+) {
+
+	let performance_data: Vec <String> =
+		check_result.performance_data ().iter ().map (
+			|performance_data| performance_data.to_string ()
+		).collect ();
+
+	assert_eq! (
+		performance_data,
+		expected_performance_data);
+
+}
+
+// ---------- stand-in servers
+
+// accepts a single connection on a local port and hands it to the given
+// function on its own thread
+
+pub fn start_stand_in_server <
+	Serve: FnOnce (TcpStream) + Send + 'static,
+> (
+	serve: Serve,
+) -> u16 {
+
+	let listener =
+		TcpListener::bind (
+			"127.0.0.1:0",
+		).unwrap ();
+
+	let port =
+		listener.local_addr ().unwrap ().port ();
+
+	thread::spawn (
+		move || {
+
+		let (stream, _) =
+			listener.accept ().unwrap ();
+
+		serve (stream);
+
+	});
+
+	port
+
+}
+
+// a request as received by a stand-in http server, with header names in
+// lower case
+
+#[ derive (Debug) ]
+pub struct StandInRequest {
+	pub request_line: String,
+	pub headers: Vec <(String, String)>,
+	pub body: String,
+}
+
+impl StandInRequest {
+
+	pub fn header (
+		& self,
+		name: & str,
+	) -> Option <& str> {
+
+		self.headers.iter ().find (
+			|& & (ref header_name, _)| header_name == name
+		).map (
+			|& (_, ref header_value)| header_value.as_str ()
+		)
+
+	}
+
+}
+
+// answers a single http request with the given status, headers and body,
+// and hands back what it received
+
+pub fn start_stand_in_http_server (
+	response_status: & 'static str,
+	response_headers: & 'static [(& 'static str, & 'static str)],
+	response_body: & 'static str,
+) -> (u16, mpsc::Receiver <StandInRequest>) {
+
+	let (sender, receiver) =
+		mpsc::channel ();
+
+	let port =
+		start_stand_in_server (
+			move |stream| {
+
+			let mut reader =
+				BufReader::new (
+					stream.try_clone ().unwrap ());
+
+			let mut request_line =
+				String::new ();
+
+			reader.read_line (
+				& mut request_line,
+			).unwrap ();
+
+			let mut headers: Vec <(String, String)> =
+				Vec::new ();
+
+			loop {
+
+				let mut header_line =
+					String::new ();
+
+				reader.read_line (
+					& mut header_line,
+				).unwrap ();
+
+				let header_line =
+					header_line.trim_right ();
+
+				if header_line.is_empty () {
+					break;
+				}
+
+				let mut header_parts =
+					header_line.splitn (2, ':');
+
+				headers.push ((
+					header_parts.next ().unwrap ().to_lowercase (),
+					header_parts.next ().unwrap_or ("").trim ().to_string (),
+				));
+
+			}
+
+			let content_length: usize =
+				headers.iter ().find (
+					|& & (ref name, _)| name == "content-length"
+				).map (
+					|& (_, ref value)| value.parse ().unwrap ()
+				).unwrap_or (0);
+
+			let mut body =
+				vec! [0; content_length];
+
+			reader.read_exact (
+				& mut body,
+			).unwrap ();
+
+			let mut response =
+				format! (
+					"HTTP/1.1 {}\r\n",
+					response_status);
+
+			for & (name, value) in response_headers.iter () {
+
+				response.push_str (
+					& format! (
+						"{}: {}\r\n",
+						name,
+						value));
+
+			}
+
+			response.push_str (
+				& format! (
+					"Content-Length: {}\r\n\
+					Connection: close\r\n\
+					\r\n\
+					{}",
+					response_body.len (),
+					response_body));
+
+			(& stream).write_all (
+				response.as_bytes (),
+			).unwrap ();
+
+			// the test may not wait for the request
+
+			sender.send (
+				StandInRequest {
+					request_line: request_line.trim_right ().to_string (),
+					headers: headers,
+					body: String::from_utf8 (body).unwrap (),
+				},
+			).ok ();
+
+		});
+
+	(port, receiver)
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,172 @@
+extern crate libc;
+
+use std::error;
+use std::ffi;
+use std::ptr;
+
+// ---------- upgrade summary
+
+#[ derive (Clone, Copy, Debug, Default, Deserialize) ]
+pub struct UpgradeSummary {
+
+	#[ serde (rename = "upgrade") ]
+	pub upgrade: u64,
+
+	#[ serde (rename = "remove") ]
+	pub remove: u64,
+
+	#[ serde (rename = "install") ]
+	pub install: u64,
+
+	#[ serde (rename = "broken") ]
+	pub broken: u64,
+
+	#[ serde (rename = "bad") ]
+	pub bad: u64,
+
+}
+
+pub fn configuration_set_string <
+	NameAsStr: AsRef <str>,
+	ValueAsStr: AsRef <str>,
+> (
+	name_as_str: NameAsStr,
+	value_as_str: ValueAsStr,
+) -> Result <(), Box <error::Error>> {
+
+	let name =
+		ffi::CString::new (
+			name_as_str.as_ref (),
+		) ?;
+
+	let value =
+		ffi::CString::new (
+			value_as_str.as_ref (),
+		) ?;
+
+	unsafe {
+
+		aptc_extern::aptc_configuration_set_string (
+			name.as_ptr (),
+			value.as_ptr ());
+
+	}
+
+	Ok (())
+
+}
+
+pub fn upgrade_summary_get (
+) -> Result <UpgradeSummary, Box <error::Error>> {
+
+	let mut summary =
+		aptc_extern::UpgradeSummary {
+			upgrade: 0,
+			remove: 0,
+			install: 0,
+			broken: 0,
+			bad: 0,
+			reserved05: 0,
+			reserved06: 0,
+			reserved07: 0,
+			reserved08: 0,
+			reserved09: 0,
+			reserved10: 0,
+			reserved11: 0,
+			reserved12: 0,
+			reserved13: 0,
+			reserved14: 0,
+			reserved15: 0,
+		};
+
+	let success =
+		unsafe {
+			aptc_extern::aptc_upgrade_summary_get (
+				& mut summary)
+		};
+
+	if success {
+
+		Ok (UpgradeSummary {
+			upgrade: summary.upgrade,
+			remove: summary.remove,
+			install: summary.install,
+			broken: summary.broken,
+			bad: summary.bad,
+		})
+
+	} else {
+
+		let error_c_string =
+			unsafe {
+				aptc_extern::aptc_error_message ()
+			};
+
+		if error_c_string == ptr::null () {
+
+			Err (
+				"unknown error".into ())
+
+		} else {
+
+			let error_c_str =
+				unsafe {
+					ffi::CStr::from_ptr (
+						error_c_string)
+				};
+
+			Err (
+				error_c_str.to_string_lossy ().into_owned ().into ())
+
+		}
+
+	}
+
+}
+
+mod aptc_extern {
+
+	use super::libc;
+
+	#[ repr (C) ]
+	pub struct UpgradeSummary {
+		pub upgrade: u64,
+		pub remove: u64,
+		pub install: u64,
+		pub broken: u64,
+		pub bad: u64,
+		pub reserved05: u64,
+		pub reserved06: u64,
+		pub reserved07: u64,
+		pub reserved08: u64,
+		pub reserved09: u64,
+		pub reserved10: u64,
+		pub reserved11: u64,
+		pub reserved12: u64,
+		pub reserved13: u64,
+		pub reserved14: u64,
+		pub reserved15: u64,
+	}
+
+	#[ link (name = "apt-pkg") ]
+	#[ link (name = "stdc++") ]
+	#[ link (name = "aptc", kind = "static") ]
+	extern "C" {
+
+		pub fn aptc_configuration_set_string (
+			name: * const libc::c_char,
+			value: * const libc::c_char,
+		);
+
+		pub fn aptc_upgrade_summary_get (
+			summary: * mut UpgradeSummary,
+		) -> bool;
+
+		pub fn aptc_error_message (
+		) -> * const libc::c_char;
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -1,10 +1,150 @@
 use super::http_prelude::*;
 
-use nom;
+use chrono::NaiveDate;
 
-use der_parser;
-use der_parser::DerObject;
-use der_parser::DerObjectContent;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+
+// ---------- certificate information
+
+#[ derive (Clone, Debug, PartialEq) ]
+pub struct CertificateInfo {
+	pub subject: String,
+	pub subject_common_name: Option <String>,
+	pub issuer: String,
+	pub issuer_common_name: Option <String>,
+	pub subject_alternative_names: Vec <String>,
+	pub serial: String,
+	pub signature_algorithm: String,
+	pub key_type: String,
+	pub key_bits: Option <u64>,
+	pub not_before: NaiveDateTime,
+	pub not_after: NaiveDateTime,
+}
+
+impl CertificateInfo {
+
+	pub fn is_self_signed (& self) -> bool {
+		self.subject == self.issuer
+	}
+
+	// md5 and sha1 signatures can be forged
+
+	pub fn has_weak_signature (& self) -> bool {
+
+		let signature_algorithm =
+			self.signature_algorithm.to_lowercase ();
+
+		signature_algorithm.starts_with ("md5")
+		|| signature_algorithm.contains ("sha1")
+
+	}
+
+	pub fn has_weak_key (& self) -> bool {
+
+		match (self.key_type.as_str (), self.key_bits) {
+			("RSA", Some (bits)) | ("DSA", Some (bits)) => bits < 2048,
+			("EC", Some (bits)) => bits < 256,
+			_ => false,
+		}
+
+	}
+
+	// whether the subject alternative names, or the common name if there are
+	// none, include a name, allowing a wildcard for the leftmost label
+
+	pub fn covers_name (
+		& self,
+		name: & str,
+	) -> bool {
+
+		let name =
+			name.trim_right_matches ('.').to_lowercase ();
+
+		if name.parse::<IpAddr> ().is_ok () {
+
+			return self.subject_alternative_names.iter ().any (
+				|alternative_name|
+				alternative_name.starts_with ("IP:")
+				&& alternative_name [3 ..].parse::<IpAddr> ().ok ()
+					== name.parse::<IpAddr> ().ok ()
+			);
+
+		}
+
+		let dns_names: Vec <String> =
+			if self.subject_alternative_names.is_empty () {
+
+				self.subject_common_name.iter ().map (
+					|common_name| common_name.to_lowercase (),
+				).collect ()
+
+			} else {
+
+				self.subject_alternative_names.iter ().filter (
+					|alternative_name| alternative_name.starts_with ("DNS:"),
+				).map (
+					|alternative_name| alternative_name [4 ..].to_lowercase (),
+				).collect ()
+
+			};
+
+		dns_names.iter ().any (
+			|dns_name|
+
+			if dns_name.starts_with ("*.") {
+
+				match name.find ('.') {
+					Some (position) =>
+						position > 0 && & name [position ..] == & dns_name [1 ..],
+					None =>
+						false,
+				}
+
+			} else {
+
+				dns_name == & name
+
+			}
+
+		)
+
+	}
+
+	// a short name for messages, the common name if there is one
+
+	pub fn name (& self) -> & str {
+
+		self.subject_common_name.as_ref ().map (
+			|common_name| common_name.as_str (),
+		).unwrap_or (
+			& self.subject,
+		)
+
+	}
+
+}
+
+pub fn inspect_certificate_chain (
+	peer_certificates: & Option <Vec <RustTlsCertificate>>,
+) -> Vec <Result <CertificateInfo, String>> {
+
+	match * peer_certificates {
+
+		Some (ref peer_certificates) =>
+			peer_certificates.iter ().map (
+				|& RustTlsCertificate (ref peer_certificate)|
+				inspect_certificate (
+					peer_certificate)
+			).collect (),
+
+		None =>
+			Vec::new (),
+
+	}
+
+}
 
 pub fn get_certificate_validity (
 	peer_certificates: & Option <Vec <RustTlsCertificate>>,
@@ -19,9 +159,14 @@ pub fn get_certificate_validity (
 			let & RustTlsCertificate (ref peer_certificate) =
 				* peer_certificate;
 
-			return get_certificate_validity_real (
+			return inspect_certificate (
 				& peer_certificate,
-			).ok ();
+			).ok ().map (
+				|certificate_info| (
+					certificate_info.not_before,
+					certificate_info.not_after,
+				)
+			);
 
 		}
 
@@ -31,116 +176,994 @@ pub fn get_certificate_validity (
 
 }
 
-pub fn get_certificate_validity_real (
+pub fn inspect_certificate (
 	bytes: & [u8],
-) -> Result <(NaiveDateTime, NaiveDateTime), ()> {
+) -> Result <CertificateInfo, String> {
 
-	let raw =
-		match der_parser::parse_der (
-			& bytes,
-		) {
+	let (certificate, _) =
+		der_read (
+			bytes,
+			DER_SEQUENCE,
+		) ?;
 
-		nom::IResult::Done (_remain, value) =>
-			value,
+	let certificate_items =
+		der_items (
+			certificate,
+		) ?;
 
-		_ =>
-			return Err (()),
+	if certificate_items.len () != 3 {
+		return Err ("invalid certificate".to_string ());
+	}
 
-	};
+	let tbs_items =
+		der_items (
+			der_expect (
+				& certificate_items [0],
+				DER_SEQUENCE,
+			) ?,
+		) ?;
+
+	// the version is optional and explicitly tagged
+
+	let offset =
+		match tbs_items.first () {
+			Some (& (DER_CONTEXT_0, _)) => 1,
+			_ => 0,
+		};
+
+	if tbs_items.len () < offset + 6 {
+		return Err ("invalid certificate".to_string ());
+	}
 
-	let certificate =
-		der_sequence (
-			& raw,
+	let serial =
+		der_expect (
+			& tbs_items [offset],
+			DER_INTEGER,
 		) ?;
 
-	let certificate_main =
-		der_sequence (
-			& certificate [0],
+	let (issuer, issuer_common_name) =
+		decode_name (
+			der_expect (
+				& tbs_items [offset + 2],
+				DER_SEQUENCE,
+			) ?,
 		) ?;
 
-	let certificate_validity =
-		der_sequence (
-			& certificate_main [4],
+	let validity_items =
+		der_items (
+			der_expect (
+				& tbs_items [offset + 3],
+				DER_SEQUENCE,
+			) ?,
 		) ?;
 
-	let valid_from =
-		der_utctime (
-			& certificate_validity [0],
+	if validity_items.len () != 2 {
+		return Err ("invalid certificate validity".to_string ());
+	}
+
+	let (subject, subject_common_name) =
+		decode_name (
+			der_expect (
+				& tbs_items [offset + 4],
+				DER_SEQUENCE,
+			) ?,
 		) ?;
 
-	let valid_to =
-		der_utctime (
-			& certificate_validity [1],
+	let (key_type, key_bits) =
+		decode_public_key (
+			der_expect (
+				& tbs_items [offset + 5],
+				DER_SEQUENCE,
+			) ?,
+		) ?;
+
+	let subject_alternative_names =
+		match tbs_items [offset + 6 ..].iter ().find (
+			|& & (tag, _)| tag == DER_CONTEXT_3,
+		) {
+
+			Some (& (_, extensions)) =>
+				decode_subject_alternative_names (
+					extensions,
+				) ?,
+
+			None =>
+				Vec::new (),
+
+		};
+
+	let signature_algorithm_items =
+		der_items (
+			der_expect (
+				& certificate_items [1],
+				DER_SEQUENCE,
+			) ?,
 		) ?;
 
+	let signature_algorithm =
+		algorithm_name (
+			der_expect (
+				signature_algorithm_items.first ().ok_or_else (
+					|| "invalid signature algorithm".to_string (),
+				) ?,
+				DER_OID,
+			) ?,
+		);
+
+	Ok (CertificateInfo {
+		subject: subject,
+		subject_common_name: subject_common_name,
+		issuer: issuer,
+		issuer_common_name: issuer_common_name,
+		subject_alternative_names: subject_alternative_names,
+		serial: decode_serial (serial),
+		signature_algorithm: signature_algorithm,
+		key_type: key_type,
+		key_bits: key_bits,
+		not_before: decode_time (& validity_items [0]) ?,
+		not_after: decode_time (& validity_items [1]) ?,
+	})
+
+}
+
+// ---------- certificate fields
+
+fn decode_name (
+	name: & [u8],
+) -> Result <(String, Option <String>), String> {
+
+	let mut parts: Vec <String> =
+		Vec::new ();
+
+	let mut common_name: Option <String> =
+		None;
+
+	for relative_name in der_items (name) ? {
+
+		for attribute in der_items (
+			der_expect (
+				& relative_name,
+				DER_SET,
+			) ?,
+		) ? {
+
+			let attribute_items =
+				der_items (
+					der_expect (
+						& attribute,
+						DER_SEQUENCE,
+					) ?,
+				) ?;
+
+			if attribute_items.len () != 2 {
+				return Err ("invalid name attribute".to_string ());
+			}
+
+			let attribute_oid =
+				decode_oid (
+					der_expect (
+						& attribute_items [0],
+						DER_OID,
+					) ?,
+				);
+
+			let attribute_value =
+				decode_string (
+					& attribute_items [1]);
+
+			let attribute_name =
+				match attribute_oid.as_str () {
+					"2.5.4.3" => "CN",
+					"2.5.4.6" => "C",
+					"2.5.4.7" => "L",
+					"2.5.4.8" => "ST",
+					"2.5.4.10" => "O",
+					"2.5.4.11" => "OU",
+					other => other,
+				};
+
+			if attribute_name == "CN" && common_name.is_none () {
+
+				common_name =
+					Some (attribute_value.clone ());
+
+			}
+
+			parts.push (
+				format! (
+					"{}={}",
+					attribute_name,
+					attribute_value));
+
+		}
+
+	}
+
 	Ok ((
-		valid_from,
-		valid_to,
+		parts.join (", "),
+		common_name,
 	))
 
 }
 
-fn der_sequence <'a> (
-	der_object: & 'a DerObject,
-) -> Result <& 'a [DerObject <'a>], ()> {
+fn decode_public_key (
+	subject_public_key_info: & [u8],
+) -> Result <(String, Option <u64>), String> {
+
+	let items =
+		der_items (
+			subject_public_key_info,
+		) ?;
+
+	if items.len () != 2 {
+		return Err ("invalid public key".to_string ());
+	}
+
+	let algorithm_items =
+		der_items (
+			der_expect (
+				& items [0],
+				DER_SEQUENCE,
+			) ?,
+		) ?;
+
+	let algorithm_oid =
+		decode_oid (
+			der_expect (
+				algorithm_items.first ().ok_or_else (
+					|| "invalid public key algorithm".to_string (),
+				) ?,
+				DER_OID,
+			) ?,
+		);
+
+	let public_key =
+		der_expect (
+			& items [1],
+			DER_BIT_STRING,
+		) ?;
+
+	match algorithm_oid.as_str () {
+
+		"1.2.840.113549.1.1.1" => {
+
+			// the bit string holds a sequence of modulus and exponent
+
+			if public_key.is_empty () {
+				return Err ("invalid rsa public key".to_string ());
+			}
+
+			let (rsa_key, _) =
+				der_read (
+					& public_key [1 ..],
+					DER_SEQUENCE,
+				) ?;
+
+			let rsa_items =
+				der_items (
+					rsa_key,
+				) ?;
+
+			let modulus =
+				der_expect (
+					rsa_items.first ().ok_or_else (
+						|| "invalid rsa public key".to_string (),
+					) ?,
+					DER_INTEGER,
+				) ?;
+
+			Ok ((
+				"RSA".to_string (),
+				Some (integer_bits (modulus)),
+			))
+
+		},
+
+		"1.2.840.10045.2.1" => {
+
+			let curve_bits =
+				match algorithm_items.get (1) {
+
+					Some (& (DER_OID, curve)) =>
+						match decode_oid (curve).as_str () {
+							"1.2.840.10045.3.1.7" => Some (256),
+							"1.3.132.0.34" => Some (384),
+							"1.3.132.0.35" => Some (521),
+							_ => None,
+						},
+
+					_ =>
+						None,
+
+				};
 
-	match der_object.content {
+			Ok ((
+				"EC".to_string (),
+				curve_bits,
+			))
 
-		DerObjectContent::Sequence (ref value) =>
-			Ok (& value),
+		},
+
+		"1.2.840.10040.4.1" => {
+
+			// the dsa parameters hold p, q and g
+
+			let prime_bits =
+				match algorithm_items.get (1) {
+
+					Some (& (DER_SEQUENCE, parameters)) =>
+						der_items (parameters) ?.first ().map (
+							|& (_, prime)| integer_bits (prime),
+						),
+
+					_ =>
+						None,
+
+				};
+
+			Ok ((
+				"DSA".to_string (),
+				prime_bits,
+			))
+
+		},
+
+		"1.3.101.112" =>
+			Ok ((
+				"Ed25519".to_string (),
+				Some (256),
+			)),
+
+		"1.3.101.113" =>
+			Ok ((
+				"Ed448".to_string (),
+				Some (456),
+			)),
 
 		_ =>
-			Err (()),
+			Ok ((
+				algorithm_oid,
+				None,
+			)),
 
 	}
 
 }
 
-fn der_utctime <'a> (
-	der_object: & 'a DerObject,
-) -> Result <NaiveDateTime, ()> {
+fn decode_subject_alternative_names (
+	extensions_wrapper: & [u8],
+) -> Result <Vec <String>, String> {
+
+	let (extensions, _) =
+		der_read (
+			extensions_wrapper,
+			DER_SEQUENCE,
+		) ?;
+
+	for extension in der_items (extensions) ? {
+
+		let extension_items =
+			der_items (
+				der_expect (
+					& extension,
+					DER_SEQUENCE,
+				) ?,
+			) ?;
+
+		let extension_oid =
+			decode_oid (
+				der_expect (
+					extension_items.first ().ok_or_else (
+						|| "invalid extension".to_string (),
+					) ?,
+					DER_OID,
+				) ?,
+			);
+
+		if extension_oid != "2.5.29.17" {
+			continue;
+		}
+
+		// the value is the last item, after the optional critical flag
+
+		let extension_value =
+			der_expect (
+				extension_items.last ().unwrap (),
+				DER_OCTET_STRING,
+			) ?;
+
+		let (general_names, _) =
+			der_read (
+				extension_value,
+				DER_SEQUENCE,
+			) ?;
 
-	match der_object.content {
+		let mut names: Vec <String> =
+			Vec::new ();
 
-		DerObjectContent::UTCTime (ref value) =>
-			Ok (parse_utc_time (
-				str::from_utf8 (
-					& value,
-				).map_err (|_| ()) ?,
-			).map_err (|_| ()) ?),
+		for (tag, value) in der_items (general_names) ? {
+
+			match tag {
+
+				DER_CONTEXT_PRIMITIVE_2 =>
+					names.push (
+						format! (
+							"DNS:{}",
+							String::from_utf8_lossy (value))),
+
+				DER_CONTEXT_PRIMITIVE_7 if value.len () == 4 =>
+					names.push (
+						format! (
+							"IP:{}",
+							Ipv4Addr::new (
+								value [0], value [1], value [2], value [3]))),
+
+				DER_CONTEXT_PRIMITIVE_7 if value.len () == 16 => {
+
+					let mut segments = [0u16; 8];
+
+					for index in 0 .. 8 {
+
+						segments [index] =
+							(value [index * 2] as u16) << 8
+							| value [index * 2 + 1] as u16;
+
+					}
+
+					names.push (
+						format! (
+							"IP:{}",
+							Ipv6Addr::new (
+								segments [0], segments [1], segments [2],
+								segments [3], segments [4], segments [5],
+								segments [6], segments [7])));
+
+				},
+
+				_ =>
+					(),
+
+			}
+
+		}
+
+		return Ok (names);
+
+	}
+
+	Ok (Vec::new ())
+
+}
+
+fn decode_serial (
+	serial: & [u8],
+) -> String {
+
+	let serial =
+		if serial.len () > 1 && serial [0] == 0 {
+			& serial [1 ..]
+		} else {
+			serial
+		};
+
+	serial.iter ().map (
+		|byte| format! ("{:02X}", byte),
+	).collect::<Vec <String>> ().join (":")
+
+}
+
+fn decode_time (
+	& (tag, value): & (u8, & [u8]),
+) -> Result <NaiveDateTime, String> {
+
+	let invalid_time =
+		|| "invalid certificate time".to_string ();
+
+	let value =
+		if value.last () == Some (& b'Z') {
+			& value [0 .. value.len () - 1]
+		} else {
+			value
+		};
+
+	let year_length =
+		match tag {
+			DER_UTC_TIME => 2,
+			DER_GENERALIZED_TIME => 4,
+			_ => return Err (invalid_time ()),
+		};
+
+	// generalized time may carry a fraction of a second, which we ignore
+
+	let value =
+		match value.iter ().position (|& byte| byte == b'.') {
+
+			Some (position)
+				if tag == DER_GENERALIZED_TIME
+					&& position == year_length + 10
+					&& position + 1 < value.len ()
+					&& value [position + 1 ..].iter ().all (
+						|byte| byte.is_ascii_digit ()) =>
+				& value [0 .. position],
+
+			Some (_) =>
+				return Err (invalid_time ()),
+
+			None =>
+				value,
+
+		};
+
+	// work on the bytes, so a multibyte character can't split a field
+
+	if (value.len () != year_length + 8 && value.len () != year_length + 10)
+		|| ! value.iter ().all (|byte| byte.is_ascii_digit ()) {
+
+		return Err (invalid_time ());
+
+	}
+
+	let number = |start: usize, length: usize| -> u32 {
+		value [start .. start + length].iter ().fold (
+			0,
+			|number, & byte| number * 10 + (byte - b'0') as u32)
+	};
+
+	// utc time has a two digit year, from 1950 to 2049
+
+	let year =
+		match tag {
+			DER_UTC_TIME if number (0, 2) >= 50 => 1900 + number (0, 2),
+			DER_UTC_TIME => 2000 + number (0, 2),
+			_ => number (0, 4),
+		};
+
+	let second =
+		if value.len () == year_length + 10 {
+			number (year_length + 8, 2)
+		} else {
+			0
+		};
+
+	NaiveDate::from_ymd_opt (
+		year as i32,
+		number (year_length, 2),
+		number (year_length + 2, 2),
+	).and_then (
+		|date| date.and_hms_opt (
+			number (year_length + 4, 2),
+			number (year_length + 6, 2),
+			second)
+	).ok_or_else (
+		invalid_time
+	)
+
+}
+
+fn decode_string (
+	& (tag, value): & (u8, & [u8]),
+) -> String {
+
+	match tag {
+
+		// bmp strings are big endian ucs-2
+
+		DER_BMP_STRING =>
+			String::from_utf16_lossy (
+				& value.chunks (2).map (
+					|pair| (pair [0] as u16) << 8
+						| pair.get (1).cloned ().unwrap_or (0) as u16,
+				).collect::<Vec <u16>> ()),
 
 		_ =>
-			Err (()),
+			String::from_utf8_lossy (
+				value,
+			).into_owned (),
+
+	}
+
+}
+
+fn decode_oid (
+	oid: & [u8],
+) -> String {
+
+	let mut components: Vec <u64> =
+		Vec::new ();
+
+	let mut value: u64 = 0;
+
+	for & byte in oid {
+
+		value =
+			(value << 7) | (byte & 0x7f) as u64;
+
+		if byte & 0x80 == 0 {
+
+			if components.is_empty () {
+
+				let first =
+					if value < 80 { value / 40 } else { 2 };
+
+				components.push (first);
+				components.push (value - first * 40);
+
+			} else {
+
+				components.push (value);
+
+			}
+
+			value = 0;
+
+		}
+
+	}
+
+	components.iter ().map (
+		|component| component.to_string (),
+	).collect::<Vec <String>> ().join (".")
+
+}
+
+fn algorithm_name (
+	oid: & [u8],
+) -> String {
+
+	let oid =
+		decode_oid (oid);
+
+	match oid.as_str () {
+		"1.2.840.113549.1.1.4" => "md5WithRSAEncryption",
+		"1.2.840.113549.1.1.5" => "sha1WithRSAEncryption",
+		"1.2.840.113549.1.1.10" => "rsassaPss",
+		"1.2.840.113549.1.1.11" => "sha256WithRSAEncryption",
+		"1.2.840.113549.1.1.12" => "sha384WithRSAEncryption",
+		"1.2.840.113549.1.1.13" => "sha512WithRSAEncryption",
+		"1.2.840.10040.4.3" => "dsa-with-SHA1",
+		"1.2.840.10045.4.1" => "ecdsa-with-SHA1",
+		"1.2.840.10045.4.3.2" => "ecdsa-with-SHA256",
+		"1.2.840.10045.4.3.3" => "ecdsa-with-SHA384",
+		"1.2.840.10045.4.3.4" => "ecdsa-with-SHA512",
+		"1.3.101.112" => "Ed25519",
+		"1.3.101.113" => "Ed448",
+		_ => return oid,
+	}.to_string ()
+
+}
+
+fn integer_bits (
+	integer: & [u8],
+) -> u64 {
+
+	let mut integer =
+		integer;
+
+	while integer.len () > 1 && integer [0] == 0 {
+		integer = & integer [1 ..];
+	}
+
+	match integer.first () {
+
+		Some (& first_byte) =>
+			(integer.len () as u64 - 1) * 8
+			+ (8 - first_byte.leading_zeros () as u64),
+
+		None =>
+			0,
 
 	}
 
 }
 
-fn parse_utc_time (
-	time_string: & str,
-) -> Result <NaiveDateTime, ()> {
+// ---------- der encoding
+
+const DER_INTEGER: u8 = 0x02;
+const DER_BIT_STRING: u8 = 0x03;
+const DER_OCTET_STRING: u8 = 0x04;
+const DER_OID: u8 = 0x06;
+const DER_UTC_TIME: u8 = 0x17;
+const DER_GENERALIZED_TIME: u8 = 0x18;
+const DER_BMP_STRING: u8 = 0x1e;
+const DER_SEQUENCE: u8 = 0x30;
+const DER_SET: u8 = 0x31;
+const DER_CONTEXT_PRIMITIVE_2: u8 = 0x82;
+const DER_CONTEXT_PRIMITIVE_7: u8 = 0x87;
+const DER_CONTEXT_0: u8 = 0xa0;
+const DER_CONTEXT_3: u8 = 0xa3;
+
+// reads one tag, length and value, returning the value and the remaining
+// input
+
+fn der_read_any <'a> (
+	input: & 'a [u8],
+) -> Result <(u8, & 'a [u8], & 'a [u8]), String> {
+
+	if input.len () < 2 {
+		return Err ("truncated der value".to_string ());
+	}
+
+	let tag = input [0];
+
+	let (length, header_length) =
+		if input [1] & 0x80 == 0 {
+
+			(input [1] as usize, 2)
+
+		} else {
+
+			let num_length_bytes =
+				(input [1] & 0x7f) as usize;
+
+			if num_length_bytes == 0 {
+				return Err ("indefinite der length".to_string ());
+			}
+
+			if num_length_bytes > 4
+				|| input.len () < 2 + num_length_bytes {
 
-	if time_string.len () == 11 {
+				return Err ("invalid der length".to_string ());
 
-		Ok (NaiveDateTime::parse_from_str (
-			& format! (
-				"20{}",
-				& time_string [0 .. 10]),
-			"%Y%m%d%H%M",
-		).map_err (|_| ()) ?)
+			}
 
-	} else if time_string.len () == 13 {
+			(
+				input [2 .. 2 + num_length_bytes].iter ().fold (
+					0,
+					|length, & byte| length << 8 | byte as usize),
+				2 + num_length_bytes,
+			)
 
-		Ok (NaiveDateTime::parse_from_str (
-			& format! (
-				"20{}",
-				& time_string [0 .. 12]),
-			"%Y%m%d%H%M%S",
-		).map_err (|_| ()) ?)
+		};
+
+	if input.len () - header_length < length {
+		return Err ("truncated der value".to_string ());
+	}
+
+	Ok ((
+		tag,
+		& input [header_length .. header_length + length],
+		& input [header_length + length ..],
+	))
+
+}
+
+fn der_read <'a> (
+	input: & 'a [u8],
+	expected_tag: u8,
+) -> Result <(& 'a [u8], & 'a [u8]), String> {
+
+	let (tag, value, remaining) =
+		der_read_any (
+			input,
+		) ?;
+
+	der_expect (
+		& (tag, value),
+		expected_tag,
+	).map (
+		|value| (value, remaining),
+	)
+
+}
+
+fn der_items <'a> (
+	mut input: & 'a [u8],
+) -> Result <Vec <(u8, & 'a [u8])>, String> {
+
+	let mut items: Vec <(u8, & 'a [u8])> =
+		Vec::new ();
+
+	while ! input.is_empty () {
+
+		let (tag, value, remaining) =
+			der_read_any (
+				input,
+			) ?;
+
+		items.push (
+			(tag, value));
+
+		input =
+			remaining;
+
+	}
+
+	Ok (items)
+
+}
+
+fn der_expect <'a> (
+	& (tag, value): & (u8, & 'a [u8]),
+	expected_tag: u8,
+) -> Result <& 'a [u8], String> {
+
+	if tag == expected_tag {
+
+		Ok (value)
 
 	} else {
 
-		Err (())
+		Err (
+			format! (
+				"expected der tag {:02x} but got {:02x}",
+				expected_tag,
+				tag))
+
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use chrono::NaiveDate;
+
+	use super::DER_GENERALIZED_TIME;
+	use super::DER_UTC_TIME;
+	use super::decode_time;
+	use super::der_read_any;
+	use super::inspect_certificate;
+
+	#[ test ]
+	fn rsa_certificate () {
+
+		let certificate_info =
+			inspect_certificate (
+				include_bytes! (
+					"../../../fixtures/certificates/rsa-sha256.der"),
+			).unwrap ();
+
+		assert_eq! (
+			certificate_info.subject,
+			"C=GB, O=Example Ltd, CN=www.example.com");
+
+		assert_eq! (
+			certificate_info.name (),
+			"www.example.com");
+
+		assert_eq! (
+			certificate_info.subject_alternative_names,
+			vec! [
+				"DNS:www.example.com",
+				"DNS:example.com",
+				"IP:192.0.2.1",
+			]);
+
+		assert_eq! (
+			certificate_info.serial,
+			"12:34:AB:CD");
+
+		assert_eq! (
+			certificate_info.signature_algorithm,
+			"sha256WithRSAEncryption");
+
+		assert_eq! (
+			(certificate_info.key_type.as_str (), certificate_info.key_bits),
+			("RSA", Some (2048)));
+
+		assert_eq! (
+			certificate_info.not_after,
+			NaiveDate::from_ymd (2027, 1, 1).and_hms (0, 0, 0));
+
+		assert! (certificate_info.covers_name ("www.example.com"));
+		assert! (certificate_info.covers_name ("Example.com."));
+		assert! (certificate_info.covers_name ("192.0.2.1"));
+		assert! (! certificate_info.covers_name ("mail.example.com"));
+
+		assert! (certificate_info.is_self_signed ());
+		assert! (! certificate_info.has_weak_signature ());
+		assert! (! certificate_info.has_weak_key ());
+
+	}
+
+	#[ test ]
+	fn ec_sha1_certificate () {
+
+		let certificate_info =
+			inspect_certificate (
+				include_bytes! (
+					"../../../fixtures/certificates/ec-sha1.der"),
+			).unwrap ();
+
+		assert_eq! (
+			certificate_info.issuer,
+			"CN=Legacy Intermediate CA");
+
+		assert_eq! (
+			certificate_info.serial,
+			"07");
+
+		assert_eq! (
+			(certificate_info.key_type.as_str (), certificate_info.key_bits),
+			("EC", Some (256)));
+
+		// dates after 2049 are encoded as generalized time
+
+		assert_eq! (
+			certificate_info.not_after,
+			NaiveDate::from_ymd (2060, 1, 1).and_hms (0, 0, 0));
+
+		assert! (certificate_info.has_weak_signature ());
+
+	}
+
+	#[ test ]
+	fn truncated_certificate () {
+
+		assert! (
+			inspect_certificate (
+				& include_bytes! (
+					"../../../fixtures/certificates/rsa-sha256.der") [0 .. 100],
+			).is_err ());
+
+	}
+
+	#[ test ]
+	fn malformed_lengths () {
+
+		for & (input, expected_error) in [
+			(& [ 0x30 ] [..], "truncated der value"),
+			(& [ 0x30, 0x80, 0x00, 0x00 ] [..], "indefinite der length"),
+			(& [ 0x30, 0x85, 0x01, 0x00, 0x00, 0x00, 0x00 ] [..], "invalid der length"),
+			(& [ 0x30, 0x82, 0x01 ] [..], "invalid der length"),
+			(& [ 0x30, 0x84, 0xff, 0xff, 0xff, 0xff, 0x00 ] [..], "truncated der value"),
+			(& [ 0x04, 0x03, 0x01, 0x02 ] [..], "truncated der value"),
+		].iter () {
+
+			assert_eq! (
+				der_read_any (input).unwrap_err (),
+				expected_error);
+
+		}
+
+		assert_eq! (
+			der_read_any (& [ 0x04, 0x81, 0x01, 0x2a, 0x05 ]).unwrap (),
+			(0x04, & [ 0x2a ] [..], & [ 0x05 ] [..]));
+
+	}
+
+	#[ test ]
+	fn certificate_times () {
+
+		for & (tag, input, expected_time) in [
+			(DER_UTC_TIME, "270101000000Z", (2027, 1, 1, 0, 0, 0)),
+			(DER_UTC_TIME, "500101123456Z", (1950, 1, 1, 12, 34, 56)),
+			(DER_UTC_TIME, "4912312359Z", (2049, 12, 31, 23, 59, 0)),
+			(DER_GENERALIZED_TIME, "20600101000000Z", (2060, 1, 1, 0, 0, 0)),
+			(DER_GENERALIZED_TIME, "20600101000000.125Z", (2060, 1, 1, 0, 0, 0)),
+		].iter () {
+
+			let (year, month, day, hour, minute, second) =
+				expected_time;
+
+			assert_eq! (
+				decode_time (& (tag, input.as_bytes ())).unwrap (),
+				NaiveDate::from_ymd (year, month, day).and_hms (
+					hour, minute, second));
+
+		}
+
+		for & (tag, input) in [
+			(DER_UTC_TIME, ""),
+			(DER_UTC_TIME, "Z"),
+			(DER_UTC_TIME, "2701010000"),
+			(DER_UTC_TIME, "27010100000Z"),
+			(DER_UTC_TIME, "270101000000.5Z"),
+			(DER_UTC_TIME, "2701é1000000Z"),
+			(DER_UTC_TIME, "é70101000000Z"),
+			(DER_UTC_TIME, "271301000000Z"),
+			(DER_GENERALIZED_TIME, "20600101000000.Z"),
+			(DER_GENERALIZED_TIME, "20600101000000.1xZ"),
+			(DER_GENERALIZED_TIME, "206001010000.5Z"),
+			(0x04, "270101000000Z"),
+		].iter () {
+
+			assert! (
+				decode_time (& (tag, input.as_bytes ())).is_err (),
+				"accepted {}",
+				input);
+
+		}
 
 	}
 
@@ -1,4 +1,6 @@
 use super::http_prelude::*;
+use super::http_data;
+use super::http_tls;
 
 pub struct HttpConnection {
 
@@ -10,6 +12,8 @@ pub struct HttpConnection {
 	tokio_core: TokioCore,
 	hyper_client: HyperClient <HttpConnector>,
 
+	peer_address: Option <SocketAddr>,
+
 	connect_duration: Duration,
 	tls_duration: Option <Duration>,
 
@@ -25,6 +29,36 @@ impl HttpConnection {
 		port: Option <u64>,
 		secure: bool,
 		hostname: String,
+		tls_options: & HttpTlsOptions,
+	) -> HttpResult <HttpConnection> {
+
+		let port =
+			port.unwrap_or (
+				if secure { 443 } else { 80 });
+
+		HttpConnection::connect_to (
+			address,
+			port,
+			Some (port),
+			secure,
+			hostname,
+			tls_options,
+			None)
+
+	}
+
+	// connects to the given address and port, but presents the hostname and
+	// port in the host header and for sni as if it was connecting there. the
+	// connect timeout covers the tcp connection and the tls handshake.
+
+	pub fn connect_to (
+		address: String,
+		connect_port: u64,
+		port: Option <u64>,
+		secure: bool,
+		hostname: String,
+		tls_options: & HttpTlsOptions,
+		connect_timeout: Option <Duration>,
 	) -> HttpResult <HttpConnection> {
 
 		let port =
@@ -42,14 +76,18 @@ impl HttpConnection {
 
 		) ?;
 
+		let rust_tls_client_config =
+			Arc::new (
+				tls_options.client_config () ?);
+
 		// setup stream
 
 		let hyper_uri: HyperUri =
 			format! (
 				"{}://{}:{}/",
 				if secure { "http" } else { "http" },
-				address,
-				port,
+				uri_host (& address),
+				connect_port,
 			).parse ().map_err (
 				|_| HttpError::InvalidUri,
 			) ?;
@@ -63,8 +101,23 @@ impl HttpConnection {
 
 		let start_time = Instant::now ();
 
-		let (http_stream, tls_duration, peer_certificates) =
-			tokio_core.run ({
+		let connect_timeout: Box <Future <Item = (), Error = IoError>> =
+			match connect_timeout {
+
+			Some (connect_timeout) =>
+				Box::new (
+					TokioTimeout::new_at (
+						start_time + connect_timeout,
+						& tokio_core.handle (),
+					).into_future ().flatten ()),
+
+			None =>
+				Box::new (
+					future::empty::<(), IoError> ()),
+
+		};
+
+		let connect_future = {
 
 			let hostname = hostname.to_string ();
 
@@ -73,21 +126,15 @@ impl HttpConnection {
 			).and_then (
 				move |tcp_stream| {
 
+				let peer_address =
+					tcp_stream.peer_addr ().ok ();
+
 				if secure {
 
 					let tls_start_time =
 						Instant::now ();
 
-					let mut rust_tls_client_config =
-						RustTlsClientConfig::new ();
-
-					rust_tls_client_config.root_store.add_trust_anchors (
-						& webpki_roots::ROOTS,
-					);
-
-					Arc::new (
-						rust_tls_client_config,
-					).connect_async (
+					rust_tls_client_config.connect_async (
 						& hostname,
 						tcp_stream,
 					).map_err (
@@ -116,6 +163,7 @@ impl HttpConnection {
 						(
 							HttpStream::Https (
 								rust_tls_stream),
+							peer_address,
 							Some (tls_end_time - tls_start_time),
 							peer_certificates,
 						)
@@ -135,6 +183,7 @@ impl HttpConnection {
 					future::ok ((
 						HttpStream::Http (
 							tcp_stream),
+						peer_address,
 						None,
 						None,
 					)).boxed ()
@@ -143,13 +192,24 @@ impl HttpConnection {
 
 			})
 
-		}).map_err (
-			|error|
+		};
 
-			HttpError::Unknown (
-				Box::new (error)),
+		let (http_stream, peer_address, tls_duration, peer_certificates) =
+			match tokio_core.run (
+				connect_future.select2 (connect_timeout)) {
 
-		) ?;
+			Ok (FutureEither::A ((connection, _))) =>
+				connection,
+
+			Err (FutureEither::A ((connect_error, _))) =>
+				return Err (
+					http_tls::tls_error (
+						connect_error)),
+
+			_ =>
+				return Err (HttpError::Timeout),
+
+		};
 
 		let http_shared_stream =
 			HttpSharedStream::new (
@@ -190,6 +250,8 @@ impl HttpConnection {
 			tokio_core: tokio_core,
 			hyper_client: hyper_client,
 
+			peer_address: peer_address,
+
 			connect_duration: end_time - start_time,
 			tls_duration: tls_duration,
 
@@ -213,7 +275,7 @@ impl HttpConnection {
 			format! (
 				"{}://{}:{}{}",
 				if self.secure { "https" } else { "http" },
-				self.address,
+				uri_host (& self.address),
 				self.port,
 				request.path)
 
@@ -222,16 +284,27 @@ impl HttpConnection {
 			format! (
 				"{}://{}{}",
 				if self.secure { "https" } else { "http" },
-				self.address,
+				uri_host (& self.address),
 				request.path)
 
 		}.parse ().map_err (|_| HttpError::InvalidUri) ?;
 
 		// create request
 
+		let hyper_method =
+			match request.method {
+				HttpMethod::Get => HyperMethod::Get,
+				HttpMethod::Head => HyperMethod::Head,
+				HttpMethod::Post => HyperMethod::Post,
+				HttpMethod::Put => HyperMethod::Put,
+				HttpMethod::Delete => HyperMethod::Delete,
+				HttpMethod::Options => HyperMethod::Options,
+				HttpMethod::Patch => HyperMethod::Patch,
+			};
+
 		let mut hyper_request =
 			HyperRequest::new (
-				HyperMethod::Get,
+				hyper_method,
 				hyper_uri);
 
 		{
@@ -240,6 +313,7 @@ impl HttpConnection {
 				hyper_request.headers_mut ();
 
 			let mut got_host = false;
+			let mut got_content_length = false;
 
 			for & (ref header_name, ref header_value)
 				in request.headers.iter () {
@@ -251,26 +325,48 @@ impl HttpConnection {
 					got_host = true;
 				}
 
+				if header_name == "content-length" {
+					got_content_length = true;
+				}
+
 				hyper_headers.set_raw (
 					header_name.to_string (),
 					vec! [ header_value.as_bytes ().to_vec () ]);
 
 			}
 
+			// methods which carry a body always send a length, even if it
+			// is empty, since some servers reject them otherwise
+
+			let method_has_body =
+				match request.method {
+					HttpMethod::Post | HttpMethod::Put | HttpMethod::Patch => true,
+					_ => false,
+				};
+
+			if ! got_content_length
+				&& (method_has_body || ! request.body.is_empty ()) {
+
+				hyper_headers.set (
+					HyperContentLengthHeader (
+						request.body.len () as u64));
+
+			}
+
 			if ! got_host {
 
 				if self.port == self.default_port () {
 
 					hyper_headers.set (
 						HyperHostHeader::new (
-							self.hostname.to_string (),
+							uri_host (& self.hostname),
 							None));
 
 				} else {
 
 					hyper_headers.set (
 						HyperHostHeader::new (
-							self.hostname.to_string (),
+							uri_host (& self.hostname),
 							self.port as u16));
 
 				}
@@ -279,6 +375,13 @@ impl HttpConnection {
 
 		}
 
+		if ! request.body.is_empty () {
+
+			hyper_request.set_body (
+				request.body);
+
+		}
+
 		// perform request
 
 		let request_start_time =
@@ -338,23 +441,8 @@ impl HttpConnection {
 			).collect ();
 
 		let response_encoding =
-			if let Some (response_content_type) =
-				hyper_response.headers ().get::<HyperContentTypeHeader> () {
-
-			if let Some (response_charset) =
-				response_content_type.get_param (
-					"charset",
-				) {
-
-				Some (response_charset.to_string ())
-
-			} else {
-				None
-			}
-
-		} else {
-			None
-		};
+			http_data::content_type_charset (
+				& response_headers);
 
 		// process response body
 
@@ -423,6 +511,10 @@ impl HttpConnection {
 
 	}
 
+	pub fn peer_address (& self) -> Option <SocketAddr> {
+		self.peer_address
+	}
+
 	pub fn connect_duration (& self) -> Duration {
 		self.connect_duration
 	}
@@ -453,4 +545,24 @@ impl HttpConnection {
 
 }
 
+// ipv6 literals must be bracketed when used as the host part of a uri
+
+fn uri_host (
+	address: & str,
+) -> String {
+
+	if address.contains (':') && ! address.starts_with ('[') {
+
+		format! (
+			"[{}]",
+			address)
+
+	} else {
+
+		address.to_string ()
+
+	}
+
+}
+
 // ex: noet ts=4 filetype=rust
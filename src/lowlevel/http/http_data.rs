@@ -1,10 +1,41 @@
+use logic::arg_helper;
+
 use super::http_prelude::*;
 
 #[ derive (Clone, Copy, Debug, PartialEq) ]
 pub enum HttpMethod {
 
 	Get,
+	Head,
 	Post,
+	Put,
+	Delete,
+	Options,
+	Patch,
+
+}
+
+impl arg_helper::EnumArg for HttpMethod {
+
+	fn from_string (
+		string_value: & str,
+	) -> Option <HttpMethod> {
+
+		match string_value {
+
+			"get" => Some (HttpMethod::Get),
+			"head" => Some (HttpMethod::Head),
+			"post" => Some (HttpMethod::Post),
+			"put" => Some (HttpMethod::Put),
+			"delete" => Some (HttpMethod::Delete),
+			"options" => Some (HttpMethod::Options),
+			"patch" => Some (HttpMethod::Patch),
+
+			_ => None,
+
+		}
+
+	}
 
 }
 
@@ -13,6 +44,7 @@ pub struct HttpRequest {
 	pub method: HttpMethod,
 	pub path: String,
 	pub headers: Vec <(String, String)>,
+	pub body: Vec <u8>,
 
 }
 
@@ -34,12 +66,47 @@ pub struct HttpResponse {
 pub enum HttpError {
 	Timeout,
 	InvalidUri,
+	Certificate (String),
 	Unknown (Box <Error>),
 }
 
 pub type HttpResult <Ok> =
 	Result <Ok, HttpError>;
 
+// the charset parameter of the content type, shared by the hyper and unix
+// socket clients
+
+pub (in super) fn content_type_charset (
+	headers: & [(String, String)],
+) -> Option <String> {
+
+	headers.iter ().find (
+		|& & (ref name, _)| name.to_lowercase () == "content-type"
+	).and_then (
+		|& (_, ref content_type)|
+		content_type.split (';').skip (1).filter_map (
+			|parameter| {
+
+			let mut parameter_parts =
+				parameter.splitn (2, '=');
+
+			match (parameter_parts.next (), parameter_parts.next ()) {
+
+				(Some (name), Some (value))
+					if name.trim ().to_lowercase () == "charset" =>
+						Some (
+							value.trim ().trim_matches ('"').to_string ()),
+
+				_ =>
+					None,
+
+			}
+
+		}).next ()
+	)
+
+}
+
 impl HttpResponse {
 
 	pub fn status_code (& self) -> u64 {
@@ -117,6 +184,9 @@ impl Error for HttpError {
 			& HttpError::InvalidUri =>
 				"Invalid URI",
 
+			& HttpError::Certificate (ref reason) =>
+				reason,
+
 			& HttpError::Unknown (ref error) =>
 				error.description (),
 
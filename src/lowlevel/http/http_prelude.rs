@@ -9,6 +9,7 @@ pub use std::io::ErrorKind as IoErrorKind;
 pub use std::io::Read;
 pub use std::io::Result as IoResult;
 pub use std::io::Write;
+pub use std::net::SocketAddr;
 pub use std::str;
 pub use std::sync::Arc;
 pub use std::sync::Mutex;
@@ -40,6 +41,7 @@ pub use hyper::Method as HyperMethod;
 pub use hyper::Uri as HyperUri;
 pub use hyper::client::HttpConnector as HyperHttpConnector;
 pub use hyper::client::Request as HyperRequest;
+pub use hyper::header::ContentLength as HyperContentLengthHeader;
 pub use hyper::header::ContentType as HyperContentTypeHeader;
 pub use hyper::header::Host as HyperHostHeader;
 
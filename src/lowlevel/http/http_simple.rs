@@ -7,11 +7,15 @@ pub struct HttpSimpleRequest <'a> {
 	pub hostname: & 'a str,
 	pub port: u64,
 	pub secure: bool,
+	pub connect_to: Option <(& 'a str, u64)>,
+	pub tls_options: & 'a HttpTlsOptions,
 
 	pub method: HttpMethod,
 	pub path: & 'a str,
 	pub headers: & 'a Vec <(String, String)>,
+	pub body: & 'a [u8],
 
+	pub connect_timeout: Option <Duration>,
 	pub timeout: Duration,
 
 }
@@ -19,19 +23,21 @@ pub struct HttpSimpleRequest <'a> {
 #[ derive (Debug) ]
 pub struct HttpSimpleResponse {
 
-	status_code: u64,
-	status_message: String,
+	pub (in super) status_code: u64,
+	pub (in super) status_message: String,
 
-	headers: Vec <(String, String)>,
+	pub (in super) headers: Vec <(String, String)>,
 
-	body: Vec <u8>,
-	body_encoding: Option <String>,
+	pub (in super) body: Vec <u8>,
+	pub (in super) body_encoding: Option <String>,
 
-	connect_duration: Duration,
-	request_duration: Duration,
-	response_duration: Duration,
+	pub (in super) peer_address: Option <SocketAddr>,
 
-	certificate_expiry: Option <NaiveDateTime>,
+	pub (in super) connect_duration: Duration,
+	pub (in super) request_duration: Duration,
+	pub (in super) response_duration: Duration,
+
+	pub (in super) certificate_expiry: Option <NaiveDateTime>,
 
 }
 
@@ -64,6 +70,10 @@ impl HttpSimpleResponse {
 		& self.body_encoding
 	}
 
+	pub fn peer_address (& self) -> Option <SocketAddr> {
+		self.peer_address
+	}
+
 	pub fn connect_duration (& self) -> Duration {
 		self.connect_duration
 	}
@@ -121,20 +131,21 @@ pub fn http_simple_perform (
 	http_request: & HttpSimpleRequest,
 ) -> HttpResult <HttpSimpleResponse> {
 
-	if http_request.method != HttpMethod::Get {
-
-		return Err (HttpError::InvalidUri);
-
-	}
-
 	// connect
 
+	let (connect_address, connect_port) =
+		http_request.connect_to.unwrap_or (
+			(http_request.address, http_request.port));
+
 	let mut http_connection =
-		HttpConnection::connect (
-			http_request.address.to_string (),
+		HttpConnection::connect_to (
+			connect_address.to_string (),
+			connect_port,
 			Some (http_request.port),
 			http_request.secure,
 			http_request.hostname.to_string (),
+			http_request.tls_options,
+			http_request.connect_timeout,
 		) ?;
 
 	// perform request
@@ -142,9 +153,10 @@ pub fn http_simple_perform (
 	let http_response =
 		http_connection.perform (
 			HttpRequest {
-				method: HttpMethod::Get,
+				method: http_request.method,
 				path: http_request.path.to_string (),
 				headers: http_request.headers.clone (),
+				body: http_request.body.to_vec (),
 			},
 			http_request.timeout,
 		) ?;
@@ -161,6 +173,8 @@ pub fn http_simple_perform (
 		body: http_response.body ().to_vec (),
 		body_encoding: http_response.body_encoding ().clone (),
 
+		peer_address: http_connection.peer_address (),
+
 		connect_duration: http_connection.connect_duration (),
 		request_duration: http_response.request_duration (),
 		response_duration: http_response.response_duration (),
@@ -0,0 +1,652 @@
+use super::http_prelude::*;
+
+use std::cmp;
+use std::fs::File;
+use std::io::BufReader;
+
+use rustls;
+use rustls::internal::pemfile;
+
+use webpki;
+
+// ---------- tls options
+
+#[ derive (Clone, Debug, Default) ]
+pub struct HttpTlsOptions {
+	pub ca_file: Option <String>,
+	pub client_cert: Option <String>,
+	pub client_key: Option <String>,
+	pub insecure: bool,
+}
+
+impl HttpTlsOptions {
+
+	pub fn client_config (
+		& self,
+	) -> HttpResult <RustTlsClientConfig> {
+
+		let mut rust_tls_client_config =
+			RustTlsClientConfig::new ();
+
+		// trust anchors
+
+		if let Some (ref ca_file) = self.ca_file {
+
+			let (num_added, _num_ignored) =
+				rust_tls_client_config.root_store.add_pem_file (
+					& mut open_pem_file (
+						ca_file,
+					) ?,
+				).map_err (
+					|()| tls_options_error (
+						format! (
+							"invalid ca file: {}",
+							ca_file)),
+				) ?;
+
+			if num_added == 0 {
+
+				return Err (
+					tls_options_error (
+						format! (
+							"no certificates in ca file: {}",
+							ca_file)));
+
+			}
+
+		} else {
+
+			rust_tls_client_config.root_store.add_trust_anchors (
+				& webpki_roots::ROOTS,
+			);
+
+		}
+
+		// client certificate
+
+		match (self.client_cert.as_ref (), self.client_key.as_ref ()) {
+
+			(Some (client_cert), Some (client_key)) => {
+
+				let certificates =
+					pemfile::certs (
+						& mut open_pem_file (
+							client_cert,
+						) ?,
+					).map_err (
+						|()| tls_options_error (
+							format! (
+								"invalid client certificate: {}",
+								client_cert)),
+					) ?;
+
+				let private_key =
+					read_private_key (
+						client_key,
+					) ?;
+
+				rust_tls_client_config.set_single_client_cert (
+					certificates,
+					private_key);
+
+			},
+
+			(None, None) =>
+				(),
+
+			_ =>
+				return Err (
+					tls_options_error (
+						"client certificate and key must be specified \
+						together".to_string ())),
+
+		}
+
+		// verification
+
+		if self.insecure {
+
+			rust_tls_client_config.dangerous ().set_certificate_verifier (
+				Box::new (
+					InsecureCertificateVerifier));
+
+		}
+
+		Ok (rust_tls_client_config)
+
+	}
+
+}
+
+// ---------- blocking handshake
+
+// for checks which talk their own protocol before or instead of http, such as
+// check-tls after a starttls exchange
+
+pub struct TlsHandshake {
+	pub duration: Duration,
+	pub protocol_version: String,
+	pub cipher_suite: String,
+	pub peer_certificates: Option <Vec <RustTlsCertificate>>,
+}
+
+pub fn tls_handshake <Stream: Read + Write> (
+	stream: & mut Stream,
+	hostname: & str,
+	tls_options: & HttpTlsOptions,
+) -> HttpResult <TlsHandshake> {
+
+	let rust_tls_client_config =
+		Arc::new (
+			tls_options.client_config () ?);
+
+	let mut tls_session =
+		RustTlsClientSession::new (
+			& rust_tls_client_config,
+			hostname);
+
+	let start_time =
+		Instant::now ();
+
+	let mut received: Vec <u8> =
+		Vec::new ();
+
+	while tls_session.is_handshaking () {
+
+		while tls_session.wants_write () {
+
+			tls_session.write_tls (
+				stream,
+			).map_err (
+				|error| HttpError::Unknown (Box::new (error)),
+			) ?;
+
+		}
+
+		if ! tls_session.is_handshaking () {
+			break;
+		}
+
+		let bytes_read =
+			tls_session.read_tls (
+				& mut HandshakeRecorder {
+					stream: stream,
+					received: & mut received,
+				},
+			).map_err (
+				|error| HttpError::Unknown (Box::new (error)),
+			) ?;
+
+		if bytes_read == 0 {
+
+			return Err (
+				HttpError::Unknown (
+					Box::new (
+						IoError::new (
+							IoErrorKind::UnexpectedEof,
+							"connection closed during handshake"))));
+
+		}
+
+		tls_session.process_new_packets ().map_err (
+			|error|
+			tls_error (
+				IoError::new (
+					IoErrorKind::InvalidData,
+					error)),
+		) ?;
+
+	}
+
+	// the client's finished message may still be queued
+
+	while tls_session.wants_write () {
+
+		tls_session.write_tls (
+			stream,
+		).map_err (
+			|error| HttpError::Unknown (Box::new (error)),
+		) ?;
+
+	}
+
+	let end_time =
+		Instant::now ();
+
+	Ok (TlsHandshake {
+
+		duration:
+			end_time - start_time,
+
+		protocol_version:
+			match tls_session.get_protocol_version () {
+				Some (rustls::ProtocolVersion::SSLv3) => "SSLv3".to_string (),
+				Some (rustls::ProtocolVersion::TLSv1_0) => "TLSv1.0".to_string (),
+				Some (rustls::ProtocolVersion::TLSv1_1) => "TLSv1.1".to_string (),
+				Some (rustls::ProtocolVersion::TLSv1_2) => "TLSv1.2".to_string (),
+				Some (rustls::ProtocolVersion::TLSv1_3) => "TLSv1.3".to_string (),
+				Some (other_version) => format! ("{:?}", other_version),
+				None => "unknown".to_string (),
+			},
+
+		cipher_suite:
+			match server_hello_cipher_suite (& received) {
+				Some (cipher_suite) => cipher_suite_name (cipher_suite),
+				None => "unknown".to_string (),
+			},
+
+		peer_certificates:
+			tls_session.get_peer_certificates (),
+
+	})
+
+}
+
+// ---------- cipher suite
+
+// rustls doesn't tell us which suite it negotiated, so we keep what the server
+// sends during the handshake and read it from the server hello ourselves
+
+const MAX_RECORDED_BYTES: usize = 16 * 1024;
+
+struct HandshakeRecorder <'a> {
+	stream: & 'a mut Read,
+	received: & 'a mut Vec <u8>,
+}
+
+impl <'a> Read for HandshakeRecorder <'a> {
+
+	fn read (
+		& mut self,
+		buffer: & mut [u8],
+	) -> IoResult <usize> {
+
+		let bytes_read =
+			self.stream.read (
+				buffer,
+			) ?;
+
+		let bytes_wanted =
+			cmp::min (
+				bytes_read,
+				MAX_RECORDED_BYTES.saturating_sub (
+					self.received.len ()));
+
+		self.received.extend_from_slice (
+			& buffer [0 .. bytes_wanted]);
+
+		Ok (bytes_read)
+
+	}
+
+}
+
+// the server hello is the first handshake message in the first record. tls
+// 1.3 drafts dropped the session id, so the suite follows the random.
+
+fn server_hello_cipher_suite (
+	received: & [u8],
+) -> Option <u16> {
+
+	// record header, then handshake header
+
+	if received.len () < 9 || received [0] != 22 || received [5] != 2 {
+		return None;
+	}
+
+	let server_hello =
+		& received [9 ..];
+
+	if server_hello.len () < 34 {
+		return None;
+	}
+
+	let version =
+		read_u16 (& server_hello [0 .. 2]);
+
+	let suite_position =
+		if version == 0x0304 || version == 0x7f12 {
+			34
+		} else {
+			35 + server_hello [34] as usize
+		};
+
+	if server_hello.len () < suite_position + 2 {
+		return None;
+	}
+
+	Some (
+		read_u16 (
+			& server_hello [suite_position .. suite_position + 2]))
+
+}
+
+fn read_u16 (
+	bytes: & [u8],
+) -> u16 {
+
+	(bytes [0] as u16) << 8 | bytes [1] as u16
+
+}
+
+fn cipher_suite_name (
+	cipher_suite: u16,
+) -> String {
+
+	rustls::ALL_CIPHERSUITES.iter ().find (
+		|supported_suite|
+		supported_suite.suite.get_u16 () == cipher_suite
+	).map (
+		|supported_suite|
+		format! ("{:?}", supported_suite.suite)
+	).unwrap_or_else (
+		||
+		format! ("0x{:04X}", cipher_suite)
+	)
+
+}
+
+// ---------- insecure mode
+
+struct InsecureCertificateVerifier;
+
+impl rustls::ServerCertVerifier for InsecureCertificateVerifier {
+
+	fn verify_server_cert (
+		& self,
+		_roots: & rustls::RootCertStore,
+		_presented_certs: & [RustTlsCertificate],
+		_dns_name: & str,
+	) -> Result <(), rustls::TLSError> {
+
+		Ok (())
+
+	}
+
+}
+
+// ---------- verification errors
+
+// the tls error ends up wrapped in one or more io errors by the time the
+// connection future fails, so we unwrap them to find the reason
+
+pub fn tls_error (
+	error: IoError,
+) -> HttpError {
+
+	match certificate_error_reason (& error) {
+
+		Some (reason) =>
+			HttpError::Certificate (reason),
+
+		None =>
+			HttpError::Unknown (
+				Box::new (error)),
+
+	}
+
+}
+
+fn certificate_error_reason (
+	error: & (Error + 'static),
+) -> Option <String> {
+
+	if let Some (tls_error) =
+		error.downcast_ref::<rustls::TLSError> () {
+
+		return match * tls_error {
+
+			rustls::TLSError::WebPKIError (ref webpki_error) =>
+				Some (
+					webpki_error_reason (
+						webpki_error)),
+
+			rustls::TLSError::NoCertificatesPresented =>
+				Some (
+					"no certificate presented".to_string ()),
+
+			_ =>
+				None,
+
+		};
+
+	}
+
+	if let Some (io_error) =
+		error.downcast_ref::<IoError> () {
+
+		if let Some (inner_error) = io_error.get_ref () {
+
+			return certificate_error_reason (
+				inner_error);
+
+		}
+
+	}
+
+	None
+
+}
+
+fn webpki_error_reason (
+	webpki_error: & webpki::Error,
+) -> String {
+
+	match * webpki_error {
+
+		webpki::Error::UnknownIssuer =>
+			"untrusted issuer".to_string (),
+
+		webpki::Error::CertNotValidForName =>
+			"hostname mismatch".to_string (),
+
+		webpki::Error::CertExpired =>
+			"expired".to_string (),
+
+		webpki::Error::CertNotValidYet =>
+			"not yet valid".to_string (),
+
+		ref other_error =>
+			format! (
+				"invalid certificate ({:?})",
+				other_error),
+
+	}
+
+}
+
+// ---------- pem files
+
+fn open_pem_file (
+	path: & str,
+) -> HttpResult <BufReader <File>> {
+
+	Ok (
+		BufReader::new (
+			File::open (
+				path,
+			).map_err (
+				|error| tls_options_error (
+					format! (
+						"unable to open {}: {}",
+						path,
+						error)),
+			) ?,
+		)
+	)
+
+}
+
+fn read_private_key (
+	path: & str,
+) -> HttpResult <rustls::PrivateKey> {
+
+	let mut private_keys =
+		pemfile::pkcs8_private_keys (
+			& mut open_pem_file (
+				path,
+			) ?,
+		).unwrap_or_default ();
+
+	if private_keys.is_empty () {
+
+		private_keys =
+			pemfile::rsa_private_keys (
+				& mut open_pem_file (
+					path,
+				) ?,
+			).unwrap_or_default ();
+
+	}
+
+	if private_keys.is_empty () {
+
+		return Err (
+			tls_options_error (
+				format! (
+					"no private key in {}",
+					path)));
+
+	}
+
+	Ok (
+		private_keys.remove (0)
+	)
+
+}
+
+fn tls_options_error (
+	message: String,
+) -> HttpError {
+
+	HttpError::Unknown (
+		Box::new (
+			IoError::new (
+				IoErrorKind::InvalidInput,
+				message)))
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use rustls;
+	use webpki;
+
+	use super::super::http_prelude::*;
+	use super::cipher_suite_name;
+	use super::server_hello_cipher_suite;
+	use super::tls_error;
+	use super::webpki_error_reason;
+
+	#[ test ]
+	fn webpki_error_reasons () {
+
+		for & (ref webpki_error, expected_reason) in [
+			(webpki::Error::UnknownIssuer, "untrusted issuer"),
+			(webpki::Error::CertNotValidForName, "hostname mismatch"),
+			(webpki::Error::CertExpired, "expired"),
+			(webpki::Error::CertNotValidYet, "not yet valid"),
+		].iter () {
+
+			assert_eq! (
+				webpki_error_reason (webpki_error),
+				expected_reason);
+
+		}
+
+	}
+
+	#[ test ]
+	fn wrapped_tls_errors () {
+
+		let error =
+			IoError::new (
+				IoErrorKind::Other,
+				IoError::new (
+					IoErrorKind::Other,
+					rustls::TLSError::WebPKIError (
+						webpki::Error::CertNotValidForName)));
+
+		match tls_error (error) {
+			HttpError::Certificate (reason) =>
+				assert_eq! (reason, "hostname mismatch"),
+			_ => panic! ("not a certificate error"),
+		}
+
+		let error =
+			IoError::new (
+				IoErrorKind::ConnectionRefused,
+				"connection refused");
+
+		match tls_error (error) {
+			HttpError::Unknown (_) => (),
+			_ => panic! ("not an unknown error"),
+		}
+
+	}
+
+	fn server_hello (
+		version: & [u8],
+		rest: & [u8],
+	) -> Vec <u8> {
+
+		let mut received: Vec <u8> =
+			vec! [ 22, 3, 3, 0, 0, 2, 0, 0, 0 ];
+
+		received.extend_from_slice (version);
+		received.extend_from_slice (& [0; 32]);
+		received.extend_from_slice (rest);
+
+		received
+
+	}
+
+	#[ test ]
+	fn server_hello_cipher_suites () {
+
+		let mut tls_1_2_rest: Vec <u8> =
+			vec! [ 32 ];
+
+		tls_1_2_rest.extend_from_slice (& [0; 32]);
+		tls_1_2_rest.extend_from_slice (& [ 0xc0, 0x2f, 0 ]);
+
+		assert_eq! (
+			server_hello_cipher_suite (
+				& server_hello (& [ 3, 3 ], & tls_1_2_rest)),
+			Some (0xc02f));
+
+		assert_eq! (
+			server_hello_cipher_suite (
+				& server_hello (& [ 0x7f, 0x12 ], & [ 0x13, 0x01 ])),
+			Some (0x1301));
+
+		// truncated in the session id, and not a handshake at all
+
+		assert_eq! (
+			server_hello_cipher_suite (
+				& server_hello (& [ 3, 3 ], & tls_1_2_rest [0 .. 20])),
+			None);
+
+		assert_eq! (
+			server_hello_cipher_suite (
+				& [ 21, 3, 3, 0, 2, 2, 40 ]),
+			None);
+
+		assert_eq! (
+			cipher_suite_name (0xc02f),
+			"TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256");
+
+		assert_eq! (
+			cipher_suite_name (0x1301),
+			"TLS13_AES_128_GCM_SHA256");
+
+		assert_eq! (
+			cipher_suite_name (0x000a),
+			"0x000A");
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,527 @@
+use super::http_prelude::*;
+use super::http_data;
+
+use std::os::unix::net::UnixStream;
+
+// a minimal client for http servers listening on a unix socket, which the
+// hyper connector can't reach. we speak http/1.0 so that the server closes
+// the connection after the response and never sends a chunked body.
+
+pub fn http_unix_perform (
+	socket_path: & str,
+	http_request: & HttpSimpleRequest,
+) -> HttpResult <HttpSimpleResponse> {
+
+	// connect
+
+	let connect_start_time =
+		Instant::now ();
+
+	let mut unix_stream =
+		UnixStream::connect (
+			socket_path,
+		).map_err (
+			|error|
+			HttpError::Unknown (
+				Box::new (
+					IoError::new (
+						error.kind (),
+						format! (
+							"unable to connect to {}: {}",
+							socket_path,
+							error)))),
+		) ?;
+
+	unix_stream.set_write_timeout (
+		Some (http_request.timeout),
+	).map_err (io_error) ?;
+
+	let request_start_time =
+		Instant::now ();
+
+	// the timeout covers the whole exchange, so a server which trickles its
+	// response can't keep resetting it
+
+	let deadline =
+		request_start_time + http_request.timeout;
+
+	// send request
+
+	unix_stream.write_all (
+		& request_bytes (
+			http_request),
+	).map_err (io_error) ?;
+
+	// read response head
+
+	let mut response_bytes: Vec <u8> =
+		Vec::new ();
+
+	let mut buffer =
+		[0u8; 4096];
+
+	let head_length =
+		loop {
+
+		if let Some (position) =
+			response_bytes.windows (4).position (
+				|window| window == b"\r\n\r\n") {
+
+			break position + 4;
+
+		}
+
+		let bytes_read =
+			read_before_deadline (
+				& mut unix_stream,
+				& mut buffer,
+				deadline,
+			) ?;
+
+		if bytes_read == 0 {
+
+			return Err (
+				HttpError::Unknown (
+					Box::new (
+						IoError::new (
+							IoErrorKind::UnexpectedEof,
+							"connection closed before response headers"))));
+
+		}
+
+		response_bytes.extend_from_slice (
+			& buffer [0 .. bytes_read]);
+
+	};
+
+	let response_start_time =
+		Instant::now ();
+
+	// read response body
+
+	loop {
+
+		let bytes_read =
+			read_before_deadline (
+				& mut unix_stream,
+				& mut buffer,
+				deadline,
+			) ?;
+
+		if bytes_read == 0 {
+			break;
+		}
+
+		response_bytes.extend_from_slice (
+			& buffer [0 .. bytes_read]);
+
+	}
+
+	let response_end_time =
+		Instant::now ();
+
+	// parse response
+
+	let head =
+		String::from_utf8_lossy (
+			& response_bytes [0 .. head_length],
+		).into_owned ();
+
+	let mut head_lines =
+		head.split ("\r\n");
+
+	let status_line =
+		head_lines.next ().unwrap_or ("");
+
+	let mut status_parts =
+		status_line.splitn (3, ' ');
+
+	let status_code: u64 =
+		match (status_parts.next (), status_parts.next ()) {
+
+		(Some (version), Some (status_code))
+			if version.starts_with ("HTTP/") =>
+				status_code.parse ().map_err (
+					|_| invalid_response (status_line),
+				) ?,
+
+		_ =>
+			return Err (
+				invalid_response (
+					status_line)),
+
+	};
+
+	let status_message =
+		status_parts.next ().unwrap_or ("").to_string ();
+
+	let headers: Vec <(String, String)> =
+		head_lines.filter (
+			|header_line| ! header_line.is_empty ()
+		).filter_map (
+			|header_line| {
+
+			let mut header_parts =
+				header_line.splitn (2, ':');
+
+			match (header_parts.next (), header_parts.next ()) {
+
+				(Some (name), Some (value)) =>
+					Some ((
+						name.trim ().to_string (),
+						value.trim ().to_string (),
+					)),
+
+				_ =>
+					None,
+
+			}
+
+		}).collect ();
+
+	let body_encoding =
+		http_data::content_type_charset (
+			& headers);
+
+	Ok (HttpSimpleResponse {
+
+		status_code: status_code,
+		status_message: status_message,
+
+		headers: headers,
+
+		body: response_bytes.split_off (head_length),
+		body_encoding: body_encoding,
+
+		peer_address: None,
+
+		connect_duration: request_start_time - connect_start_time,
+		request_duration: response_start_time - request_start_time,
+		response_duration: response_end_time - response_start_time,
+
+		certificate_expiry: None,
+
+	})
+
+}
+
+fn request_bytes (
+	http_request: & HttpSimpleRequest,
+) -> Vec <u8> {
+
+	let method =
+		match http_request.method {
+			HttpMethod::Get => "GET",
+			HttpMethod::Head => "HEAD",
+			HttpMethod::Post => "POST",
+			HttpMethod::Put => "PUT",
+			HttpMethod::Delete => "DELETE",
+			HttpMethod::Options => "OPTIONS",
+			HttpMethod::Patch => "PATCH",
+		};
+
+	let mut request_string =
+		format! (
+			"{} {} HTTP/1.0\r\n",
+			method,
+			http_request.path);
+
+	let has_header = |header_name: & str|
+		http_request.headers.iter ().any (
+			|& (ref name, _)| name.to_lowercase () == header_name);
+
+	if ! has_header ("host") {
+
+		request_string.push_str (
+			& format! (
+				"Host: {}\r\n",
+				http_request.hostname));
+
+	}
+
+	if ! has_header ("content-length")
+		&& ! http_request.body.is_empty () {
+
+		request_string.push_str (
+			& format! (
+				"Content-Length: {}\r\n",
+				http_request.body.len ()));
+
+	}
+
+	for & (ref name, ref value) in http_request.headers.iter () {
+
+		request_string.push_str (
+			& format! (
+				"{}: {}\r\n",
+				name,
+				value));
+
+	}
+
+	request_string.push_str (
+		"\r\n");
+
+	let mut request_bytes =
+		request_string.into_bytes ();
+
+	request_bytes.extend_from_slice (
+		http_request.body);
+
+	request_bytes
+
+}
+
+fn read_before_deadline (
+	unix_stream: & mut UnixStream,
+	buffer: & mut [u8],
+	deadline: Instant,
+) -> HttpResult <usize> {
+
+	let now =
+		Instant::now ();
+
+	if now >= deadline {
+		return Err (HttpError::Timeout);
+	}
+
+	unix_stream.set_read_timeout (
+		Some (deadline - now),
+	).map_err (io_error) ?;
+
+	unix_stream.read (
+		buffer,
+	).map_err (io_error)
+
+}
+
+fn io_error (
+	error: IoError,
+) -> HttpError {
+
+	match error.kind () {
+
+		IoErrorKind::WouldBlock | IoErrorKind::TimedOut =>
+			HttpError::Timeout,
+
+		_ =>
+			HttpError::Unknown (
+				Box::new (error)),
+
+	}
+
+}
+
+fn invalid_response (
+	status_line: & str,
+) -> HttpError {
+
+	HttpError::Unknown (
+		Box::new (
+			IoError::new (
+				IoErrorKind::InvalidData,
+				format! (
+					"invalid response status line: {}",
+					status_line))))
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::env;
+	use std::error::Error;
+	use std::fs;
+	use std::io::Read;
+	use std::io::Write;
+	use std::os::unix::net::UnixListener;
+	use std::path::Path;
+	use std::path::PathBuf;
+	use std::thread;
+	use std::time::Duration;
+	use std::time::Instant;
+	use std::time::SystemTime;
+	use std::time::UNIX_EPOCH;
+
+	use super::super::*;
+
+	fn socket_path (
+	) -> PathBuf {
+
+		env::temp_dir ().join (
+			format! (
+				"wbs-monitoring-test-{}.sock",
+				SystemTime::now ().duration_since (
+					UNIX_EPOCH,
+				).unwrap ().subsec_nanos ()))
+
+	}
+
+	fn get_status (
+		socket_path: & Path,
+		timeout: Duration,
+	) -> HttpResult <HttpSimpleResponse> {
+
+		let headers =
+			vec! [
+				("Authorization".to_string (), "Bearer secret".to_string ()),
+			];
+
+		http_unix_perform (
+			socket_path.to_str ().unwrap (),
+			& HttpSimpleRequest {
+				address: "localhost",
+				hostname: "localhost",
+				port: 80,
+				secure: false,
+				connect_to: None,
+				tls_options: & HttpTlsOptions::default (),
+				method: HttpMethod::Get,
+				path: "/status",
+				headers: & headers,
+				body: & [],
+				connect_timeout: None,
+				timeout: timeout,
+			})
+
+	}
+
+	#[ test ]
+	fn request_over_unix_socket () {
+
+		let socket_path =
+			socket_path ();
+
+		let listener =
+			UnixListener::bind (
+				& socket_path,
+			).unwrap ();
+
+		let server_thread =
+			thread::spawn (
+				move || {
+
+			let (mut stream, _) =
+				listener.accept ().unwrap ();
+
+			let mut request =
+				vec! [0u8; 4096];
+
+			let bytes_read =
+				stream.read (
+					& mut request,
+				).unwrap ();
+
+			stream.write_all (
+				b"HTTP/1.0 200 OK\r\n\
+				Content-Type: application/json; charset=utf-8\r\n\
+				\r\n\
+				{\"status\": \"ok\"}",
+			).unwrap ();
+
+			String::from_utf8 (
+				request [0 .. bytes_read].to_vec (),
+			).unwrap ()
+
+		});
+
+		let http_response =
+			get_status (
+				& socket_path,
+				Duration::new (10, 0),
+			).unwrap_or_else (
+				|error| panic! ("{}", error.description ()));
+
+		let request =
+			server_thread.join ().unwrap ();
+
+		fs::remove_file (
+			& socket_path,
+		).unwrap ();
+
+		assert_eq! (
+			request,
+			"GET /status HTTP/1.0\r\n\
+			Host: localhost\r\n\
+			Authorization: Bearer secret\r\n\
+			\r\n");
+
+		assert_eq! (
+			http_response.status_code (),
+			200);
+
+		assert_eq! (
+			http_response.body_string ().unwrap (),
+			"{\"status\": \"ok\"}");
+
+		assert_eq! (
+			http_response.body_encoding (),
+			& Some ("utf-8".to_string ()));
+
+	}
+
+	#[ test ]
+	fn slow_response_times_out () {
+
+		let socket_path =
+			socket_path ();
+
+		let listener =
+			UnixListener::bind (
+				& socket_path,
+			).unwrap ();
+
+		// each byte arrives well within the timeout, but the whole response
+		// takes far longer
+
+		thread::spawn (
+			move || {
+
+			let (mut stream, _) =
+				listener.accept ().unwrap ();
+
+			let mut request =
+				vec! [0u8; 4096];
+
+			stream.read (
+				& mut request,
+			).unwrap ();
+
+			for byte in b"HTTP/1.0 200 OK\r\n\r\n{\"status\": \"ok\"}".iter () {
+
+				if stream.write_all (& [* byte]).is_err () {
+					return;
+				}
+
+				thread::sleep (
+					Duration::from_millis (200));
+
+			}
+
+		});
+
+		let start_time =
+			Instant::now ();
+
+		let result =
+			get_status (
+				& socket_path,
+				Duration::new (1, 0));
+
+		fs::remove_file (
+			& socket_path,
+		).unwrap ();
+
+		match result {
+			Err (HttpError::Timeout) => (),
+			_ => panic! ("expected a timeout"),
+		}
+
+		assert! (
+			start_time.elapsed () < Duration::new (3, 0));
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -6,8 +6,13 @@ mod http_perform;
 mod http_prelude;
 mod http_simple;
 mod http_stream;
+mod http_tls;
+mod http_unix;
 
+pub use self::http_certificate::CertificateInfo;
 pub use self::http_certificate::get_certificate_validity;
+pub use self::http_certificate::inspect_certificate;
+pub use self::http_certificate::inspect_certificate_chain;
 
 pub use self::http_connection::HttpConnection;
 
@@ -28,4 +33,10 @@ pub use self::http_stream::HttpBorrowedStream;
 pub use self::http_stream::HttpSharedStream;
 pub use self::http_stream::HttpStream;
 
+pub use self::http_tls::HttpTlsOptions;
+pub use self::http_tls::TlsHandshake;
+pub use self::http_tls::tls_handshake;
+
+pub use self::http_unix::http_unix_perform;
+
 // ex: noet ts=4 filetype=rust
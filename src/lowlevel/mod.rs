@@ -1,3 +1,4 @@
+pub mod apt;
 pub mod file;
 pub mod http;
 
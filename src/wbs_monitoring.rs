@@ -0,0 +1,13 @@
+extern crate wbsmonitoring;
+
+use wbsmonitoring::checks;
+use wbsmonitoring::logic;
+
+fn main () {
+
+	logic::run_multi_call (
+		checks::all_plugin_providers);
+
+}
+
+// ex: noet ts=4 filetype=rust